@@ -3,7 +3,7 @@ use proc_macro2::{Span, TokenStream};
 use quote::{quote, quote_spanned, ToTokens};
 use syn::spanned::Spanned;
 
-use super::parse::Input;
+use super::parse::{Attribute, Input, Uniform, UniformKind};
 
 pub fn gen_code(input: &Input) -> TokenStream {
     let imp = gen_program_impl(input);
@@ -20,107 +20,203 @@ fn gen_program_impl(input: &Input) -> TokenStream {
     let data_field = &input.program_data;
     let vert_code = &input.vertex_source;
     let frag_code = &input.fragment_source;
+    let glsl_version = option_tokens(input.glsl_version.as_ref());
+    let precision = option_tokens(input.precision.as_ref());
+    let backend = backend_type(input);
 
     let init_attrs = input.attributes.iter().map(|attr| {
         let name = &attr.field;
         let init_expr = quote!(::willow::Attribute::create_from_macro());
         quote!(#name: #init_expr)
     });
-    let init_uniforms = input.uniforms.iter().map(|attr| {
-        let name = &attr.field;
-        let init_expr = quote!(::willow::Uniform::create_from_macro());
+    let init_uniforms = input
+        .uniforms
+        .iter()
+        .filter(|unif| unif.member.is_none())
+        .map(|unif| {
+            let name = &unif.field;
+            let init_expr = quote!(::willow::Uniform::create_from_macro());
+            quote!(#name: #init_expr)
+        });
+    let init_sampler_units = input.sampler_units.iter().map(|sampler| {
+        let name = &sampler.field;
+        quote!(#name: ::willow::Uniform::create_from_macro())
+    });
+    let init_uniform_structs = input.uniform_structs.iter().map(|(name, members)| {
+        let init_expr = quote!(::willow::UniformStruct::create_from_macro(#members));
         quote!(#name: #init_expr)
     });
 
+    let check_uniforms = input
+        .uniforms
+        .iter()
+        .filter(|unif| unif.kind == UniformKind::Value)
+        .map(|unif| {
+            let ty = unif.array.as_ref().map_or(&unif.ty, |(elem, _)| elem);
+            let gl_name = &unif.gl;
+            quote!(::willow::check_uniform_support::<#ty, _>(context, #gl_name)?;)
+        });
+
     let create_internally = quote! {
-        fn create_internally(context: &::willow::Context) -> Self {
-            let gl = &context.native;
-
-            let program = gl.create_program().expect("Cannot initialize program");
-            let vertex_shader = gl.create_shader(::willow::WebGlRenderingContext::VERTEX_SHADER).expect("Cannot initialize vertex shader");
-            let fragment_shader = gl.create_shader(::willow::WebGlRenderingContext::FRAGMENT_SHADER).expect("Cannot initialize fragment shader");
-
-            Self {
-                #data_field: ProgramData {
-                    program,
-                    vertex_shader,
-                    fragment_shader,
-                },
+        fn create_internally(context: &#backend) -> ::willow::Result<Self> {
+            #(#check_uniforms)*
+            Ok(Self {
+                #data_field: ::willow::ProgramData::create_from_macro(context)?,
                 #(#init_attrs,)*
                 #(#init_uniforms,)*
-            }
+                #(#init_sampler_units,)*
+                #(#init_uniform_structs,)*
+            })
         }
     };
 
     let compile_shaders = quote! {
-        fn compile_shaders(&self, context: &::willow::Context) {
-            let gl = &context.native;
-
-            gl.shader_source(&self.#data_field.vertex_shader, #vert_code);
-            gl.compile_shader(&self.#data_field.vertex_shader);
-
-            gl.shader_source(&self.#data_field.fragment_shader, #frag_code);
-            gl.compile_shader(&self.#data_field.fragment_shader);
-
-            #[cfg(debug_assertions)]
-            {
-                for (debug_name, shader) in &[("vertex shader", &self.#data_field.vertex_shader), ("fragment shader", &self.#data_field.fragment_shader)] {
-                    let value = gl.get_shader_parameter(shader, ::willow::WebGlRenderingContext::COMPILE_STATUS);
-                    if !value.is_truthy() {
-                        let log = gl.get_shader_info_log(shader);
-                        panic!("Error compiling {} of {}: {}", debug_name, stringify!(#ident), log.unwrap_or_default());
-                    }
-                }
-            }
+        fn compile_shaders(&self, context: &#backend) -> ::willow::Result<()> {
+            let vert_code = ::willow::shader_with_header(#vert_code, #glsl_version, None)?;
+            let frag_code = ::willow::shader_with_header(#frag_code, #glsl_version, #precision)?;
+            self.#data_field.compile_from_macro(context, stringify!(#ident), &vert_code, &frag_code);
+            Ok(())
         }
     };
 
-    let link_shaders = quote! {
-        fn link_shaders(&self, context: &::willow::Context) {
-            let gl = &context.native;
-
-            gl.attach_shader(&self.#data_field.program, &self.#data_field.vertex_shader);
-            gl.attach_shader(&self.#data_field.program, &self.#data_field.fragment_shader);
-            gl.link_program(&self.#data_field.program);
-
-            #[cfg(debug_assertions)]
-            {
-                let value = gl.get_program_parameter(&self.#data_field.program, ::willow::WebGlRenderingContext::LINK_STATUS);
-                if !value.is_truthy() {
-                    let log = gl.get_program_info_log(&self.#data_field.program);
-                    panic!("Error linking {}: {}", stringify!(#ident), log.unwrap_or_default());
+    let (assign_sampler_units, assign_sampler_units_call) = if input.sampler_units.is_empty() {
+        (quote!(), quote!())
+    } else {
+        let fields = input.sampler_units.iter().map(|sampler| &sampler.field);
+        let gl_names = input.sampler_units.iter().map(|sampler| &sampler.gl);
+        let units = input
+            .sampler_units
+            .iter()
+            .map(|sampler| sampler.unit as i32);
+        (
+            quote! {
+                /// Assigns the fixed texture units declared with `#[willow(sampler_unit = ...)]`.
+                ///
+                /// Returns an error if a unit exceeds `MAX_COMBINED_TEXTURE_IMAGE_UNITS`.
+                fn assign_sampler_units(&self, context: &#backend) -> ::willow::Result<()> {
+                    let max_units = ::willow::Backend::limits(context).max_combined_texture_image_units;
+                    ::willow::Program::use_program(self, context);
+                    #(
+                        if #units as u32 >= max_units {
+                            ::willow::anyhow::bail!(
+                                "Cannot assign texture unit {} to the uniform \"{}\" (MAX_COMBINED_TEXTURE_IMAGE_UNITS = {})",
+                                #units,
+                                #gl_names,
+                                max_units,
+                            );
+                        }
+                        match self.#fields.get_location(context, &self.#data_field, #gl_names) {
+                            Some(location) => ::willow::UniformType::apply_uniform(#units, context, location),
+                            None => ::willow::log::warn!("Could not retrieve uniform location with name \"{}\"", #gl_names),
+                        }
+                    )*
+                    Ok(())
                 }
-            }
+            },
+            quote!(self.assign_sampler_units(context)?;),
+        )
+    };
+
+    let link_shaders = quote! {
+        fn link_shaders(&self, context: &#backend) -> ::willow::Result<()> {
+            self.#data_field.link_from_macro(context, stringify!(#ident));
+            #assign_sampler_units_call
+            Ok(())
         }
     };
 
-    let attr_fields = input.attributes.iter().map(|attr| &attr.field);
-    let attr_names = input.attributes.iter().map(|attr| &attr.gl);
-    let field_index = 0..input.attributes.len();
-    let apply_attrs = quote! {
-        fn apply_attrs(&self, context: &::willow::Context, buffer: &::willow::Buffer<Self::AttrStruct>) {
-            let gl = &context.native;
+    let (instance_attrs, vertex_attrs): (Vec<_>, Vec<_>) = input
+        .attributes
+        .iter()
+        .partition(|attr| attr.divisor.is_some());
 
-            gl.bind_buffer(::willow::WebGlRenderingContext::ARRAY_BUFFER, Some(&buffer.buf));
+    let attr_fields = vertex_attrs.iter().map(|attr| &attr.field);
+    let attr_names = vertex_attrs.iter().map(|attr| &attr.gl);
+    let field_index = 0..vertex_attrs.len();
+    let apply_attrs = quote! {
+        fn apply_attrs(&self, context: &#backend, buffer: &::willow::Buffer<Self::AttrStruct, #backend>) {
+            ::willow::Backend::bind_buffer(context, ::willow::WebGlRenderingContext::ARRAY_BUFFER, Some(&buffer.buf));
 
             #(
-                let location = self.#attr_fields.get_location(context, &self.#data_field, #attr_names);
-                buffer.bind_to_attr(context, location, #field_index);
+                if let Some(location) = self.#attr_fields.get_location(context, &self.#data_field, #attr_names) {
+                    buffer.bind_to_attr(context, location, #field_index);
+                }
             )*
         }
     };
 
+    let instance_methods = if instance_attrs.is_empty() {
+        quote!()
+    } else {
+        let instance_ident = &input.instance_ident;
+        let fields: Vec<_> = instance_attrs.iter().map(|attr| &attr.field).collect();
+        let gl_names: Vec<_> = instance_attrs.iter().map(|attr| &attr.gl).collect();
+        let divisors = instance_attrs.iter().map(|attr| attr.divisor.unwrap_or(1));
+        let field_index: Vec<_> = (0..instance_attrs.len()).collect();
+        quote! {
+            /// Prepares a buffer with the `#[willow(instanced)]` attributes of each instance.
+            ///
+            /// The instance buffer is separate from the vertex buffer,
+            /// so it has its own `usage`, e.g. `DynamicDraw` for instances updated every frame
+            /// while the vertices are `StaticDraw`.
+            ///
+            /// Returns an error in the same cases as [`Buffer::from_slice`][::willow::Buffer::from_slice].
+            #vis fn prepare_instance_buffer(context: &#backend, instances: &[#instance_ident], usage: ::willow::BufferDataUsage) -> ::std::result::Result<::willow::Buffer<#instance_ident, #backend>, ::willow::DrawError> {
+                ::willow::Buffer::from_slice(context, instances, usage)
+            }
+
+            /// Binds the `#[willow(instanced)]` attributes to `buffer`,
+            /// advancing them once per instance or once every `instanced = N` instances.
+            fn bind_instances(&self, context: &#backend, buffer: &::willow::Buffer<#instance_ident, #backend>) -> ::std::result::Result<(), ::willow::DrawError> {
+                ::willow::Backend::bind_buffer(context, ::willow::WebGlRenderingContext::ARRAY_BUFFER, Some(&buffer.buf));
+
+                #(
+                    if let Some(location) = self.#fields.get_location(context, &self.#data_field, #gl_names) {
+                        buffer.bind_to_attr(context, location, #field_index);
+                        for column in 0..<#instance_ident as ::willow::AttrStruct>::field_num_columns(#field_index) {
+                            ::willow::Backend::vertex_attrib_divisor(context, location + column as u32, #divisors)?;
+                        }
+                    }
+                )*
+                Ok(())
+            }
+
+            /// Restores the divisors of the `#[willow(instanced)]` attributes to 0,
+            /// so that other programs using the same locations read them per vertex.
+            fn unbind_instances(&self, context: &#backend) {
+                #(
+                    if let Some(location) = self.#fields.get_location(context, &self.#data_field, #gl_names) {
+                        for column in 0..<#instance_ident as ::willow::AttrStruct>::field_num_columns(#field_index) {
+                            let _ = ::willow::Backend::vertex_attrib_divisor(context, location + column as u32, 0);
+                        }
+                    }
+                )*
+            }
+        }
+    };
+
     let use_program = quote! {
-        fn use_program(&self, gl: &::willow::Context) {
-            gl.native.use_program(Some(&self.#data_field.program));
+        fn use_program(&self, context: &#backend) {
+            ::willow::__trace!("useProgram {}", stringify!(#ident));
+            ::willow::Backend::use_program(context, Some(&self.#data_field.program));
         }
     };
 
     let empty_generics = input.uniforms.iter().map(|_| quote!(()));
-    let filled_generics = input
-        .uniforms
-        .iter()
-        .map(|uniform| uniform.ty.to_token_stream());
+    let default_generics = input.uniforms.iter().map(|uniform| {
+        if uniform.kind.is_reference() {
+            quote!(())
+        } else {
+            uniform.ty.to_token_stream()
+        }
+    });
+    let default_values = input.uniforms.iter().map(|uniform| {
+        if uniform.kind.is_reference() {
+            quote!(())
+        } else {
+            quote!(Default::default())
+        }
+    });
     let uniform_names: Vec<_> = input
         .uniforms
         .iter()
@@ -131,27 +227,165 @@ fn gen_program_impl(input: &Input) -> TokenStream {
         #vis fn with_uniforms<'program>(&'program self) -> #builder_ident<'program, #(#empty_generics),*> {
             #builder_ident {
                 program: self,
+                ignore_missing_uniforms: false,
                 #(#uniform_names: ()),*
             }
         }
 
         /// Creates a builder type with all uniforms assigned to the
         /// [`Default`][std::default::Default] value.
-        #vis fn with_default_uniforms<'program>(&'program self) -> #builder_ident<'program, #(#filled_generics),*> {
+        ///
+        /// The default values can be replaced with the same setters as in `with_uniforms`.
+        /// Texture and `MatrixArray` uniforms have no default value and must still be assigned.
+        #vis fn with_default_uniforms<'program>(&'program self) -> #builder_ident<'program, #(#default_generics),*> {
             #builder_ident {
                 program: self,
-                #(#uniform_names: Default::default()),*
+                ignore_missing_uniforms: false,
+                #(#uniform_names: #default_values),*
             }
         }
     };
 
+    let reset_fields = input
+        .attributes
+        .iter()
+        .map(|attr| &attr.field)
+        .chain(
+            input
+                .uniforms
+                .iter()
+                .filter(|unif| unif.member.is_none())
+                .map(|unif| &unif.field),
+        )
+        .chain(input.sampler_units.iter().map(|sampler| &sampler.field))
+        .chain(input.uniform_structs.iter().map(|(name, _)| name));
+    let resolve_attrs = input.attributes.iter().map(|attr| {
+        let field = &attr.field;
+        let gl_name = &attr.gl;
+        quote! {
+            if self.#field.get_location(context, &self.#data_field, #gl_name).is_none() {
+                missing.push(#gl_name);
+            }
+        }
+    });
+    let resolve_uniforms = input.uniforms.iter().map(|uniform| {
+        let gl_name = &uniform.gl;
+        let get_location = match &uniform.member {
+            None => {
+                let field = &uniform.field;
+                quote!(self.#field.get_location(context, &self.#data_field, #gl_name))
+            }
+            Some((struct_field, index)) => {
+                quote!(self.#struct_field.get_location(context, &self.#data_field, #index, #gl_name))
+            }
+        };
+        quote! {
+            if #get_location.is_none() {
+                missing.push(#gl_name);
+            }
+        }
+    });
+    let resolve_sampler_units = input.sampler_units.iter().map(|sampler| {
+        let field = &sampler.field;
+        let gl_name = &sampler.gl;
+        quote! {
+            if self.#field.get_location(context, &self.#data_field, #gl_name).is_none() {
+                missing.push(#gl_name);
+            }
+        }
+    });
+    let resolve_locations = quote! {
+        /// Resolves the locations of all attributes and uniforms immediately,
+        /// instead of lazily when they are first used.
+        ///
+        /// Returns an error listing the GLSL names without a location,
+        /// e.g. because of a typo or because the GLSL compiler optimized out an unused variable.
+        /// Call this after creating the program to detect such problems at startup.
+        #vis fn resolve_locations(&self, context: &#backend) -> ::willow::Result<()> {
+            let mut missing: ::std::vec::Vec<&str> = ::std::vec::Vec::new();
+            #(#resolve_attrs)*
+            #(#resolve_uniforms)*
+            #(#resolve_sampler_units)*
+            if !missing.is_empty() {
+                ::willow::anyhow::bail!(
+                    "Could not retrieve the locations of {} in {}",
+                    missing.join(", "),
+                    stringify!(#ident),
+                );
+            }
+            Ok(())
+        }
+    };
+
+    let reload = quote! {
+        /// Replaces the shader sources, then recompiles and relinks the program.
+        ///
+        /// The `#version` and `precision` directives specified in the struct attributes
+        /// are prepended to the new sources.
+        /// The cached attribute and uniform locations are reset even if compilation fails.
+        #vis fn reload(&mut self, context: &#backend, vert: &str, frag: &str) -> ::willow::Result<()> {
+            let vert = ::willow::shader_with_header(vert, #glsl_version, None)?;
+            let frag = ::willow::shader_with_header(frag, #glsl_version, #precision)?;
+            let result = self.#data_field.recompile(context, &vert, &frag);
+            #(self.#reset_fields.reset();)*
+            result?;
+            #assign_sampler_units_call
+            Ok(())
+        }
+    };
+
+    let element_setters = input.uniforms.iter().filter_map(|uniform| {
+        let (elem, len) = uniform.array.as_ref()?;
+        let field = &uniform.field;
+        let gl_name = &uniform.gl;
+        let setter = quote::format_ident!("{}_at", field);
+        let doc_str = format!(
+            "Sets the element `index` of the `{}` array uniform without changing the other elements.",
+            gl_name
+        );
+        Some(quote! {
+            #[doc = #doc_str]
+            ///
+            /// This uses the program, and returns an error if `index` is out of bounds.
+            #vis fn #setter(&self, context: &#backend, index: usize, value: #elem) -> ::willow::Result<()> {
+                if index >= #len {
+                    ::willow::anyhow::bail!("Index {} is out of bounds for the uniform array \"{}\" of length {}", index, #gl_name, #len);
+                }
+                ::willow::Program::use_program(self, context);
+                let location = self.#field.get_element_location(context, &self.#data_field, #gl_name, index);
+                let location = ::willow::anyhow::Context::with_context(location, || format!("Could not retrieve uniform location with name \"{}[{}]\"", #gl_name, index))?;
+                ::willow::UniformType::apply_uniform(value, context, &location);
+                Ok(())
+            }
+        })
+    });
+
     quote! {
         impl #ident {
             #with_uniforms
+
+            #reload
+
+            #resolve_locations
+
+            /// Checks whether the program can execute in the current WebGL state
+            /// with `validateProgram`, returning the info log on failure.
+            ///
+            /// This is called automatically before the first draw of the program in debug builds.
+            #vis fn validate(&self, context: &#backend) -> ::willow::Result<()> {
+                self.#data_field.validate(context)
+            }
+
+            #assign_sampler_units
+
+            #instance_methods
+
+            #(#element_setters)*
         }
 
         impl ::willow::Program for #ident {
             type AttrStruct = #attr_ident;
+            type Backend = #backend;
 
             #create_internally
 
@@ -166,11 +400,64 @@ fn gen_program_impl(input: &Input) -> TokenStream {
     }
 }
 
+/// The type of the value assigned to a uniform in the builder
+fn uniform_arg_type(uniform: &Uniform) -> TokenStream {
+    match uniform.kind {
+        UniformKind::Value => uniform.ty.to_token_stream(),
+        UniformKind::Texture => quote!(&'program ::willow::Texture),
+        UniformKind::MatrixArray => quote!(&'program ::willow::MatrixArray),
+    }
+}
+
+/// The backend of the program, i.e. the type argument of the `ProgramData` field
+fn backend_type(input: &Input) -> TokenStream {
+    match &input.backend {
+        Some(ty) => ty.to_token_stream(),
+        None => quote!(::willow::Context),
+    }
+}
+
+fn option_tokens<T: ToTokens>(option: Option<T>) -> TokenStream {
+    match option {
+        Some(value) => quote!(Some(#value)),
+        None => quote!(None),
+    }
+}
+
 fn gen_attrs(input: &Input) -> TokenStream {
-    let vis = &input.vis;
-    let attr_ident = &input.attr_ident;
+    let (instance_attrs, vertex_attrs): (Vec<_>, Vec<_>) = input
+        .attributes
+        .iter()
+        .partition(|attr| attr.divisor.is_some());
+
+    let vertex_struct = gen_attr_struct(
+        &input.vis,
+        &input.attr_ident,
+        "Stores the attributes for a single vertex.",
+        &vertex_attrs,
+    );
+    let instance_struct = if instance_attrs.is_empty() {
+        quote!()
+    } else {
+        gen_attr_struct(
+            &input.vis,
+            &input.instance_ident,
+            "Stores the `#[willow(instanced)]` attributes for a single instance.",
+            &instance_attrs,
+        )
+    };
 
-    let field_def = input.attributes.iter().map(|attr| {
+    quote! { #vertex_struct #instance_struct }
+}
+
+/// Generates an `AttrStruct` with the given attributes as fields
+fn gen_attr_struct(
+    vis: &syn::Visibility,
+    attr_ident: &syn::Ident,
+    struct_doc: &str,
+    attributes: &[&Attribute],
+) -> TokenStream {
+    let field_def = attributes.iter().map(|attr| {
         let name = &attr.field;
         let ty = &attr.ty;
         let doc = &attr.doc;
@@ -180,7 +467,7 @@ fn gen_attrs(input: &Input) -> TokenStream {
         }
     });
 
-    let num_fields = input.attributes.len();
+    let num_fields = attributes.len();
     let fn_fields_count = quote! { fn fields_count() -> usize { #num_fields } };
 
     fn define_function<T: ToTokens>(
@@ -210,58 +497,96 @@ fn gen_attrs(input: &Input) -> TokenStream {
     let fn_field_gl_name = define_function(
         "field_gl_name",
         quote!(&'static str),
-        input.attributes.iter().map(|attr| {
+        attributes.iter().map(|attr| {
             let name = &attr.gl;
             quote!(#name)
         }),
-        input.attributes.iter().map(|attr| attr.ty.span()),
+        attributes.iter().map(|attr| attr.ty.span()),
     );
 
     let fn_field_offset = define_function(
         "field_offset",
         quote!(usize),
-        input.attributes.iter().map(|attr| {
+        attributes.iter().map(|attr| {
             let name = &attr.field;
             quote!(::willow::offset_of!(Self => #name).get_byte_offset())
         }),
-        input.attributes.iter().map(|attr| attr.ty.span()),
+        attributes.iter().map(|attr| attr.ty.span()),
     );
 
     let fn_field_type = define_function(
         "field_type",
         quote!(u32),
-        input.attributes.iter().map(|attr| {
+        attributes.iter().map(|attr| {
             let ty = &attr.ty;
             quote!(<#ty as ::willow::AttributeType>::gl_type())
         }),
-        input.attributes.iter().map(|attr| attr.ty.span()),
+        attributes.iter().map(|attr| attr.ty.span()),
     );
 
     let fn_field_num_comps = define_function(
         "field_num_comps",
         quote!(usize),
-        input.attributes.iter().map(|attr| {
+        attributes.iter().map(|attr| {
             let ty = &attr.ty;
             quote!(<#ty as ::willow::AttributeType>::num_comps())
         }),
-        input.attributes.iter().map(|attr| attr.ty.span()),
+        attributes.iter().map(|attr| attr.ty.span()),
     );
 
     let fn_field_normalized = define_function(
         "field_normalized",
         quote!(bool),
-        input.attributes.iter().map(|attr| {
+        attributes.iter().map(|attr| {
             let normalized = attr.normalized;
-            quote!(#normalized)
+            let ty = &attr.ty;
+            quote!(#normalized || <#ty as ::willow::AttributeType>::normalized())
         }),
-        input.attributes.iter().map(|attr| attr.ty.span()),
+        attributes.iter().map(|attr| attr.ty.span()),
     );
 
+    let fn_field_num_columns = define_function(
+        "field_num_columns",
+        quote!(usize),
+        attributes.iter().map(|attr| {
+            let ty = &attr.ty;
+            quote!(<#ty as ::willow::AttributeType>::num_columns())
+        }),
+        attributes.iter().map(|attr| attr.ty.span()),
+    );
+
+    // `Debug` and `Default` are only implemented if all field types implement them.
+    // The higher-ranked bounds are not checked until the impl is used,
+    // so the struct still compiles if a field type lacks the trait.
+    let names: Vec<_> = attributes.iter().map(|attr| &attr.field).collect();
+    let types: Vec<_> = attributes.iter().map(|attr| &attr.ty).collect();
+
     quote! {
-        /// Stores the attributes for a single vertex.
+        #[doc = #struct_doc]
         #[repr(C)]
+        #[derive(Clone, Copy)]
         #vis struct #attr_ident { #(#field_def),* }
 
+        impl ::std::fmt::Debug for #attr_ident
+        where #(for<'__willow> #types: ::std::fmt::Debug),*
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_struct(stringify!(#attr_ident))
+                    #(.field(stringify!(#names), &self.#names))*
+                    .finish()
+            }
+        }
+
+        impl ::std::default::Default for #attr_ident
+        where #(for<'__willow> #types: ::std::default::Default),*
+        {
+            fn default() -> Self {
+                Self {
+                    #(#names: ::std::default::Default::default()),*
+                }
+            }
+        }
+
         impl ::willow::AttrStruct for #attr_ident {
             #fn_fields_count
 
@@ -270,6 +595,7 @@ fn gen_attrs(input: &Input) -> TokenStream {
             #fn_field_type
             #fn_field_num_comps
             #fn_field_normalized
+            #fn_field_num_columns
         }
     }
 }
@@ -280,6 +606,7 @@ fn gen_builder(input: &Input) -> TokenStream {
     let builder_ident = &input.builder_ident;
     let attr_ident = &input.attr_ident;
     let data_field = &input.program_data;
+    let backend = backend_type(input);
 
     let doc_str = format!(
         "A builder type to run a `{}` program after resetting all uniforms.",
@@ -290,7 +617,7 @@ fn gen_builder(input: &Input) -> TokenStream {
         .uniforms
         .iter()
         .map(|uniform| {
-            let raw = format!("has {}", uniform.field.to_string());
+            let raw = format!("has {}", uniform.field);
             let camel = raw.to_camel_case();
             syn::Ident::new(camel.as_str(), uniform.field.span())
         })
@@ -301,37 +628,34 @@ fn gen_builder(input: &Input) -> TokenStream {
         .iter()
         .map(|uniform| &uniform.field)
         .collect();
-    let types: Vec<_> = input.uniforms.iter().map(|uniform| &uniform.ty).collect();
+    let types: Vec<_> = input.uniforms.iter().map(uniform_arg_type).collect();
 
     let builders = field_names.iter().enumerate().map(|(i, field_name)| {
-        let empty_generics = generics.iter().enumerate().map(|(j, ident)| {
-            if i == j { quote!(()) } else { quote!(#ident) }
-        });
         let filled_generics = generics.iter().enumerate().map(|(j, ident)| {
-            if i == j { types[i].to_token_stream() } else { quote!(#ident) }
+            if i == j { types[i].clone() } else { quote!(#ident) }
         });
 
         let other_fields = input.uniforms.iter().enumerate().filter(|&(j, _)| j != i)
             .map(|(_, uniform)| &uniform.field);
 
-        let other_generics = generics.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, v)| v);
-
-        let ty = &input.uniforms[i].ty;
+        let ty = &types[i];
 
-        let doc_str = format!("Sets the `{}` uniform", input.uniforms[i].gl.as_str());
+        let doc_str = format!("Sets the `{}` uniform, replacing the previous value if any", input.uniforms[i].gl.as_str());
 
         quote! {
-            impl<'program, #(#other_generics),*> #builder_ident<'program, #(#empty_generics),*> {
+            impl<'program, #(#generics),*> #builder_ident<'program, #(#generics),*> {
                 #[doc = #doc_str]
                 #vis fn #field_name(self, #field_name: #ty) -> #builder_ident<'program, #(#filled_generics),*> {
                     let Self {
                         program,
-                        #field_name: (),
+                        ignore_missing_uniforms,
+                        #field_name: _,
                         #(#other_fields),*
                     } = self;
 
                     #builder_ident {
                         program,
+                        ignore_missing_uniforms,
                         #(#field_names),*
                     }
                 }
@@ -339,27 +663,187 @@ fn gen_builder(input: &Input) -> TokenStream {
         }
     });
 
-    let gl_names = input.uniforms.iter().map(|uniform| &uniform.gl);
+    let apply_blocks = input.uniforms.iter().map(|uniform| {
+        let field = &uniform.field;
+        let gl_name = &uniform.gl;
+
+        if let Some((elem, _)) = &uniform.array {
+            // The location of an array uniform is the location of its first element,
+            // so all elements are uploaded with a single `uniform*v` call.
+            return quote! {
+                match self.program.#field.get_location(context, &self.program.#data_field, #gl_name) {
+                    Some(location) => {
+                        ::willow::__trace!("uniform {}.{}", stringify!(#ident), #gl_name);
+                        <#elem as ::willow::UniformType>::apply_uniforms(&self.#field[..], context, location);
+                    }
+                    None if self.ignore_missing_uniforms => ::willow::log::debug!("Skipping uniform \"{}\" without a location", #gl_name),
+                    None => ::willow::anyhow::bail!("Could not retrieve uniform location with name \"{}\"", #gl_name),
+                }
+            };
+        }
+
+        let get_location = match &uniform.member {
+            None => {
+                quote!(self.program.#field.get_location(context, &self.program.#data_field, #gl_name))
+            }
+            Some((struct_field, index)) => {
+                quote!(self.program.#struct_field.get_location(context, &self.program.#data_field, #index, #gl_name))
+            }
+        };
+        let apply_value = match uniform.kind {
+            UniformKind::Value => {
+                quote!(::willow::UniformType::apply_uniform(self.#field, context, location))
+            }
+            UniformKind::Texture => {
+                quote!(::willow::Texture::apply_sampler(self.#field, context, location, &mut texture_unit, fixed_units)?)
+            }
+            UniformKind::MatrixArray => {
+                quote!(::willow::MatrixArray::apply_uniform(self.#field, context, location))
+            }
+        };
+        quote! {
+            match #get_location {
+                Some(location) => {
+                    ::willow::__trace!("uniform {}.{}", stringify!(#ident), #gl_name);
+                    #apply_value;
+                }
+                None if self.ignore_missing_uniforms => ::willow::log::debug!("Skipping uniform \"{}\" without a location", #gl_name),
+                None => ::willow::anyhow::bail!("Could not retrieve uniform location with name \"{}\"", #gl_name),
+            }
+        }
+    });
+    let init_texture_unit = if input
+        .uniforms
+        .iter()
+        .any(|uniform| uniform.kind == UniformKind::Texture)
+    {
+        // Skip the units assigned with `#[willow(sampler_unit = N)]`,
+        // since the samplers of this program still sample from them.
+        let fixed_units = input.sampler_units.iter().map(|sampler| sampler.unit);
+        quote! {
+            let mut texture_unit = 0;
+            let fixed_units: &[u32] = &[#(#fixed_units),*];
+        }
+    } else {
+        quote!()
+    };
+
+    let draw_with_instances = if input.attributes.iter().any(|attr| attr.divisor.is_some()) {
+        let instance_ident = &input.instance_ident;
+        quote! {
+            /// Calls the program after setting all uniforms,
+            /// drawing one instance of the vertices for each element of `instances`.
+            ///
+            /// The `#[willow(instanced)]` attributes are read from `instances`,
+            /// and the other attributes are read from `buffer` for each vertex.
+            ///
+            /// Returns an error if instancing is not supported by the context.
+            #vis fn draw_with_instances(self, context: &#backend, mode: ::willow::RenderPrimitiveType, buffer: &::willow::Buffer<#attr_ident, #backend>, instances: &::willow::Buffer<#instance_ident, #backend>, indices: &impl ::willow::AbstractIndices<#backend>) -> ::willow::Result<()> {
+                self.apply_uniforms(context)?;
+
+                if instances.is_empty() {
+                    return Err(::willow::DrawError::BufferEmpty.into());
+                }
+                let result = self.program.bind_instances(context, instances).and_then(|()| {
+                    ::willow::AbstractIndices::draw_instanced(indices, mode, context, self.program, buffer, instances.len())
+                });
+                self.program.unbind_instances(context);
+                result?;
+
+                Ok(())
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    // Framebuffers are only supported by `Context`
+    let draw_to = if input.backend.is_none() {
+        quote! {
+            /// Calls the program after setting all uniforms,
+            /// drawing into `framebuffer` instead of the canvas.
+            ///
+            /// The viewport is set to the size of the framebuffer during the draw.
+            /// The default framebuffer and the previous viewport are restored afterwards.
+            #vis fn draw_to(self, framebuffer: &::willow::Framebuffer, context: &::willow::Context, mode: ::willow::RenderPrimitiveType, buffer: &::willow::Buffer<#attr_ident>, indices: &impl ::willow::AbstractIndices) -> ::willow::Result<()> {
+                ::willow::DrawBuilder::draw_to(self, framebuffer, context, mode, buffer, indices)
+            }
+        }
+    } else {
+        quote!()
+    };
 
     let draw_def = quote! {
         impl<'program> #builder_ident<'program, #(#types),*> {
             /// Calls the program after setting all uniforms.
-            #vis fn draw(self, context: &::willow::Context, mode: ::willow::RenderPrimitiveType, buffer: &::willow::Buffer<#attr_ident>, indices: &impl ::willow::AbstractIndices) -> ::willow::Result<()> {
-                use ::willow::anyhow::Context;
+            #vis fn draw(self, context: &#backend, mode: ::willow::RenderPrimitiveType, buffer: &::willow::Buffer<#attr_ident, #backend>, indices: &impl ::willow::AbstractIndices<#backend>) -> ::willow::Result<()> {
+                self.apply_uniforms(context)?;
+
+                ::willow::AbstractIndices::draw(indices, mode, context, self.program, buffer)?;
+
+                Ok(())
+            }
+
+            #draw_to
+
+            /// Calls the program after setting all uniforms,
+            /// drawing `instances` instances of the vertices.
+            ///
+            /// Returns an error if instancing is not supported by the context.
+            #vis fn draw_instanced(self, context: &#backend, mode: ::willow::RenderPrimitiveType, buffer: &::willow::Buffer<#attr_ident, #backend>, indices: &impl ::willow::AbstractIndices<#backend>, instances: usize) -> ::willow::Result<()> {
+                self.apply_uniforms(context)?;
+
+                ::willow::AbstractIndices::draw_instanced(indices, mode, context, self.program, buffer, instances)?;
 
+                Ok(())
+            }
+
+            #draw_with_instances
+
+            /// Calls the program after setting all uniforms,
+            /// drawing each range of `indices` in `ranges` with a separate draw call.
+            ///
+            /// This is equivalent to calling `draw` with each subindex of `indices`,
+            /// but the uniforms and attributes are only applied once.
+            #vis fn draw_multi(self, context: &#backend, mode: ::willow::RenderPrimitiveType, buffer: &::willow::Buffer<#attr_ident, #backend>, indices: &::willow::Indices<#backend>, ranges: &[::std::ops::Range<usize>]) -> ::willow::Result<()> {
+                self.apply_uniforms(context)?;
+
+                indices.draw_multi(mode, context, self.program, buffer, ranges)?;
+
+                Ok(())
+            }
+
+            fn apply_uniforms(&self, context: &#backend) -> ::willow::Result<()> {
+                if ::willow::Backend::is_context_lost(context) {
+                    return Err(::willow::DrawError::ContextLost.into());
+                }
                 ::willow::Program::use_program(self.program, context);
 
-                #({
-                    let location = self.program.#field_names.get_location(context, &self.program.#data_field, #gl_names)
-                        .with_context(|| format!("Could not retrieve uniform location with name \"{}\"", #gl_names))?;
-                    ::willow::UniformType::apply_uniform(self.#field_names, &context.native, location);
-                })*
+                #init_texture_unit
+                #(#apply_blocks)*
 
-                ::willow::AbstractIndices::draw(indices, mode, context, self.program, buffer);
+                #[cfg(debug_assertions)]
+                self.program.#data_field.validate_once(context)?;
 
                 Ok(())
             }
         }
+
+        impl<'program> ::willow::DrawBuilder for #builder_ident<'program, #(#types),*> {
+            type Program = #ident;
+
+            fn apply_uniforms(&self, context: &#backend) -> ::willow::Result<()> {
+                #builder_ident::apply_uniforms(self, context)
+            }
+
+            fn draw(self, context: &#backend, mode: ::willow::RenderPrimitiveType, buffer: &::willow::Buffer<#attr_ident, #backend>, indices: &impl ::willow::AbstractIndices<#backend>) -> ::willow::Result<()> {
+                #builder_ident::draw(self, context, mode, buffer, indices)
+            }
+
+            fn ignore_missing_uniforms(self) -> Self {
+                #builder_ident::ignore_missing_uniforms(self)
+            }
+        }
     };
 
     quote! {
@@ -368,9 +852,21 @@ fn gen_builder(input: &Input) -> TokenStream {
         #[must_use = "Builder type must be called"]
         #vis struct #builder_ident<'program, #(#generics),*> {
             program: &'program #ident,
+            ignore_missing_uniforms: bool,
             #(#field_names: #generics,)*
         }
 
+        impl<'program, #(#generics),*> #builder_ident<'program, #(#generics),*> {
+            /// Skips uniforms without a location instead of returning an error when drawing,
+            /// e.g. uniforms optimized out by the GLSL compiler because they are unused.
+            ///
+            /// The skipped uniforms are logged at the debug level.
+            #vis fn ignore_missing_uniforms(mut self) -> Self {
+                self.ignore_missing_uniforms = true;
+                self
+            }
+        }
+
         #(#builders)*
 
         #draw_def
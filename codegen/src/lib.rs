@@ -12,8 +12,157 @@ mod parse;
 ///
 /// The struct must only contain the following fields:
 /// - Exactly one `ProgramData` field
-/// - `Uniform<T>` fields (use `#[willow(uniform(T)]` if aliased)
+/// - `Uniform<T>` fields (use `#[willow(uniform(T))]` if aliased)
 /// - `Attribute<T>` fields (use `#[willow(attribute(T))]` if aliased)
+/// - `UniformStruct` fields for GLSL struct uniforms,
+///   with the members declared as `#[willow(uniform_struct(member: T, ...))]`
+///
+/// The program runs on the backend of the `ProgramData` field,
+/// which is `willow::Context` by default.
+/// A program with a `ProgramData<MockContext>` field runs on the mock backend
+/// of the `mock` feature instead, and declares its uniforms as `Uniform<T, MockContext>`.
+/// Texture uniforms and `draw_to` are only available on `Context`.
+///
+/// The `uniform(T)` and `attribute(T)` annotations declare the type used in the generated code,
+/// which is useful when the field type is a type alias or a `Uniform`/`Attribute`
+/// of a type that does not implement `UniformType`/`AttributeType`:
+/// ```ignore
+/// type Transform = Uniform<Matrix4<f32>>;
+///
+/// #[willow(uniform(Matrix4<f32>))]
+/// transform: Transform,
+/// #[willow(uniform(Matrix4<f32>))]
+/// model: Uniform<ModelMatrix>,
+/// ```
+/// The uniform setters take the annotated type `T`,
+/// and the attribute struct stores the annotated type `T`,
+/// while the field type only stores the location.
+/// A `Uniform` field cannot be annotated with `attribute(T)` and vice versa.
+///
+/// A `Uniform<Texture>` field corresponds to a `sampler2D` uniform,
+/// and is assigned with a `&Texture` in the builder.
+/// Each texture is bound to the next free texture unit when drawing,
+/// and the sampler uniform is set to that unit.
+/// Drawing fails if a program has more texture uniforms than
+/// `MAX_TEXTURE_IMAGE_UNITS` (at least 8).
+///
+/// Alternatively, a sampler uniform can be assigned a fixed texture unit
+/// with `#[willow(sampler_unit = N)]`:
+/// ```ignore
+/// #[willow(sampler_unit = 0)]
+/// diffuse: Uniform<Sampler2D>,
+/// ```
+/// The unit is assigned only once after the program is linked (and again after `reload`),
+/// so the field has no setter in the builder,
+/// and draws only need to bind the texture to the unit with `Texture::bind_unit`.
+/// Texture units are shared by all programs while the assigned unit is stored per program,
+/// so programs sharing a unit must bind the right texture before each draw.
+/// `Uniform<Texture>` fields of the same program skip the fixed units when drawing.
+/// The attribute is only allowed on `Sampler2D`, `Sampler2DShadow`, `Sampler2DArray`
+/// and `Sampler3D` uniforms, and creating the program fails if `N` is not less than
+/// `MAX_COMBINED_TEXTURE_IMAGE_UNITS`.
+///
+/// A `Uniform<MatrixArray>` field corresponds to a `mat4` array uniform
+/// whose length is only known at runtime, e.g. `uniform mat4 bones[64]`,
+/// and is assigned with a `&MatrixArray` in the builder,
+/// which uploads all matrices at once:
+/// ```ignore
+/// let bones = MatrixArray::new(&skeleton.matrices)?;
+/// skin.with_uniforms().bones(bones).draw(...)?;
+/// ```
+///
+/// A `Uniform<[T; N]>` field corresponds to a GLSL array uniform, e.g. `uniform vec3 points[16]`
+/// for `Uniform<[Vector3<f32>; 16]>`.
+/// The builder assigns all elements with a `[T; N]` in a single `uniform*v` call,
+/// while the generated method `{field}_at(context, index, value)`
+/// assigns a single element without changing the others:
+/// ```ignore
+/// scene.points_at(&context, 3, Vector3::new(0., 1., 0.))?;
+/// ```
+///
+/// The GLSL name of a field defaults to the field name,
+/// and can be changed with `#[willow(gl_name = "name")]`.
+/// The GLSL name may refer to a struct member or an array element,
+/// e.g. `#[willow(gl_name = "light.color")]`.
+///
+/// If the shaders name their variables with a common prefix,
+/// the prefixes can be declared in the struct attribute instead:
+/// ```ignore
+/// #[willow(attr_prefix = "a_", uniform_prefix = "u_")]
+/// ```
+/// A field `color` then maps to the GLSL attribute `a_color` or the GLSL uniform `u_color`.
+/// The uniform prefix also applies to `UniformStruct` fields, e.g. `u_light.color`.
+/// The prefixes are not applied to fields with an explicit `gl_name`.
+///
+/// Two attributes or two uniforms (including `UniformStruct` members) mapping to the same GLSL name
+/// are rejected at compile time.
+///
+/// If the shaders use a different naming convention than the snake_case field names,
+/// the field names can be converted with the `gl_case` struct attribute,
+/// which accepts `"camel"` (`modelView`), `"pascal"` (`ModelView`) or `"snake"` (`model_view`):
+/// ```ignore
+/// #[willow(gl_case = "camel", uniform_prefix = "u_")]
+/// ```
+/// A field `model_view` then maps to the GLSL uniform `u_modelView`.
+/// The conversion also applies to the members of `UniformStruct` fields,
+/// but not to fields with an explicit `gl_name`.
+/// For a struct uniform with multiple members, a `UniformStruct` field is more convenient:
+/// ```ignore
+/// #[willow(uniform_struct(color: Vector3<f32>, intensity: f32))]
+/// light: UniformStruct,
+/// ```
+/// This generates the uniform setters `light_color` and `light_intensity`,
+/// which assign the GLSL uniforms `light.color` and `light.intensity`.
+///
+/// The attributes of each vertex are stored in the generated `{Struct}Attr` struct (e.g. `SceneAttr`),
+/// which implements `Clone` and `Copy`.
+/// It also implements `Debug` and `Default` if all attribute types implement them,
+/// so vertices can be built incrementally from `SceneAttr::default()`.
+///
+/// Attribute fields with integer component types can be marked with `#[willow(normalized)]`
+/// so that the shader reads them as floating point values in a fixed range:
+/// - Unsigned types are mapped to `[0, 1]`, e.g. `u8` maps `[0, 255]` to `[0, 1]`.
+/// - Signed types are mapped to `[-1, 1]`, e.g. `i8` maps `[-128, 127]` to `[-1, 1]`.
+///
+/// For example, an RGBA color stored as four bytes is read as a `vec4` in the shader:
+/// ```ignore
+/// #[willow(normalized)]
+/// a_color: Attribute<nalgebra::Vector4<u8>>,
+/// ```
+/// Integer vectors are uploaded with their integer component type (`UNSIGNED_BYTE` here),
+/// so without `#[willow(normalized)]` the shader reads the components as `0.0` to `255.0`.
+///
+/// Normalization is specified per `vertexAttribPointer` call,
+/// so it applies to all components of the field.
+/// Split the components into separate attributes if they need different normalization.
+/// `#[willow(normalized)]` has no effect on `f32` attributes.
+///
+/// Attribute fields marked with `#[willow(instanced)]` advance once per instance instead of once per vertex,
+/// e.g. the model matrix or color of each instance.
+/// They are stored in a separate `{Struct}Instance` struct (e.g. `SceneInstance`)
+/// instead of the `{Struct}Attr` struct, which are uploaded to separate buffers:
+/// ```ignore
+/// #[willow(instanced)]
+/// offset: Attribute<Vector3<f32>>,
+/// ```
+/// ```ignore
+/// let instances = Scene::prepare_instance_buffer(&context, &offsets, BufferDataUsage::DynamicDraw)?;
+/// scene.with_uniforms()
+///     .projection(projection)
+///     .draw_with_instances(&context, RenderPrimitiveType::Triangles, &vertices, &instances, &indices)?;
+/// ```
+/// `draw_with_instances` draws one instance for each element of the instance buffer,
+/// and sets the attribute divisors to 1 during the draw.
+///
+/// `#[willow(instanced = N)]` sets the divisor to `N` instead,
+/// so that the attribute advances once every `N` instances,
+/// e.g. a color shared by each group of `N` instances.
+/// Since the number of instances is still the length of the instance buffer,
+/// instance `i` reads element `i / N` of such an attribute.
+/// The divisor must be at least 1.
+/// Drawing such a program with the other draw methods reads the instanced attributes
+/// from whatever buffer they were last bound to.
+/// Instancing requires WebGL 2 or the `ANGLE_instanced_arrays` extension.
 ///
 /// In the struct attribute, the path to the GLSL shaders must be specified:
 /// ```ignore
@@ -28,6 +177,38 @@ mod parse;
 /// #[willow(vert = VERTEX_SHADER_CODE, frag = FRAGMENT_SHADER_CODE)]
 /// ```
 ///
+/// Shaders generated by a build script can be embedded from `OUT_DIR`
+/// with the `vert` and `frag` expressions:
+/// ```ignore
+/// #[willow(
+///     vert = include_str!(concat!(env!("OUT_DIR"), "/scene.vert")),
+///     frag = include_str!(concat!(env!("OUT_DIR"), "/scene.frag")),
+/// )]
+/// ```
+///
+/// Alternatively, if the build script exports the paths of the generated files
+/// in environment variables (with `cargo:rustc-env=SCENE_VERT=...`),
+/// they can be loaded with `include_str!(env!("SCENE_VERT"))` by writing this:
+/// ```ignore
+/// #[willow(vert_env = "SCENE_VERT", frag_env = "SCENE_FRAG")]
+/// ```
+///
+/// In both cases, the crate is rebuilt when the generated files change,
+/// but cargo only reruns the build script if it prints `cargo:rerun-if-changed`
+/// for the inputs the shaders are generated from;
+/// otherwise the build script is rerun on every change in the package.
+///
+/// The `#version` directive and the default float precision can be prepended
+/// to the shader sources with the following struct attributes:
+/// ```ignore
+/// #[willow(glsl_version = "300 es", precision = "highp float")]
+/// ```
+///
+/// The `#version` directive is inserted as the first line of both shaders,
+/// so the shader files must not contain their own `#version` directive.
+/// The `precision` declaration is only prepended to the fragment shader,
+/// since vertex shaders already have a default float precision.
+///
 /// # Example
 /// ```ignore
 /// #[derive(willow::Program)]
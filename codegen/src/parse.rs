@@ -6,14 +6,21 @@ use syn::spanned::Spanned;
 pub struct Input {
     pub vertex_source: CodeSource,
     pub fragment_source: CodeSource,
+    pub glsl_version: Option<String>,
+    pub precision: Option<String>,
 
     pub attributes: Vec<Attribute>,
     pub uniforms: Vec<Uniform>,
+    pub uniform_structs: Vec<(syn::Ident, usize)>,
+    pub sampler_units: Vec<SamplerUnit>,
     pub program_data: syn::Ident,
+    /// The backend type argument of the `ProgramData` field, if any
+    pub backend: Option<Box<syn::Type>>,
 
     pub vis: syn::Visibility,
     pub ident: syn::Ident,
     pub attr_ident: syn::Ident,
+    pub instance_ident: syn::Ident,
     pub builder_ident: syn::Ident,
 }
 
@@ -25,12 +32,24 @@ pub fn parse_input(ts: TokenStream) -> syn::Result<Input> {
         VertexCode(syn::Expr),
         /// Specifies the fragment GLSL code dynamically
         FragmentCode(syn::Expr),
+        /// Specifies the environment variable containing the path to the vertex GLSL file
+        VertexEnv(syn::LitStr),
+        /// Specifies the environment variable containing the path to the fragment GLSL file
+        FragmentEnv(syn::LitStr),
+        /// Specifies the `#version` directive prepended to both shaders
+        GlslVersion(String),
+        /// Specifies the default float precision prepended to the fragment shader
+        Precision(String),
+        /// Specifies the prefix of GLSL names of attributes without an explicit `gl_name`
+        AttrPrefix(String),
+        /// Specifies the prefix of GLSL names of uniforms without an explicit `gl_name`
+        UniformPrefix(String),
+        /// Specifies the case of GLSL names of fields without an explicit `gl_name`
+        GlCase(GlCase),
     }
 
     impl Parse for StructAttr {
-        fn parse(input: ParseStream) -> syn::Result<Self> {
-            let content;
-            syn::parenthesized!(content in input);
+        fn parse(content: ParseStream) -> syn::Result<Self> {
             let kw: syn::Ident = content.parse()?;
             Ok(match kw.to_string().as_str() {
                 "path" => {
@@ -49,32 +68,122 @@ pub fn parse_input(ts: TokenStream) -> syn::Result<Input> {
                     let expr: syn::Expr = content.parse()?;
                     Self::FragmentCode(expr)
                 }
+                "vert_env" => {
+                    let _: syn::Token![=] = content.parse()?;
+                    Self::VertexEnv(content.parse()?)
+                }
+                "frag_env" => {
+                    let _: syn::Token![=] = content.parse()?;
+                    Self::FragmentEnv(content.parse()?)
+                }
+                "glsl_version" => {
+                    let _: syn::Token![=] = content.parse()?;
+                    let version: syn::LitStr = content.parse()?;
+                    if version.value().trim().is_empty() {
+                        return Err(syn::Error::new_spanned(
+                            version,
+                            "GLSL version must not be empty",
+                        ));
+                    }
+                    Self::GlslVersion(version.value().trim().to_string())
+                }
+                "precision" => {
+                    let _: syn::Token![=] = content.parse()?;
+                    let precision: syn::LitStr = content.parse()?;
+                    let value = precision.value();
+                    let value = value.trim().trim_end_matches(';');
+                    let words: Vec<_> = value.split_whitespace().collect();
+                    match words.as_slice() {
+                        ["lowp" | "mediump" | "highp", _] => {}
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                precision,
+                                "Precision must be in the form \"highp float\"",
+                            ))
+                        }
+                    }
+                    Self::Precision(words.join(" "))
+                }
+                "attr_prefix" => {
+                    let _: syn::Token![=] = content.parse()?;
+                    let prefix: syn::LitStr = content.parse()?;
+                    Self::AttrPrefix(prefix.value())
+                }
+                "uniform_prefix" => {
+                    let _: syn::Token![=] = content.parse()?;
+                    let prefix: syn::LitStr = content.parse()?;
+                    Self::UniformPrefix(prefix.value())
+                }
+                "gl_case" => {
+                    let _: syn::Token![=] = content.parse()?;
+                    let case: syn::LitStr = content.parse()?;
+                    Self::GlCase(match case.value().as_str() {
+                        "camel" => GlCase::Camel,
+                        "pascal" => GlCase::Pascal,
+                        "snake" => GlCase::Snake,
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                case,
+                                "GLSL case must be \"camel\", \"pascal\" or \"snake\"",
+                            ))
+                        }
+                    })
+                }
                 kw => return Err(content.error(format!("Unsupported attribute #[willow({})]", kw))),
             })
         }
     }
 
+    /// A comma-separated list of `StructAttr`s in a `#[willow(...)]` attribute
+    struct StructAttrs(Vec<StructAttr>);
+
+    impl Parse for StructAttrs {
+        fn parse(input: ParseStream) -> syn::Result<Self> {
+            let content;
+            syn::parenthesized!(content in input);
+            let attrs = content.parse_terminated::<_, syn::Token![,]>(StructAttr::parse)?;
+            Ok(Self(attrs.into_iter().collect()))
+        }
+    }
+
     let input: syn::DeriveInput = syn::parse2(ts)?;
 
     let vis = &input.vis;
     let mut vertex_source = None;
     let mut fragment_source = None;
+    let mut glsl_version = None;
+    let mut precision = None;
+    let mut naming = Naming::default();
 
     let input_ident = &input.ident;
 
     for attr in &input.attrs {
         if attr.path.is_ident("willow") {
-            match syn::parse2::<StructAttr>(attr.tokens.clone())? {
-                StructAttr::Path(path) => {
-                    vertex_source = Some(CodeSource::File(attr.span(), format!("{}.vert", &path)));
-                    fragment_source =
-                        Some(CodeSource::File(attr.span(), format!("{}.frag", &path)));
-                }
-                StructAttr::VertexCode(expr) => {
-                    vertex_source = Some(CodeSource::Expr(Box::new(expr)));
-                }
-                StructAttr::FragmentCode(expr) => {
-                    fragment_source = Some(CodeSource::Expr(Box::new(expr)));
+            for struct_attr in syn::parse2::<StructAttrs>(attr.tokens.clone())?.0 {
+                match struct_attr {
+                    StructAttr::Path(path) => {
+                        vertex_source =
+                            Some(CodeSource::File(attr.span(), format!("{}.vert", &path)));
+                        fragment_source =
+                            Some(CodeSource::File(attr.span(), format!("{}.frag", &path)));
+                    }
+                    StructAttr::VertexCode(expr) => {
+                        vertex_source = Some(CodeSource::Expr(Box::new(expr)));
+                    }
+                    StructAttr::FragmentCode(expr) => {
+                        fragment_source = Some(CodeSource::Expr(Box::new(expr)));
+                    }
+                    StructAttr::VertexEnv(var) => {
+                        vertex_source = Some(CodeSource::Env(var.span(), var.value()));
+                    }
+                    StructAttr::FragmentEnv(var) => {
+                        fragment_source = Some(CodeSource::Env(var.span(), var.value()));
+                    }
+                    StructAttr::GlslVersion(version) => glsl_version = Some(version),
+                    StructAttr::Precision(value) => precision = Some(value),
+                    StructAttr::AttrPrefix(prefix) => naming.attribute_prefix = prefix,
+                    StructAttr::UniformPrefix(prefix) => naming.uniform_prefix = prefix,
+                    StructAttr::GlCase(case) => naming.case = Some(case),
                 }
             }
         }
@@ -102,17 +211,40 @@ pub fn parse_input(ts: TokenStream) -> syn::Result<Input> {
 
     let mut attributes = Vec::new();
     let mut uniforms = Vec::new();
+    let mut uniform_structs = Vec::new();
+    let mut sampler_units = Vec::new();
 
     let mut program_data = None;
 
     for field in &fields.named {
-        match FieldOutput::from_field(field)? {
+        match FieldOutput::from_field(field, &naming)? {
             FieldOutput::Attribute(attr) => attributes.push(attr),
             FieldOutput::Uniform(unif) => uniforms.push(unif),
-            FieldOutput::ProgramData(ident) => program_data = Some(ident),
+            FieldOutput::UniformStruct(ident, members) => {
+                uniform_structs.push((ident, members.len()));
+                uniforms.extend(members);
+            }
+            FieldOutput::SamplerUnit(sampler) => sampler_units.push(sampler),
+            FieldOutput::ProgramData(ident, ty) => program_data = Some((ident, ty)),
         }
     }
 
+    check_collisions(
+        "attribute",
+        attributes.iter().map(|attr| (&attr.gl, &attr.field)),
+    )?;
+    check_collisions(
+        "uniform",
+        uniforms
+            .iter()
+            .map(|unif| {
+                // struct members are reported at their `UniformStruct` field
+                let field = unif.member.as_ref().map_or(&unif.field, |(field, _)| field);
+                (&unif.gl, field)
+            })
+            .chain(sampler_units.iter().map(|unif| (&unif.gl, &unif.field))),
+    )?;
+
     let vertex_source = match vertex_source {
         Some(s) => s,
         None => {
@@ -131,7 +263,7 @@ pub fn parse_input(ts: TokenStream) -> syn::Result<Input> {
             ))
         }
     };
-    let program_data = match program_data {
+    let (program_data, backend) = match program_data {
         Some(data) => data,
         None => return Err(syn::Error::new_spanned(
             &input.fields,
@@ -142,18 +274,26 @@ pub fn parse_input(ts: TokenStream) -> syn::Result<Input> {
     Ok(Input {
         vertex_source,
         fragment_source,
+        glsl_version,
+        precision,
         attributes,
         uniforms,
+        uniform_structs,
+        sampler_units,
         program_data,
+        backend,
         vis: vis.clone(),
         ident: input_ident.clone(),
         attr_ident: quote::format_ident!("{}Attr", &input_ident),
+        instance_ident: quote::format_ident!("{}Instance", &input_ident),
         builder_ident: quote::format_ident!("{}Draw", &input_ident),
     })
 }
 
 pub enum CodeSource {
     File(Span, String),
+    /// A file whose path is in an environment variable at compile time
+    Env(Span, String),
     Expr(Box<syn::Expr>),
 }
 
@@ -163,6 +303,9 @@ impl ToTokens for CodeSource {
             Self::File(span, path) => {
                 tokens.extend(quote_spanned! { *span=> include_str!(#path) });
             }
+            Self::Env(span, var) => {
+                tokens.extend(quote_spanned! { *span=> include_str!(env!(#var)) });
+            }
             Self::Expr(expr) => {
                 expr.to_tokens(&mut *tokens);
             }
@@ -170,10 +313,71 @@ impl ToTokens for CodeSource {
     }
 }
 
+/// Returns an error pointing at both fields if two fields map to the same GLSL name.
+fn check_collisions<'t>(
+    kind: &str,
+    names: impl IntoIterator<Item = (&'t String, &'t syn::Ident)>,
+) -> syn::Result<()> {
+    let mut seen = std::collections::HashMap::new();
+    for (gl, field) in names {
+        if let Some(first) = seen.insert(gl, field) {
+            let mut err = syn::Error::new_spanned(
+                field,
+                format!(
+                    "GLSL {} `{}` is already used by the field `{}`",
+                    kind, gl, first
+                ),
+            );
+            err.combine(syn::Error::new_spanned(
+                first,
+                format!("GLSL {} `{}` is first used here", kind, gl),
+            ));
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// The rules to infer the GLSL names of fields without an explicit `gl_name`.
+#[derive(Default)]
+struct Naming {
+    attribute_prefix: String,
+    uniform_prefix: String,
+    case: Option<GlCase>,
+}
+
+impl Naming {
+    /// Converts a Rust name to the case of the GLSL names
+    fn convert(&self, name: &str) -> String {
+        use heck::{CamelCase, MixedCase, SnakeCase};
+
+        match self.case {
+            None => name.to_string(),
+            Some(GlCase::Camel) => name.to_mixed_case(),
+            Some(GlCase::Pascal) => name.to_camel_case(),
+            Some(GlCase::Snake) => name.to_snake_case(),
+        }
+    }
+}
+
+/// The naming convention of GLSL names, specified with `#[willow(gl_case = "...")]`
+#[derive(Clone, Copy)]
+enum GlCase {
+    /// `modelView`
+    Camel,
+    /// `ModelView`
+    Pascal,
+    /// `model_view`
+    Snake,
+}
+
 pub enum FieldOutput {
     Attribute(Attribute),
     Uniform(Uniform),
-    ProgramData(syn::Ident),
+    UniformStruct(syn::Ident, Vec<Uniform>),
+    SamplerUnit(SamplerUnit),
+    /// The `ProgramData` field and its backend type argument, if any
+    ProgramData(syn::Ident, Option<Box<syn::Type>>),
 }
 
 fn is_ending_ident(path: &syn::Path, name: &str) -> bool {
@@ -184,18 +388,37 @@ fn is_ending_ident(path: &syn::Path, name: &str) -> bool {
     }
 }
 
+/// The first type argument of a `ProgramData<B>` type, if any
+fn backend_argument(ty: &syn::Type) -> Option<Box<syn::Type>> {
+    let path = match ty {
+        syn::Type::Path(path) if is_ending_ident(&path.path, "ProgramData") => path,
+        _ => return None,
+    };
+    let segment = path.path.segments.last()?;
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+            syn::GenericArgument::Type(ty) => Some(Box::new(ty.clone())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 impl FieldOutput {
-    fn from_field(field: &syn::Field) -> syn::Result<Self> {
+    fn from_field(field: &syn::Field, naming: &Naming) -> syn::Result<Self> {
         enum FieldType {
             Attribute(Box<syn::Type>),
             Uniform(Box<syn::Type>),
+            UniformStruct(Vec<(syn::Ident, syn::Type)>),
             Data,
         }
         let mut field_type = None;
 
         let field_name = field.ident.as_ref().expect("Fields checked as named");
-        let mut gl_name = field_name.to_string();
+        let mut gl_name = None;
         let mut normalized = false;
+        let mut divisor = None;
+        let mut sampler_unit = None;
         let mut doc = String::new();
 
         for attr in &field.attrs {
@@ -203,9 +426,14 @@ impl FieldOutput {
                 match syn::parse2::<FieldAttr>(attr.tokens.clone())? {
                     FieldAttr::Attribute(ty) => field_type = Some(FieldType::Attribute(ty)),
                     FieldAttr::Uniform(ty) => field_type = Some(FieldType::Uniform(ty)),
-                    FieldAttr::GlName(name) => gl_name = name,
+                    FieldAttr::UniformStruct(members) => {
+                        field_type = Some(FieldType::UniformStruct(members))
+                    }
+                    FieldAttr::GlName(name) => gl_name = Some(name),
                     FieldAttr::Data => field_type = Some(FieldType::Data),
                     FieldAttr::Normalized => normalized = true,
+                    FieldAttr::Instanced(value) => divisor = Some(value),
+                    FieldAttr::SamplerUnit(unit) => sampler_unit = Some(unit),
                 }
             } else if attr.path.is_ident("doc") {
                 let mut tokens = attr.tokens.clone().into_iter();
@@ -222,9 +450,24 @@ impl FieldOutput {
             }
         }
 
+        if let syn::Type::Path(path) = &field.ty {
+            let mismatch = match &field_type {
+                Some(FieldType::Attribute(_)) if is_ending_ident(&path.path, "Uniform") => {
+                    Some("Uniform field cannot be declared as #[willow(attribute(...))]")
+                }
+                Some(FieldType::Uniform(_)) if is_ending_ident(&path.path, "Attribute") => {
+                    Some("Attribute field cannot be declared as #[willow(uniform(...))]")
+                }
+                _ => None,
+            };
+            if let Some(message) = mismatch {
+                return Err(syn::Error::new_spanned(&field.ty, message));
+            }
+        }
+
         if field_type.is_none() {
             match &field.ty {
-                syn::Type::Path(path) if path.path.is_ident("ProgramData") => {
+                syn::Type::Path(path) if is_ending_ident(&path.path, "ProgramData") => {
                     field_type = Some(FieldType::Data)
                 }
                 syn::Type::Path(path)
@@ -263,6 +506,12 @@ impl FieldOutput {
                         unreachable!()
                     }
                 }
+                syn::Type::Path(path) if is_ending_ident(&path.path, "UniformStruct") => {
+                    return Err(syn::Error::new_spanned(
+                        &field.ty,
+                        "UniformStruct fields must declare their members with #[willow(uniform_struct(...))]",
+                    ))
+                }
                 _ => {
                     return Err(syn::Error::new_spanned(
                         &field.ty,
@@ -272,21 +521,86 @@ impl FieldOutput {
             }
         }
 
-        Ok(match field_type.expect("checked") {
+        let field_type = field_type.expect("checked");
+        if normalized && !matches!(field_type, FieldType::Attribute(_)) {
+            return Err(syn::Error::new_spanned(
+                field,
+                "#[willow(normalized)] can only be used on attributes",
+            ));
+        }
+        if divisor.is_some() && !matches!(field_type, FieldType::Attribute(_)) {
+            return Err(syn::Error::new_spanned(
+                field,
+                "#[willow(instanced)] can only be used on attributes",
+            ));
+        }
+        if sampler_unit.is_some()
+            && !matches!(&field_type, FieldType::Uniform(ty) if is_sampler_type(ty))
+        {
+            return Err(syn::Error::new_spanned(
+                field,
+                "#[willow(sampler_unit = ...)] can only be used on sampler uniforms \
+                (Sampler2D, Sampler2DShadow, Sampler2DArray or Sampler3D)",
+            ));
+        }
+
+        let gl_name = gl_name.unwrap_or_else(|| {
+            let prefix = match field_type {
+                FieldType::Attribute(_) => &naming.attribute_prefix,
+                _ => &naming.uniform_prefix,
+            };
+            format!("{}{}", prefix, naming.convert(&field_name.to_string()))
+        });
+
+        Ok(match field_type {
+            FieldType::Uniform(_) if sampler_unit.is_some() => {
+                FieldOutput::SamplerUnit(SamplerUnit {
+                    field: field_name.clone(),
+                    gl: gl_name,
+                    unit: sampler_unit.expect("checked"),
+                })
+            }
             FieldType::Attribute(ty) => FieldOutput::Attribute(Attribute {
                 field: field_name.clone(),
                 ty,
                 gl: gl_name,
                 normalized,
+                divisor,
                 doc,
             }),
             FieldType::Uniform(ty) => FieldOutput::Uniform(Uniform {
                 field: field_name.clone(),
                 gl: gl_name,
+                kind: UniformKind::of(&ty),
+                array: match &*ty {
+                    syn::Type::Array(array) => {
+                        Some((array.elem.clone(), Box::new(array.len.clone())))
+                    }
+                    _ => None,
+                },
                 ty,
                 doc,
+                member: None,
             }),
-            FieldType::Data => FieldOutput::ProgramData(field_name.clone()),
+            FieldType::UniformStruct(members) => FieldOutput::UniformStruct(
+                field_name.clone(),
+                members
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (member, ty))| Uniform {
+                        field: quote::format_ident!("{}_{}", field_name, member),
+                        gl: format!("{}.{}", &gl_name, naming.convert(&member.to_string())),
+                        kind: UniformKind::of(&ty),
+                        array: None,
+                        ty: Box::new(ty),
+                        doc: doc.clone(),
+                        member: Some((field_name.clone(), index)),
+                    })
+                    .collect(),
+            ),
+            FieldType::Data => {
+                FieldOutput::ProgramData(field_name.clone(), backend_argument(&field.ty))
+            }
         })
     }
 }
@@ -296,6 +610,10 @@ pub struct Attribute {
     pub ty: Box<syn::Type>,
     pub gl: String,
     pub normalized: bool,
+    /// The number of instances drawn before the attribute advances,
+    /// or `None` if it advances per vertex.
+    /// Instanced attributes are stored in the instance struct.
+    pub divisor: Option<u32>,
     pub doc: String,
 }
 
@@ -304,14 +622,79 @@ pub struct Uniform {
     pub gl: String,
     pub ty: Box<syn::Type>,
     pub doc: String,
+    /// The `UniformStruct` field and the member index if this is a struct member
+    pub member: Option<(syn::Ident, usize)>,
+    /// Whether the uniform is assigned with a `UniformType` value or a reference
+    pub kind: UniformKind,
+    /// The element type and length if this is an array uniform declared as `[T; N]`
+    pub array: Option<(Box<syn::Type>, Box<syn::Expr>)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum UniformKind {
+    /// Assigned with a `UniformType` value
+    Value,
+    /// A sampler uniform assigned with a `&Texture`
+    Texture,
+    /// A `mat4` array uniform assigned with a `&MatrixArray`
+    MatrixArray,
+}
+
+impl UniformKind {
+    /// Detects `Texture` and `MatrixArray` types or references to them
+    fn of(ty: &syn::Type) -> Self {
+        match ty {
+            syn::Type::Path(path) if path.qself.is_none() => {
+                if is_ending_ident(&path.path, "Texture") {
+                    Self::Texture
+                } else if is_ending_ident(&path.path, "MatrixArray") {
+                    Self::MatrixArray
+                } else {
+                    Self::Value
+                }
+            }
+            syn::Type::Reference(reference) => Self::of(&reference.elem),
+            _ => Self::Value,
+        }
+    }
+
+    /// Whether the uniform is assigned with a reference and has no default value
+    pub fn is_reference(self) -> bool {
+        self != Self::Value
+    }
+}
+
+/// Whether `ty` is one of the sampler uniform types assigned with a texture unit
+fn is_sampler_type(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) if path.qself.is_none() => [
+            "Sampler2D",
+            "Sampler2DShadow",
+            "Sampler2DArray",
+            "Sampler3D",
+        ]
+        .iter()
+        .any(|name| is_ending_ident(&path.path, name)),
+        _ => false,
+    }
+}
+
+/// A sampler uniform assigned a fixed texture unit after linking
+pub struct SamplerUnit {
+    pub field: syn::Ident,
+    pub gl: String,
+    pub unit: u32,
 }
 
 enum FieldAttr {
     Attribute(Box<syn::Type>),
     Uniform(Box<syn::Type>),
+    UniformStruct(Vec<(syn::Ident, syn::Type)>),
     GlName(String),
     Data,
     Normalized,
+    Instanced(u32),
+    SamplerUnit(u32),
 }
 
 impl Parse for FieldAttr {
@@ -321,6 +704,22 @@ impl Parse for FieldAttr {
         let kw: syn::Ident = content.parse()?;
         Ok(match kw.to_string().as_str() {
             "normalized" => Self::Normalized,
+            "instanced" => {
+                if content.peek(syn::Token![=]) {
+                    let _: syn::Token![=] = content.parse()?;
+                    let lit: syn::LitInt = content.parse()?;
+                    let divisor: u32 = lit.base10_parse()?;
+                    if divisor == 0 {
+                        return Err(syn::Error::new_spanned(
+                            lit,
+                            "The instance divisor must be at least 1",
+                        ));
+                    }
+                    Self::Instanced(divisor)
+                } else {
+                    Self::Instanced(1)
+                }
+            }
             "attribute" => {
                 let inner;
                 syn::parenthesized!(inner in content);
@@ -333,13 +732,93 @@ impl Parse for FieldAttr {
                 let ty: syn::Type = inner.parse()?;
                 Self::Uniform(Box::new(ty))
             }
+            "uniform_struct" => {
+                let inner;
+                syn::parenthesized!(inner in content);
+                let members = inner.parse_terminated::<_, syn::Token![,]>(|input| {
+                    let name: syn::Ident = input.parse()?;
+                    let _: syn::Token![:] = input.parse()?;
+                    let ty: syn::Type = input.parse()?;
+                    Ok((name, ty))
+                })?;
+                if members.is_empty() {
+                    return Err(inner.error("uniform_struct must have at least one member"));
+                }
+                Self::UniformStruct(members.into_iter().collect())
+            }
             "gl_name" => {
                 let _: syn::Token![=] = content.parse()?;
                 let str: syn::LitStr = content.parse()?;
                 Self::GlName(str.value())
             }
+            "sampler_unit" => {
+                let _: syn::Token![=] = content.parse()?;
+                let unit: syn::LitInt = content.parse()?;
+                Self::SamplerUnit(unit.base10_parse()?)
+            }
             "data" => Self::Data,
             kw => return Err(content.error(format!("Unsupported attribute #[willow({})]", kw))),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use super::parse_input;
+
+    #[test]
+    fn prefix_applies_to_inferred_names() {
+        let input = parse_input(quote! {
+            #[willow(path = "shader", attr_prefix = "a_", uniform_prefix = "u_")]
+            struct Prefixed {
+                data: ProgramData,
+                position: Attribute<[f32; 2]>,
+                scale: Uniform<f32>,
+            }
+        })
+        .unwrap();
+
+        assert_eq!(input.attributes[0].gl, "a_position");
+        assert_eq!(input.uniforms[0].gl, "u_scale");
+    }
+
+    #[test]
+    fn explicit_gl_name_overrides_prefix() {
+        let input = parse_input(quote! {
+            #[willow(path = "shader", attr_prefix = "a_", uniform_prefix = "u_")]
+            struct Prefixed {
+                data: ProgramData,
+                #[willow(gl_name = "vertex")]
+                position: Attribute<[f32; 2]>,
+                #[willow(gl_name = "zoom")]
+                scale: Uniform<f32>,
+            }
+        })
+        .unwrap();
+
+        assert_eq!(input.attributes[0].gl, "vertex");
+        assert_eq!(input.uniforms[0].gl, "zoom");
+    }
+
+    #[test]
+    fn prefix_induced_collision_is_rejected() {
+        let err = parse_input(quote! {
+            #[willow(path = "shader", uniform_prefix = "u_")]
+            struct Prefixed {
+                data: ProgramData,
+                scale: Uniform<f32>,
+                #[willow(gl_name = "u_scale")]
+                zoom: Uniform<f32>,
+            }
+        })
+        .err()
+        .expect("colliding GLSL names should be rejected");
+
+        assert_eq!(
+            err.to_string(),
+            "GLSL uniform `u_scale` is already used by the field `scale`"
+        );
+    }
+}
@@ -1,14 +1,13 @@
 //! Example webapp using [`willow`](https://docs.rs/willow/).
 
-#![allow(clippy::blacklisted_name)]
+#![allow(clippy::disallowed_names)]
 #![warn(missing_docs)]
 
 use nalgebra::{Matrix4, Vector3};
 use wasm_bindgen::prelude::*;
-use web_sys::WebGlRenderingContext;
 use willow::{
-    AspectFix, Attribute, BufferDataUsage, Clear, Context, Indices, Program, ProgramData,
-    RenderPrimitiveType, Uniform,
+    AspectFix, Attribute, BufferDataUsage, Capability, Clear, Context, DepthFunc, Indices, Program,
+    ProgramData, RenderPrimitiveType, Uniform,
 };
 
 /// This type wraps the program with the `foo.vert` and `foo.frag` shaders.
@@ -36,15 +35,11 @@ pub fn main() {
         .unwrap();
 
     let context = Context::from_canvas(canvas, AspectFix::FromWidth).unwrap();
-    let (foo,) = willow::create_programs!(context => Foo);
+    let (foo,) = willow::create_programs!(context => Foo).unwrap();
 
-    context.clear(Clear {
-        color: Some([0., 0., 0., 1.]),
-        depth: Some(1.),
-        stencil: None,
-    });
-    context.native.enable(WebGlRenderingContext::DEPTH_TEST);
-    context.native.depth_func(WebGlRenderingContext::LEQUAL);
+    context.clear(Clear::new().color([0., 0., 0., 1.]).depth(1.));
+    context.set_capability(Capability::DepthTest, true);
+    context.set_depth_func(DepthFunc::LEqual);
 
     let attrs = Foo::prepare_buffer(
         &context,
@@ -67,7 +62,8 @@ pub fn main() {
             },
         ],
         BufferDataUsage::StaticDraw,
-    );
+    )
+    .unwrap();
 
     let indices = Indices::new(
         &context,
@@ -0,0 +1,599 @@
+//! Abstracts the rendering context used by buffers, indices and programs.
+
+use js_sys::{Uint16Array, Uint32Array};
+use web_sys::{
+    WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlRenderingContext, WebGlShader,
+    WebGlUniformLocation,
+};
+
+use crate::{ActiveInfo, Context, DrawError, GlLimits, MatrixShape, UniformValue, VectorSize};
+
+/// The operations that the draw path issues on a rendering context.
+///
+/// This is implemented by [`Context`][Context] to call the WebGL API.
+/// With the `mock` feature enabled, [`MockContext`][crate::MockContext] also implements this trait
+/// to record the calls instead, which allows testing draw logic outside a browser.
+pub trait Backend {
+    /// The handle type for a buffer allocated in the context.
+    ///
+    /// Cloning the handle refers to the same buffer.
+    type Buffer: Clone;
+
+    /// The handle type for a program allocated in the context.
+    type Program;
+
+    /// The handle type for a shader allocated in the context.
+    type Shader;
+
+    /// The location of a uniform in a linked program.
+    type UniformLocation: Clone;
+
+    /// Allocates a new buffer.
+    fn create_buffer(&self) -> Option<Self::Buffer>;
+
+    /// Binds `buffer` to `target`, e.g. `WebGlRenderingContext::ARRAY_BUFFER`.
+    fn bind_buffer(&self, target: u32, buffer: Option<&Self::Buffer>);
+
+    /// Uploads raw bytes to the buffer bound to `target`.
+    fn buffer_data_u8(&self, target: u32, data: &[u8], usage: u32);
+
+    /// Reallocates the buffer bound to `target` with `size` zeroed bytes.
+    fn buffer_data_size(&self, target: u32, size: i32, usage: u32);
+
+    /// Uploads 16-bit integers to the buffer bound to `target`.
+    fn buffer_data_u16(&self, target: u32, data: &[u16], usage: u32);
+
+    /// Uploads 32-bit integers to the buffer bound to `target`.
+    fn buffer_data_u32(&self, target: u32, data: &[u32], usage: u32);
+
+    /// Overwrites raw bytes in the buffer bound to `target`,
+    /// starting from the byte offset `offset`.
+    fn buffer_sub_data_u8(&self, target: u32, offset: i32, data: &[u8]);
+
+    /// Overwrites 16-bit integers in the buffer bound to `target`,
+    /// starting from the byte offset `offset`.
+    fn buffer_sub_data_u16(&self, target: u32, offset: i32, data: &[u16]);
+
+    /// Overwrites 32-bit integers in the buffer bound to `target`,
+    /// starting from the byte offset `offset`.
+    fn buffer_sub_data_u32(&self, target: u32, offset: i32, data: &[u32]);
+
+    /// Whether the context is lost, in which case all draw calls are ignored.
+    fn is_context_lost(&self) -> bool;
+
+    /// Whether a program is currently in use.
+    fn has_current_program(&self) -> bool;
+
+    /// Enables the extension with the given name, returning whether it is supported.
+    fn enable_extension(&self, name: &str) -> bool;
+
+    /// Whether vertex attributes can have the component type `ty`,
+    /// e.g. `WebGl2RenderingContext::HALF_FLOAT` is only supported in WebGL 2.
+    fn supports_attrib_type(&self, ty: u32) -> bool;
+
+    /// Specifies the layout of a vertex attribute in the buffer bound to `ARRAY_BUFFER`.
+    fn vertex_attrib_pointer(
+        &self,
+        index: u32,
+        size: i32,
+        ty: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    );
+
+    /// Draws `count` vertices starting from `first`.
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32);
+
+    /// Draws `count` vertices indexed by the buffer bound to `ELEMENT_ARRAY_BUFFER`,
+    /// starting from the byte offset `offset`.
+    fn draw_elements(&self, mode: u32, count: i32, ty: u32, offset: i32);
+
+    /// Whether instanced drawing is supported.
+    fn supports_instancing(&self) -> bool;
+
+    /// Draws `instances` instances of `count` vertices starting from `first`.
+    ///
+    /// This is only called if [`supports_instancing`][Backend::supports_instancing] returns `true`.
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32);
+
+    /// Draws `instances` instances of `count` vertices indexed by the buffer bound to
+    /// `ELEMENT_ARRAY_BUFFER`, starting from the byte offset `offset`.
+    ///
+    /// This is only called if [`supports_instancing`][Backend::supports_instancing] returns `true`.
+    fn draw_elements_instanced(&self, mode: u32, count: i32, ty: u32, offset: i32, instances: i32);
+
+    /// Sets the number of instances drawn before the vertex attribute at `index` advances,
+    /// or 0 to advance it per vertex.
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32) -> Result<(), DrawError>;
+
+    /// Allocates a new program.
+    fn create_program(&self) -> Option<Self::Program>;
+
+    /// Deletes `program`, which is deferred until it is no longer in use.
+    fn delete_program(&self, program: &Self::Program);
+
+    /// Allocates a new shader of type `ty`, e.g. `WebGlRenderingContext::VERTEX_SHADER`.
+    fn create_shader(&self, ty: u32) -> Option<Self::Shader>;
+
+    /// Deletes `shader`, which is deferred until it is detached from all programs.
+    fn delete_shader(&self, shader: &Self::Shader);
+
+    /// Replaces the source code of `shader`.
+    fn shader_source(&self, shader: &Self::Shader, source: &str);
+
+    /// The source code of `shader` as submitted to the driver.
+    fn get_shader_source(&self, shader: &Self::Shader) -> String;
+
+    /// Compiles `shader`.
+    fn compile_shader(&self, shader: &Self::Shader);
+
+    /// Whether the last compilation of `shader` succeeded.
+    fn shader_compile_status(&self, shader: &Self::Shader) -> bool;
+
+    /// The info log of the last compilation of `shader`.
+    fn shader_info_log(&self, shader: &Self::Shader) -> String;
+
+    /// Attaches `shader` to `program`.
+    fn attach_shader(&self, program: &Self::Program, shader: &Self::Shader);
+
+    /// Links `program` with its attached shaders.
+    fn link_program(&self, program: &Self::Program);
+
+    /// Whether the last link of `program` succeeded.
+    fn program_link_status(&self, program: &Self::Program) -> bool;
+
+    /// Validates `program` against the current state, returning whether it can execute.
+    fn validate_program(&self, program: &Self::Program) -> bool;
+
+    /// The info log of the last link or validation of `program`.
+    fn program_info_log(&self, program: &Self::Program) -> String;
+
+    /// Uses `program` for subsequent draw calls.
+    fn use_program(&self, program: Option<&Self::Program>);
+
+    /// The number of active uniforms in the linked `program`.
+    fn active_uniform_count(&self, program: &Self::Program) -> u32;
+
+    /// Describes the active uniform at `index` in `program`,
+    /// or `None` if `index` is out of range.
+    fn get_active_uniform(&self, program: &Self::Program, index: u32) -> Option<ActiveInfo>;
+
+    /// The number of active attributes in the linked `program`.
+    fn active_attrib_count(&self, program: &Self::Program) -> u32;
+
+    /// Describes the active attribute at `index` in `program`,
+    /// or `None` if `index` is out of range.
+    fn get_active_attrib(&self, program: &Self::Program, index: u32) -> Option<ActiveInfo>;
+
+    /// The location of the active attribute `name` in `program`,
+    /// or `None` if the program has no such attribute.
+    fn get_attrib_location(&self, program: &Self::Program, name: &str) -> Option<u32>;
+
+    /// Enables reading the vertex attribute at `index` from the bound buffer.
+    fn enable_vertex_attrib_array(&self, index: u32);
+
+    /// The location of the active uniform `name` in `program`,
+    /// or `None` if the program has no such uniform.
+    fn get_uniform_location(
+        &self,
+        program: &Self::Program,
+        name: &str,
+    ) -> Option<Self::UniformLocation>;
+
+    /// Assigns `value` to the uniform at `location` in the program in use.
+    fn uniform(&self, location: &Self::UniformLocation, value: UniformValue<'_>);
+
+    /// Whether fragment shaders support `highp` float precision.
+    fn fragment_highp_supported(&self) -> bool;
+
+    /// Whether the context is a WebGL 2 context.
+    fn is_webgl2(&self) -> bool;
+
+    /// Queries an integer parameter such as `WebGlRenderingContext::MAX_TEXTURE_SIZE`,
+    /// returning `None` if the query fails.
+    fn get_parameter_i32(&self, pname: u32) -> Option<i32>;
+
+    /// Queries a parameter with multiple integers such as `WebGlRenderingContext::MAX_VIEWPORT_DIMS`,
+    /// returning `None` if the query fails.
+    fn get_parameter_i32_array(&self, pname: u32) -> Option<Vec<i32>>;
+
+    /// The implementation limits of the context, queried once and cached.
+    fn limits(&self) -> &GlLimits;
+}
+
+impl Backend for Context {
+    type Buffer = WebGlBuffer;
+    type Program = WebGlProgram;
+    type Shader = WebGlShader;
+    type UniformLocation = WebGlUniformLocation;
+
+    fn create_buffer(&self) -> Option<WebGlBuffer> {
+        self.native.create_buffer()
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<&WebGlBuffer>) {
+        crate::__trace!(
+            "bindBuffer target=0x{:x} bound={}",
+            target,
+            buffer.is_some()
+        );
+        self.native.bind_buffer(target, buffer);
+    }
+
+    fn buffer_data_u8(&self, target: u32, data: &[u8], usage: u32) {
+        crate::__trace!("bufferData target=0x{:x} bytes={}", target, data.len());
+        self.native.buffer_data_with_u8_array(target, data, usage);
+    }
+
+    fn buffer_data_size(&self, target: u32, size: i32, usage: u32) {
+        crate::__trace!("bufferData target=0x{:x} size={}", target, size);
+        self.native.buffer_data_with_i32(target, size, usage);
+    }
+
+    fn buffer_data_u16(&self, target: u32, data: &[u16], usage: u32) {
+        crate::__trace!("bufferData target=0x{:x} u16s={}", target, data.len());
+        let array = Uint16Array::from(data);
+        self.native
+            .buffer_data_with_array_buffer_view(target, &array, usage);
+    }
+
+    fn buffer_data_u32(&self, target: u32, data: &[u32], usage: u32) {
+        crate::__trace!("bufferData target=0x{:x} u32s={}", target, data.len());
+        let array = Uint32Array::from(data);
+        self.native
+            .buffer_data_with_array_buffer_view(target, &array, usage);
+    }
+
+    fn buffer_sub_data_u8(&self, target: u32, offset: i32, data: &[u8]) {
+        crate::__trace!(
+            "bufferSubData target=0x{:x} offset={} bytes={}",
+            target,
+            offset,
+            data.len()
+        );
+        self.native
+            .buffer_sub_data_with_i32_and_u8_array(target, offset, data);
+    }
+
+    fn buffer_sub_data_u16(&self, target: u32, offset: i32, data: &[u16]) {
+        crate::__trace!(
+            "bufferSubData target=0x{:x} offset={} u16s={}",
+            target,
+            offset,
+            data.len()
+        );
+        let array = Uint16Array::from(data);
+        self.native
+            .buffer_sub_data_with_i32_and_array_buffer_view(target, offset, &array);
+    }
+
+    fn buffer_sub_data_u32(&self, target: u32, offset: i32, data: &[u32]) {
+        crate::__trace!(
+            "bufferSubData target=0x{:x} offset={} u32s={}",
+            target,
+            offset,
+            data.len()
+        );
+        let array = Uint32Array::from(data);
+        self.native
+            .buffer_sub_data_with_i32_and_array_buffer_view(target, offset, &array);
+    }
+
+    fn is_context_lost(&self) -> bool {
+        let lost = self.is_lost();
+        if lost {
+            // The bound program does not survive a context loss.
+            self.program_bound.set(false);
+        }
+        lost
+    }
+
+    fn has_current_program(&self) -> bool {
+        // Querying parameters may stall the pipeline,
+        // so only query if the program was not bound by willow.
+        self.program_bound.get()
+            || self
+                .native
+                .get_parameter(WebGlRenderingContext::CURRENT_PROGRAM)
+                .map_or(false, |program| !program.is_null())
+    }
+
+    fn enable_extension(&self, name: &str) -> bool {
+        self.get_extension(name).is_some()
+    }
+
+    fn supports_attrib_type(&self, ty: u32) -> bool {
+        ty != WebGl2RenderingContext::HALF_FLOAT || self.webgl2().is_some()
+    }
+
+    fn vertex_attrib_pointer(
+        &self,
+        index: u32,
+        size: i32,
+        ty: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    ) {
+        self.native
+            .vertex_attrib_pointer_with_i32(index, size, ty, normalized, stride, offset);
+    }
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        crate::__trace!(
+            "drawArrays mode=0x{:x} first={} count={}",
+            mode,
+            first,
+            count
+        );
+        self.record_draw(count, 1);
+        self.native.draw_arrays(mode, first, count);
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, ty: u32, offset: i32) {
+        crate::__trace!(
+            "drawElements mode=0x{:x} count={} type=0x{:x} offset={}",
+            mode,
+            count,
+            ty,
+            offset
+        );
+        self.record_draw(count, 1);
+        self.native.draw_elements_with_i32(mode, count, ty, offset);
+    }
+
+    fn supports_instancing(&self) -> bool {
+        Context::supports_instancing(self)
+    }
+
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32) {
+        crate::__trace!(
+            "drawArraysInstanced mode=0x{:x} first={} count={} instances={}",
+            mode,
+            first,
+            count,
+            instances
+        );
+        self.record_draw(count, instances);
+        self.instancing()
+            .expect("Instancing is not supported")
+            .draw_arrays_instanced(mode, first, count, instances);
+    }
+
+    fn draw_elements_instanced(&self, mode: u32, count: i32, ty: u32, offset: i32, instances: i32) {
+        crate::__trace!(
+            "drawElementsInstanced mode=0x{:x} count={} type=0x{:x} offset={} instances={}",
+            mode,
+            count,
+            ty,
+            offset,
+            instances
+        );
+        self.record_draw(count, instances);
+        self.instancing()
+            .expect("Instancing is not supported")
+            .draw_elements_instanced(mode, count, ty, offset, instances);
+    }
+
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32) -> Result<(), DrawError> {
+        Context::vertex_attrib_divisor(self, index, divisor)
+    }
+
+    fn create_program(&self) -> Option<WebGlProgram> {
+        self.native.create_program()
+    }
+
+    fn delete_program(&self, program: &WebGlProgram) {
+        self.native.delete_program(Some(program));
+    }
+
+    fn create_shader(&self, ty: u32) -> Option<WebGlShader> {
+        self.native.create_shader(ty)
+    }
+
+    fn delete_shader(&self, shader: &WebGlShader) {
+        self.native.delete_shader(Some(shader));
+    }
+
+    fn shader_source(&self, shader: &WebGlShader, source: &str) {
+        self.native.shader_source(shader, source);
+    }
+
+    fn get_shader_source(&self, shader: &WebGlShader) -> String {
+        self.native.get_shader_source(shader).unwrap_or_default()
+    }
+
+    fn compile_shader(&self, shader: &WebGlShader) {
+        self.native.compile_shader(shader);
+    }
+
+    fn shader_compile_status(&self, shader: &WebGlShader) -> bool {
+        self.native
+            .get_shader_parameter(shader, WebGlRenderingContext::COMPILE_STATUS)
+            .is_truthy()
+    }
+
+    fn shader_info_log(&self, shader: &WebGlShader) -> String {
+        self.native.get_shader_info_log(shader).unwrap_or_default()
+    }
+
+    fn attach_shader(&self, program: &WebGlProgram, shader: &WebGlShader) {
+        self.native.attach_shader(program, shader);
+    }
+
+    fn link_program(&self, program: &WebGlProgram) {
+        self.native.link_program(program);
+    }
+
+    fn program_link_status(&self, program: &WebGlProgram) -> bool {
+        self.native
+            .get_program_parameter(program, WebGlRenderingContext::LINK_STATUS)
+            .is_truthy()
+    }
+
+    fn validate_program(&self, program: &WebGlProgram) -> bool {
+        self.native.validate_program(program);
+        self.native
+            .get_program_parameter(program, WebGlRenderingContext::VALIDATE_STATUS)
+            .is_truthy()
+    }
+
+    fn program_info_log(&self, program: &WebGlProgram) -> String {
+        self.native
+            .get_program_info_log(program)
+            .unwrap_or_default()
+    }
+
+    fn use_program(&self, program: Option<&WebGlProgram>) {
+        self.native.use_program(program);
+        self.program_bound.set(program.is_some());
+    }
+
+    fn active_uniform_count(&self, program: &WebGlProgram) -> u32 {
+        self.native
+            .get_program_parameter(program, WebGlRenderingContext::ACTIVE_UNIFORMS)
+            .as_f64()
+            .unwrap_or(0.) as u32
+    }
+
+    fn get_active_uniform(&self, program: &WebGlProgram, index: u32) -> Option<ActiveInfo> {
+        self.native
+            .get_active_uniform(program, index)
+            .map(ActiveInfo::from)
+    }
+
+    fn active_attrib_count(&self, program: &WebGlProgram) -> u32 {
+        self.native
+            .get_program_parameter(program, WebGlRenderingContext::ACTIVE_ATTRIBUTES)
+            .as_f64()
+            .unwrap_or(0.) as u32
+    }
+
+    fn get_active_attrib(&self, program: &WebGlProgram, index: u32) -> Option<ActiveInfo> {
+        self.native
+            .get_active_attrib(program, index)
+            .map(ActiveInfo::from)
+    }
+
+    fn get_attrib_location(&self, program: &WebGlProgram, name: &str) -> Option<u32> {
+        let location = self.native.get_attrib_location(program, name);
+        if location >= 0 {
+            Some(location as u32)
+        } else {
+            None
+        }
+    }
+
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        self.native.enable_vertex_attrib_array(index);
+    }
+
+    fn get_uniform_location(
+        &self,
+        program: &WebGlProgram,
+        name: &str,
+    ) -> Option<WebGlUniformLocation> {
+        self.native.get_uniform_location(program, name)
+    }
+
+    fn uniform(&self, location: &WebGlUniformLocation, value: UniformValue<'_>) {
+        let gl = &self.native;
+        let location = Some(location);
+        match value {
+            UniformValue::Int { size, data } => match size {
+                VectorSize::One => gl.uniform1iv_with_i32_array(location, &data),
+                VectorSize::Two => gl.uniform2iv_with_i32_array(location, &data),
+                VectorSize::Three => gl.uniform3iv_with_i32_array(location, &data),
+                VectorSize::Four => gl.uniform4iv_with_i32_array(location, &data),
+            },
+            UniformValue::Float { size, data } => match size {
+                VectorSize::One => gl.uniform1fv_with_f32_array(location, &data),
+                VectorSize::Two => gl.uniform2fv_with_f32_array(location, &data),
+                VectorSize::Three => gl.uniform3fv_with_f32_array(location, &data),
+                VectorSize::Four => gl.uniform4fv_with_f32_array(location, &data),
+            },
+            UniformValue::Matrix { shape, data } => {
+                // Programs with uniforms of non-square matrices fail to create in WebGL 1,
+                // so this only fails if `Backend::uniform` is called directly.
+                let webgl2 = || {
+                    let gl2 = self.webgl2();
+                    if gl2.is_none() {
+                        log::error!(
+                            "Skipping mat{}x{} uniform, which requires WebGL 2",
+                            shape.columns(),
+                            shape.rows()
+                        );
+                    }
+                    gl2
+                };
+                match shape {
+                    MatrixShape::Mat2 => {
+                        gl.uniform_matrix2fv_with_f32_array(location, false, &data)
+                    }
+                    MatrixShape::Mat3 => {
+                        gl.uniform_matrix3fv_with_f32_array(location, false, &data)
+                    }
+                    MatrixShape::Mat4 => {
+                        gl.uniform_matrix4fv_with_f32_array(location, false, &data)
+                    }
+                    MatrixShape::Mat2x3 => {
+                        if let Some(gl2) = webgl2() {
+                            gl2.uniform_matrix2x3fv_with_f32_array(location, false, &data);
+                        }
+                    }
+                    MatrixShape::Mat2x4 => {
+                        if let Some(gl2) = webgl2() {
+                            gl2.uniform_matrix2x4fv_with_f32_array(location, false, &data);
+                        }
+                    }
+                    MatrixShape::Mat3x2 => {
+                        if let Some(gl2) = webgl2() {
+                            gl2.uniform_matrix3x2fv_with_f32_array(location, false, &data);
+                        }
+                    }
+                    MatrixShape::Mat3x4 => {
+                        if let Some(gl2) = webgl2() {
+                            gl2.uniform_matrix3x4fv_with_f32_array(location, false, &data);
+                        }
+                    }
+                    MatrixShape::Mat4x2 => {
+                        if let Some(gl2) = webgl2() {
+                            gl2.uniform_matrix4x2fv_with_f32_array(location, false, &data);
+                        }
+                    }
+                    MatrixShape::Mat4x3 => {
+                        if let Some(gl2) = webgl2() {
+                            gl2.uniform_matrix4x3fv_with_f32_array(location, false, &data);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn fragment_highp_supported(&self) -> bool {
+        Context::fragment_highp_supported(self)
+    }
+
+    fn is_webgl2(&self) -> bool {
+        self.webgl2().is_some()
+    }
+
+    fn get_parameter_i32(&self, pname: u32) -> Option<i32> {
+        let value = self.native.get_parameter(pname).ok()?.as_f64()?;
+        Some(value as i32)
+    }
+
+    fn get_parameter_i32_array(&self, pname: u32) -> Option<Vec<i32>> {
+        let value = self.native.get_parameter(pname).ok()?;
+        if value.is_null() || value.is_undefined() {
+            return None;
+        }
+        Some(js_sys::Int32Array::from(value).to_vec())
+    }
+
+    fn limits(&self) -> &GlLimits {
+        Context::limits(self)
+    }
+}
@@ -0,0 +1,278 @@
+use anyhow::{bail, Result};
+use web_sys::WebGlRenderingContext;
+
+use crate::Context;
+
+/// A factor in the blend function.
+///
+/// Corresponds to the [`sfactor`/`dfactor` parameters in `blendFunc`][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/blendFunc#parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendFactor {
+    /// Multiplies all colors by 0.
+    Zero,
+    /// Multiplies all colors by 1.
+    One,
+    /// Multiplies all colors by the source colors.
+    SrcColor,
+    /// Multiplies all colors by 1 minus each source color.
+    OneMinusSrcColor,
+    /// Multiplies all colors by the destination color.
+    DstColor,
+    /// Multiplies all colors by 1 minus each destination color.
+    OneMinusDstColor,
+    /// Multiplies all colors by the source alpha value.
+    SrcAlpha,
+    /// Multiplies all colors by 1 minus the source alpha value.
+    OneMinusSrcAlpha,
+    /// Multiplies all colors by the destination alpha value.
+    DstAlpha,
+    /// Multiplies all colors by 1 minus the destination alpha value.
+    OneMinusDstAlpha,
+    /// Multiplies all colors by the constant color in [`Blend::color`][Blend::color].
+    ConstantColor,
+    /// Multiplies all colors by 1 minus the constant color in [`Blend::color`][Blend::color].
+    OneMinusConstantColor,
+    /// Multiplies all colors by the constant alpha in [`Blend::color`][Blend::color].
+    ConstantAlpha,
+    /// Multiplies all colors by 1 minus the constant alpha in [`Blend::color`][Blend::color].
+    OneMinusConstantAlpha,
+    /// Multiplies the RGB colors by the smaller of the source alpha
+    /// and 1 minus the destination alpha.
+    SrcAlphaSaturate,
+}
+
+impl BlendFactor {
+    fn to_const(self) -> u32 {
+        match self {
+            Self::Zero => WebGlRenderingContext::ZERO,
+            Self::One => WebGlRenderingContext::ONE,
+            Self::SrcColor => WebGlRenderingContext::SRC_COLOR,
+            Self::OneMinusSrcColor => WebGlRenderingContext::ONE_MINUS_SRC_COLOR,
+            Self::DstColor => WebGlRenderingContext::DST_COLOR,
+            Self::OneMinusDstColor => WebGlRenderingContext::ONE_MINUS_DST_COLOR,
+            Self::SrcAlpha => WebGlRenderingContext::SRC_ALPHA,
+            Self::OneMinusSrcAlpha => WebGlRenderingContext::ONE_MINUS_SRC_ALPHA,
+            Self::DstAlpha => WebGlRenderingContext::DST_ALPHA,
+            Self::OneMinusDstAlpha => WebGlRenderingContext::ONE_MINUS_DST_ALPHA,
+            Self::ConstantColor => WebGlRenderingContext::CONSTANT_COLOR,
+            Self::OneMinusConstantColor => WebGlRenderingContext::ONE_MINUS_CONSTANT_COLOR,
+            Self::ConstantAlpha => WebGlRenderingContext::CONSTANT_ALPHA,
+            Self::OneMinusConstantAlpha => WebGlRenderingContext::ONE_MINUS_CONSTANT_ALPHA,
+            Self::SrcAlphaSaturate => WebGlRenderingContext::SRC_ALPHA_SATURATE,
+        }
+    }
+
+    /// Whether this factor references the constant blend color.
+    pub fn uses_constant(self) -> bool {
+        matches!(
+            self,
+            Self::ConstantColor
+                | Self::OneMinusConstantColor
+                | Self::ConstantAlpha
+                | Self::OneMinusConstantAlpha
+        )
+    }
+}
+
+/// The equation used to combine the source and destination colors.
+///
+/// Corresponds to the [`mode` parameter in `blendEquation`][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/blendEquation#parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendEquation {
+    /// `source + destination`
+    Add,
+    /// `source - destination`
+    Subtract,
+    /// `destination - source`
+    ReverseSubtract,
+}
+
+impl BlendEquation {
+    fn to_const(self) -> u32 {
+        match self {
+            Self::Add => WebGlRenderingContext::FUNC_ADD,
+            Self::Subtract => WebGlRenderingContext::FUNC_SUBTRACT,
+            Self::ReverseSubtract => WebGlRenderingContext::FUNC_REVERSE_SUBTRACT,
+        }
+    }
+}
+
+/// Parameters for [`Context::set_blend`][Context::set_blend].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blend {
+    /// The factor applied on the source RGB values.
+    pub src_rgb: BlendFactor,
+    /// The factor applied on the destination RGB values.
+    pub dst_rgb: BlendFactor,
+    /// The factor applied on the source alpha value.
+    pub src_alpha: BlendFactor,
+    /// The factor applied on the destination alpha value.
+    pub dst_alpha: BlendFactor,
+    /// The equation combining the source and destination RGB values.
+    pub equation_rgb: BlendEquation,
+    /// The equation combining the source and destination alpha values.
+    pub equation_alpha: BlendEquation,
+    /// The RGBA values (in the range `[0, 1]`) of the constant blend color.
+    ///
+    /// This must be set if any factor is a `Constant*` factor.
+    pub color: Option<[f32; 4]>,
+}
+
+impl Blend {
+    /// Creates a blend function applying the same factors on the RGB and alpha values.
+    pub fn new(src: BlendFactor, dst: BlendFactor) -> Self {
+        Self {
+            src_rgb: src,
+            dst_rgb: dst,
+            src_alpha: src,
+            dst_alpha: dst,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the constant blend color.
+    pub fn with_color(self, color: [f32; 4]) -> Self {
+        Self {
+            color: Some(color),
+            ..self
+        }
+    }
+
+    /// The blend function for the accumulation pass of weighted blended order-independent transparency.
+    ///
+    /// See [`OitPass`][OitPass] for the full recipe.
+    pub fn oit_accumulation() -> Self {
+        Self::new(BlendFactor::One, BlendFactor::One)
+    }
+
+    /// The blend function for the revealage pass of weighted blended order-independent transparency.
+    ///
+    /// See [`OitPass`][OitPass] for the full recipe.
+    pub fn oit_revealage() -> Self {
+        Self::new(BlendFactor::Zero, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    /// The blend function for compositing the result of
+    /// weighted blended order-independent transparency onto the opaque scene.
+    ///
+    /// See [`OitPass`][OitPass] for the full recipe.
+    pub fn oit_composite() -> Self {
+        Self::new(BlendFactor::OneMinusSrcAlpha, BlendFactor::SrcAlpha)
+    }
+
+    fn uses_constant(&self) -> bool {
+        [self.src_rgb, self.dst_rgb, self.src_alpha, self.dst_alpha]
+            .iter()
+            .any(|factor| factor.uses_constant())
+    }
+}
+
+impl Default for Blend {
+    /// The initial blend function of WebGL,
+    /// which replaces the destination with the source.
+    fn default() -> Self {
+        Self {
+            src_rgb: BlendFactor::One,
+            dst_rgb: BlendFactor::Zero,
+            src_alpha: BlendFactor::One,
+            dst_alpha: BlendFactor::Zero,
+            equation_rgb: BlendEquation::Add,
+            equation_alpha: BlendEquation::Add,
+            color: None,
+        }
+    }
+}
+
+/// A pass of [weighted blended order-independent transparency][paper],
+/// used with [`Context::set_oit_blend`][Context::set_oit_blend].
+///
+/// Transparent surfaces are drawn in any order into two render targets,
+/// then composited onto the opaque scene:
+///
+/// 1. [`Accumulation`][OitPass::Accumulation]: draw all transparent surfaces
+///    into an RGBA float target cleared to `[0, 0, 0, 0]`,
+///    with the fragment shader writing `vec4(color.rgb * color.a, color.a) * weight`.
+/// 2. [`Revealage`][OitPass::Revealage]: draw all transparent surfaces again
+///    into a target cleared to `[1, 1, 1, 1]`,
+///    with the fragment shader writing `vec4(color.a)`.
+///    With WebGL 2 draw buffers, this can be merged into the accumulation pass.
+/// 3. [`Composite`][OitPass::Composite]: draw a fullscreen quad onto the opaque scene,
+///    with the fragment shader writing `vec4(accum.rgb / max(accum.a, 1e-5), revealage)`,
+///    where `accum` and `revealage` are sampled from the two targets.
+///
+/// `weight` is a function decreasing with depth, e.g. `clamp(0.03 / (1e-5 + pow(z / 200.0, 4.0)), 0.01, 3000.0)`.
+/// Depth writes should be disabled in the first two passes.
+///
+/// [paper]: https://jcgt.org/published/0002/02/09/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OitPass {
+    /// Sums the weighted premultiplied colors. Uses [`Blend::oit_accumulation`][Blend::oit_accumulation].
+    Accumulation,
+    /// Multiplies `1 - alpha` of all surfaces. Uses [`Blend::oit_revealage`][Blend::oit_revealage].
+    Revealage,
+    /// Composites the result onto the opaque scene. Uses [`Blend::oit_composite`][Blend::oit_composite].
+    Composite,
+}
+
+impl OitPass {
+    /// The blend function used in this pass.
+    pub fn blend(self) -> Blend {
+        match self {
+            Self::Accumulation => Blend::oit_accumulation(),
+            Self::Revealage => Blend::oit_revealage(),
+            Self::Composite => Blend::oit_composite(),
+        }
+    }
+}
+
+impl Context {
+    /// Enables blending with the preset for a pass of weighted blended order-independent transparency.
+    ///
+    /// This is equivalent to `set_blend(Some(pass.blend()))`.
+    pub fn set_oit_blend(&self, pass: OitPass) {
+        self.set_blend(Some(pass.blend()))
+            .expect("OIT blend presets do not use the constant color");
+    }
+
+    /// Enables blending with the specified parameters,
+    /// or disables blending if `blend` is `None`.
+    ///
+    /// Returns an error if a `Constant*` factor is used without setting
+    /// [`Blend::color`][Blend::color].
+    pub fn set_blend(&self, blend: Option<Blend>) -> Result<()> {
+        let gl = &self.native;
+
+        let blend = match blend {
+            Some(blend) => blend,
+            None => {
+                gl.disable(WebGlRenderingContext::BLEND);
+                return Ok(());
+            }
+        };
+
+        if blend.uses_constant() && blend.color.is_none() {
+            bail!("Blend factors reference the constant color, but no color is set");
+        }
+
+        gl.enable(WebGlRenderingContext::BLEND);
+        gl.blend_func_separate(
+            blend.src_rgb.to_const(),
+            blend.dst_rgb.to_const(),
+            blend.src_alpha.to_const(),
+            blend.dst_alpha.to_const(),
+        );
+        gl.blend_equation_separate(
+            blend.equation_rgb.to_const(),
+            blend.equation_alpha.to_const(),
+        );
+        if let Some([r, g, b, a]) = blend.color {
+            gl.blend_color(r, g, b, a);
+        }
+
+        Ok(())
+    }
+}
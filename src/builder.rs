@@ -0,0 +1,321 @@
+use std::cell::{Cell, RefCell};
+
+use anyhow::{Context as _, Result};
+use js_sys::{Object, Reflect};
+use once_cell::unsync::OnceCell;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{HtmlCanvasElement, OffscreenCanvas, WebGl2RenderingContext};
+
+use crate::{AspectFix, Context, InstancingCache};
+
+/// Attributes of the drawing buffer, requested when the context is created.
+///
+/// Corresponds to the [`contextAttributes` parameter in `getContext`][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/HTMLCanvasElement/getContext#parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextAttributes {
+    /// Whether the canvas contains an alpha buffer.
+    pub alpha: bool,
+    /// Whether the drawing buffer has a depth buffer of at least 16 bits.
+    pub depth: bool,
+    /// Whether the drawing buffer has a stencil buffer of at least 8 bits.
+    pub stencil: bool,
+    /// Whether to perform anti-aliasing if possible.
+    pub antialias: bool,
+    /// Whether the page compositor assumes the drawing buffer contains premultiplied alpha.
+    pub premultiplied_alpha: bool,
+    /// Whether the drawing buffer is preserved until cleared or overwritten.
+    pub preserve_drawing_buffer: bool,
+    /// Which GPU the browser should prefer on systems with multiple GPUs.
+    pub power_preference: PowerPreference,
+    /// Whether context creation fails if the system performance is low,
+    /// e.g. if the browser would fall back to software rendering.
+    pub fail_if_major_performance_caveat: bool,
+}
+
+impl Default for ContextAttributes {
+    /// The default attributes used by browsers.
+    fn default() -> Self {
+        Self {
+            alpha: true,
+            depth: true,
+            stencil: false,
+            antialias: true,
+            premultiplied_alpha: true,
+            preserve_drawing_buffer: false,
+            power_preference: PowerPreference::Default,
+            fail_if_major_performance_caveat: false,
+        }
+    }
+}
+
+impl ContextAttributes {
+    fn to_js(self) -> Object {
+        let object = Object::new();
+        for &(key, value) in &[
+            ("alpha", self.alpha),
+            ("depth", self.depth),
+            ("stencil", self.stencil),
+            ("antialias", self.antialias),
+            ("premultipliedAlpha", self.premultiplied_alpha),
+            ("preserveDrawingBuffer", self.preserve_drawing_buffer),
+            (
+                "failIfMajorPerformanceCaveat",
+                self.fail_if_major_performance_caveat,
+            ),
+        ] {
+            Reflect::set(&object, &JsValue::from_str(key), &JsValue::from_bool(value))
+                .expect("Setting a property on a plain object should not fail");
+        }
+        Reflect::set(
+            &object,
+            &JsValue::from_str("powerPreference"),
+            &JsValue::from_str(self.power_preference.to_str()),
+        )
+        .expect("Setting a property on a plain object should not fail");
+        object
+    }
+}
+
+/// A hint to the browser about which GPU to use for the context.
+///
+/// Corresponds to the [`powerPreference` context attribute][mdn].
+/// The browser may ignore the hint, e.g. on systems with a single GPU.
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/HTMLCanvasElement/getContext#powerpreference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerPreference {
+    /// Lets the browser decide, which usually selects the integrated GPU on laptops.
+    Default,
+    /// Prefers the discrete GPU for rendering performance,
+    /// at the cost of higher power consumption and battery drain.
+    HighPerformance,
+    /// Prefers the integrated GPU to save power,
+    /// which is usually sufficient for simple scenes.
+    LowPower,
+}
+
+impl PowerPreference {
+    fn to_str(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::HighPerformance => "high-performance",
+            Self::LowPower => "low-power",
+        }
+    }
+}
+
+enum Canvas {
+    Element(web_sys::Element, AspectFix),
+    Offscreen(OffscreenCanvas, f32),
+}
+
+/// The canvas that a context was created on.
+pub(crate) enum ContextCanvas {
+    Element(HtmlCanvasElement),
+    Offscreen(OffscreenCanvas),
+}
+
+/// A builder for [`Context`][Context],
+/// created from [`Context::builder`][Context::builder]
+/// or [`Context::offscreen_builder`][Context::offscreen_builder].
+#[must_use = "Call build() to create the context"]
+pub struct ContextBuilder {
+    canvas: Canvas,
+    webgl2: bool,
+    attributes: ContextAttributes,
+    extensions: Vec<String>,
+}
+
+impl Context {
+    /// Creates a builder for a context on the canvas element.
+    pub fn builder(canvas: web_sys::Element) -> ContextBuilder {
+        ContextBuilder::new(Canvas::Element(canvas, AspectFix::None))
+    }
+
+    /// Creates a builder for a context on an `OffscreenCanvas`.
+    ///
+    /// See [`from_offscreen_canvas`][Context::from_offscreen_canvas] for details.
+    pub fn offscreen_builder(canvas: OffscreenCanvas, aspect: f32) -> ContextBuilder {
+        ContextBuilder::new(Canvas::Offscreen(canvas, aspect))
+    }
+}
+
+impl ContextBuilder {
+    fn new(canvas: Canvas) -> Self {
+        Self {
+            canvas,
+            webgl2: false,
+            attributes: ContextAttributes::default(),
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Creates a WebGL 2 context instead of a WebGL 1 context.
+    ///
+    /// [`build`][ContextBuilder::build] fails if the browser does not support WebGL 2.
+    pub fn webgl2(mut self) -> Self {
+        self.webgl2 = true;
+        self
+    }
+
+    /// Sets the policy for correcting the canvas size.
+    ///
+    /// This has no effect on offscreen canvases.
+    pub fn aspect(mut self, aspect_fix: AspectFix) -> Self {
+        if let Canvas::Element(_, fix) = &mut self.canvas {
+            *fix = aspect_fix;
+        }
+        self
+    }
+
+    /// Replaces all context attributes.
+    pub fn attributes(mut self, attributes: ContextAttributes) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Sets [`ContextAttributes::alpha`][ContextAttributes::alpha].
+    pub fn alpha(mut self, alpha: bool) -> Self {
+        self.attributes.alpha = alpha;
+        self
+    }
+
+    /// Sets [`ContextAttributes::depth`][ContextAttributes::depth].
+    pub fn depth(mut self, depth: bool) -> Self {
+        self.attributes.depth = depth;
+        self
+    }
+
+    /// Sets [`ContextAttributes::stencil`][ContextAttributes::stencil].
+    pub fn stencil(mut self, stencil: bool) -> Self {
+        self.attributes.stencil = stencil;
+        self
+    }
+
+    /// Sets [`ContextAttributes::antialias`][ContextAttributes::antialias].
+    pub fn antialias(mut self, antialias: bool) -> Self {
+        self.attributes.antialias = antialias;
+        self
+    }
+
+    /// Sets [`ContextAttributes::premultiplied_alpha`][ContextAttributes::premultiplied_alpha].
+    pub fn premultiplied_alpha(mut self, premultiplied_alpha: bool) -> Self {
+        self.attributes.premultiplied_alpha = premultiplied_alpha;
+        self
+    }
+
+    /// Sets [`ContextAttributes::preserve_drawing_buffer`][ContextAttributes::preserve_drawing_buffer].
+    pub fn preserve_drawing_buffer(mut self, preserve_drawing_buffer: bool) -> Self {
+        self.attributes.preserve_drawing_buffer = preserve_drawing_buffer;
+        self
+    }
+
+    /// Sets [`ContextAttributes::power_preference`][ContextAttributes::power_preference].
+    ///
+    /// Games and other performance-sensitive applications may request
+    /// [`HighPerformance`][PowerPreference::HighPerformance],
+    /// but switching to the discrete GPU may take some time and drains the battery faster.
+    pub fn power_preference(mut self, power_preference: PowerPreference) -> Self {
+        self.attributes.power_preference = power_preference;
+        self
+    }
+
+    /// Sets [`ContextAttributes::fail_if_major_performance_caveat`][ContextAttributes::fail_if_major_performance_caveat].
+    ///
+    /// If set, [`build`][ContextBuilder::build] fails instead of creating a slow context,
+    /// so that the application can fall back to a simpler renderer.
+    pub fn fail_if_major_performance_caveat(mut self, fail: bool) -> Self {
+        self.attributes.fail_if_major_performance_caveat = fail;
+        self
+    }
+
+    /// Requires the extension with the given name.
+    ///
+    /// [`build`][ContextBuilder::build] fails if the extension is not supported.
+    /// The extension object is cached in the context and can be retrieved with
+    /// [`Context::get_extension`][Context::get_extension].
+    pub fn require_extension(mut self, name: &str) -> Self {
+        self.extensions.push(name.to_string());
+        self
+    }
+
+    /// Creates the context.
+    pub fn build(self) -> Result<Context> {
+        let context_type = if self.webgl2 { "webgl2" } else { "webgl" };
+        let options = self.attributes.to_js();
+
+        let (object, aspect, canvas) = match self.canvas {
+            Canvas::Element(canvas, aspect_fix) => {
+                let canvas = canvas
+                    .dyn_into::<HtmlCanvasElement>()
+                    .ok()
+                    .context("The element is not a <canvas>")?;
+                let aspect = canvas.client_width() as f32 / canvas.client_height() as f32;
+
+                match aspect_fix {
+                    AspectFix::None => (),
+                    AspectFix::FromWidth => {
+                        canvas.set_height((canvas.width() as f32 / aspect) as u32)
+                    }
+                    AspectFix::FromHeight => {
+                        canvas.set_width((canvas.height() as f32 * aspect) as u32)
+                    }
+                }
+
+                (
+                    canvas.get_context_with_context_options(context_type, &options),
+                    aspect,
+                    ContextCanvas::Element(canvas),
+                )
+            }
+            Canvas::Offscreen(canvas, aspect) => (
+                canvas.get_context_with_context_options(context_type, &options),
+                aspect,
+                ContextCanvas::Offscreen(canvas),
+            ),
+        };
+
+        let object = object
+            .ok()
+            .flatten()
+            .with_context(|| format!("Could not initialize {} context", context_type))?;
+
+        let (native, webgl2) = if self.webgl2 {
+            let webgl2 = object
+                .dyn_into::<WebGl2RenderingContext>()
+                .ok()
+                .context("WebGL 2 context has an incorrect type")?;
+            // WebGL 2 contexts support all methods of WebGL 1 contexts.
+            (webgl2.clone().unchecked_into(), Some(webgl2))
+        } else {
+            let native = object
+                .dyn_into()
+                .ok()
+                .context("WebGL context has an incorrect type")?;
+            (native, None)
+        };
+
+        let context = Context {
+            native,
+            webgl2,
+            canvas,
+            aspect,
+            attributes: self.attributes,
+            extensions: RefCell::default(),
+            instancing: InstancingCache::default(),
+            limits: OnceCell::new(),
+            stats: Cell::default(),
+            program_bound: Cell::new(false),
+        };
+
+        for name in &self.extensions {
+            context
+                .get_extension(name)
+                .with_context(|| format!("Required extension {} is not supported", name))?;
+        }
+
+        Ok(context)
+    }
+}
@@ -0,0 +1,68 @@
+use web_sys::WebGlRenderingContext;
+
+use crate::Context;
+
+/// A capability that can be enabled or disabled in a context.
+///
+/// Corresponds to the [`cap` parameter in `enable`][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/enable#parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Blends the fragment colors with the colors in the color buffer.
+    ///
+    /// Prefer [`Context::set_blend`][Context::set_blend], which also sets the blend function.
+    Blend,
+    /// Culls polygons based on their winding.
+    CullFace,
+    /// Compares the fragment depth with the depth buffer.
+    DepthTest,
+    /// Dithers color components before writing them to the color buffer.
+    /// Enabled by default.
+    Dither,
+    /// Adds an offset to the depth of polygon fragments.
+    PolygonOffsetFill,
+    /// Computes a temporary coverage value from the fragment alpha value.
+    SampleAlphaToCoverage,
+    /// ANDs the fragment coverage with the temporary coverage value.
+    SampleCoverage,
+    /// Discards fragments outside the scissor box.
+    ///
+    /// Prefer [`Context::set_scissor`][Context::set_scissor], which also sets the scissor box.
+    ScissorTest,
+    /// Compares the fragment with the stencil buffer.
+    StencilTest,
+}
+
+impl Capability {
+    fn to_const(self) -> u32 {
+        match self {
+            Self::Blend => WebGlRenderingContext::BLEND,
+            Self::CullFace => WebGlRenderingContext::CULL_FACE,
+            Self::DepthTest => WebGlRenderingContext::DEPTH_TEST,
+            Self::Dither => WebGlRenderingContext::DITHER,
+            Self::PolygonOffsetFill => WebGlRenderingContext::POLYGON_OFFSET_FILL,
+            Self::SampleAlphaToCoverage => WebGlRenderingContext::SAMPLE_ALPHA_TO_COVERAGE,
+            Self::SampleCoverage => WebGlRenderingContext::SAMPLE_COVERAGE,
+            Self::ScissorTest => WebGlRenderingContext::SCISSOR_TEST,
+            Self::StencilTest => WebGlRenderingContext::STENCIL_TEST,
+        }
+    }
+}
+
+impl Context {
+    /// Enables or disables a capability.
+    pub fn set_capability(&self, capability: Capability, enabled: bool) {
+        crate::__trace!("setCapability {:?} {}", capability, enabled);
+        if enabled {
+            self.native.enable(capability.to_const());
+        } else {
+            self.native.disable(capability.to_const());
+        }
+    }
+
+    /// Whether a capability is enabled.
+    pub fn is_enabled(&self, capability: Capability) -> bool {
+        self.native.is_enabled(capability.to_const())
+    }
+}
@@ -0,0 +1,71 @@
+use web_sys::WebGlRenderingContext;
+
+use crate::Context;
+
+/// The function comparing the depth of a fragment with the value in the depth buffer.
+///
+/// Corresponds to the [`func` parameter in `depthFunc`][mdn].
+/// The fragment passes the depth test if the function returns true
+/// for the fragment depth compared with the stored depth.
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/depthFunc#parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthFunc {
+    /// Never passes.
+    Never,
+    /// Passes if the fragment depth is less than the stored depth.
+    /// This is the default.
+    Less,
+    /// Passes if the fragment depth is equal to the stored depth.
+    Equal,
+    /// Passes if the fragment depth is less than or equal to the stored depth.
+    LEqual,
+    /// Passes if the fragment depth is greater than the stored depth.
+    Greater,
+    /// Passes if the fragment depth is not equal to the stored depth.
+    NotEqual,
+    /// Passes if the fragment depth is greater than or equal to the stored depth.
+    GEqual,
+    /// Always passes.
+    Always,
+}
+
+impl DepthFunc {
+    fn to_const(self) -> u32 {
+        match self {
+            Self::Never => WebGlRenderingContext::NEVER,
+            Self::Less => WebGlRenderingContext::LESS,
+            Self::Equal => WebGlRenderingContext::EQUAL,
+            Self::LEqual => WebGlRenderingContext::LEQUAL,
+            Self::Greater => WebGlRenderingContext::GREATER,
+            Self::NotEqual => WebGlRenderingContext::NOTEQUAL,
+            Self::GEqual => WebGlRenderingContext::GEQUAL,
+            Self::Always => WebGlRenderingContext::ALWAYS,
+        }
+    }
+}
+
+impl Context {
+    /// Sets the function used for the depth test.
+    ///
+    /// This has no effect unless [`Capability::DepthTest`][crate::Capability::DepthTest]
+    /// is enabled.
+    pub fn set_depth_func(&self, func: DepthFunc) {
+        crate::__trace!("depthFunc {:?}", func);
+        self.native.depth_func(func.to_const());
+    }
+
+    /// Enables or disables writing to the depth buffer.
+    ///
+    /// Disabling depth writes while keeping [`Capability::DepthTest`][crate::Capability::DepthTest]
+    /// enabled is common for sorted transparent objects,
+    /// which are occluded by opaque objects but do not occlude each other.
+    ///
+    /// The mask also applies to [`Context::clear`][Context::clear]:
+    /// the depth buffer is not cleared while depth writes are disabled,
+    /// so re-enable them before clearing the depth of the next frame.
+    pub fn set_depth_mask(&self, enabled: bool) {
+        crate::__trace!("depthMask {}", enabled);
+        self.native.depth_mask(enabled);
+    }
+}
@@ -0,0 +1,164 @@
+//! Development helpers for iterating on shaders without rebuilding the application.
+//!
+//! This module is only available with the `dev` feature,
+//! which should be disabled in production builds.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::{anyhow, bail, Context as _, Error, Result};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Response;
+
+type Sources = (String, String);
+
+/// Polls the shader sources of a program from a dev server
+/// and reloads the program when they change.
+///
+/// ```ignore
+/// let mut reloader = HotReloader::new("/shaders/foo.vert", "/shaders/foo.frag")
+///     .initial_sources(include_str!("foo.vert"), include_str!("foo.frag"))
+///     .on_error(|err| log::error!("Shader reload failed: {:?}", err));
+///
+/// // in the render loop
+/// reloader.poll(|vert, frag| program.reload(&context, vert, frag));
+/// ```
+///
+/// The sources are fetched at most once per [`interval`][HotReloader::interval].
+/// This only throttles the fetches and does not wait for the files to stop changing,
+/// so a save in the middle of a burst of saves may be reloaded before the last one.
+/// The `reload` callback passed to [`poll`][HotReloader::poll]
+/// is only called with sources different from the previous call.
+///
+/// The first fetched sources are compared with the sources set by
+/// [`initial_sources`][HotReloader::initial_sources],
+/// which should be the sources the program was created with.
+/// If they are not set, the first fetched sources are assumed to be the same
+/// and are not reloaded.
+///
+/// The `reload` method derived by the [`Program`][crate::Program] macro
+/// only replaces the program if the new shaders compile and link,
+/// so the previous program stays active until the error is fixed.
+pub struct HotReloader {
+    vert_url: String,
+    frag_url: String,
+    interval: f64,
+    last_fetch: Option<f64>,
+    fetched: Rc<RefCell<Option<Result<Sources>>>>,
+    in_flight: bool,
+    current: Option<Sources>,
+    on_error: Box<dyn FnMut(&Error)>,
+}
+
+impl HotReloader {
+    /// Creates a reloader fetching the vertex and fragment shaders from the URLs.
+    ///
+    /// The sources are polled every 500 milliseconds by default,
+    /// and errors are logged with [`log::error!`][log::error].
+    pub fn new(vert_url: impl Into<String>, frag_url: impl Into<String>) -> Self {
+        Self {
+            vert_url: vert_url.into(),
+            frag_url: frag_url.into(),
+            interval: 500.,
+            last_fetch: None,
+            fetched: Rc::default(),
+            in_flight: false,
+            current: None,
+            on_error: Box::new(|err| log::error!("Failed to reload shaders: {:?}", err)),
+        }
+    }
+
+    /// Sets the sources that the program was created with,
+    /// so that the first fetch only reloads the program if the sources changed since then.
+    pub fn initial_sources(mut self, vert: impl Into<String>, frag: impl Into<String>) -> Self {
+        self.current = Some((vert.into(), frag.into()));
+        self
+    }
+
+    /// Sets the minimum interval between two fetches in milliseconds.
+    pub fn interval(mut self, millis: f64) -> Self {
+        self.interval = millis;
+        self
+    }
+
+    /// Sets the callback for errors when fetching or reloading the shaders.
+    pub fn on_error(mut self, on_error: impl FnMut(&Error) + 'static) -> Self {
+        self.on_error = Box::new(on_error);
+        self
+    }
+
+    /// Reloads the program with `reload` if the fetched sources changed,
+    /// and starts a new fetch if the interval has elapsed.
+    ///
+    /// This should be called once per frame.
+    /// Errors returned by `reload` are passed to the [`on_error`][HotReloader::on_error] callback,
+    /// and the same sources are not reloaded again.
+    pub fn poll(&mut self, reload: impl FnOnce(&str, &str) -> Result<()>) {
+        let fetched = self.fetched.borrow_mut().take();
+        if let Some(fetched) = fetched {
+            self.in_flight = false;
+            match fetched {
+                Ok(sources) if self.current.is_none() => self.current = Some(sources),
+                Ok(sources) if self.current.as_ref() != Some(&sources) => {
+                    if let Err(err) = reload(&sources.0, &sources.1) {
+                        (self.on_error)(&err);
+                    }
+                    self.current = Some(sources);
+                }
+                Ok(_) => {}
+                Err(err) => (self.on_error)(&err),
+            }
+        }
+
+        let now = js_sys::Date::now();
+        let due = match self.last_fetch {
+            Some(last_fetch) => now - last_fetch >= self.interval,
+            None => true,
+        };
+        if !self.in_flight && due {
+            self.in_flight = true;
+            self.last_fetch = Some(now);
+
+            let vert_url = cache_busted(&self.vert_url, now);
+            let frag_url = cache_busted(&self.frag_url, now);
+            let fetched = Rc::clone(&self.fetched);
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = async {
+                    let vert = fetch_text(&vert_url).await?;
+                    let frag = fetch_text(&frag_url).await?;
+                    Ok((vert, frag))
+                }
+                .await;
+                *fetched.borrow_mut() = Some(result);
+            });
+        }
+    }
+}
+
+/// Appends a timestamp to the URL so that the browser does not serve a cached response.
+fn cache_busted(url: &str, now: f64) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{}{}willow_reload={}", url, separator, now as u64)
+}
+
+async fn fetch_text(url: &str) -> Result<String> {
+    let window = web_sys::window().context("Hot reloading requires a window")?;
+    let response = JsFuture::from(window.fetch_with_str(url))
+        .await
+        .map_err(|err| anyhow!("Failed to fetch {}: {:?}", url, err))?;
+    let response: Response = response
+        .dyn_into()
+        .map_err(|_| anyhow!("fetch did not return a Response"))?;
+    if !response.ok() {
+        bail!("Fetching {} returned status {}", url, response.status());
+    }
+    let text = response
+        .text()
+        .map_err(|err| anyhow!("Failed to read {}: {:?}", url, err))?;
+    let text = JsFuture::from(text)
+        .await
+        .map_err(|err| anyhow!("Failed to read {}: {:?}", url, err))?;
+    text.as_string()
+        .with_context(|| format!("Response from {} is not text", url))
+}
@@ -0,0 +1,86 @@
+use std::fmt;
+
+/// The reasons a draw call can fail,
+/// returned by [`AbstractIndices::draw`][crate::AbstractIndices::draw] and related methods.
+///
+/// This type implements [`std::error::Error`],
+/// so it converts into [`anyhow::Error`] with the `?` operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawError {
+    /// The WebGL context is lost, e.g. because the GPU was reset.
+    ///
+    /// Resources allocated from the context are no longer valid,
+    /// and must be recreated after the context is restored.
+    ContextLost,
+    /// No program is in use.
+    ///
+    /// Call [`Program::use_program`][crate::Program::use_program] before drawing.
+    NoProgram,
+    /// The vertex buffer or the index buffer is empty.
+    BufferEmpty,
+    /// The drawn range `start..end` is out of bounds for a buffer of `len` vertices or indices.
+    IndexOutOfRange {
+        /// The start of the drawn range.
+        start: usize,
+        /// The end (exclusive) of the drawn range.
+        end: usize,
+        /// The number of vertices or indices in the buffer.
+        len: usize,
+    },
+    /// The vertex layout of a buffer does not match the attribute struct it is drawn as,
+    /// returned by [`Buffer::view_checked`][crate::Buffer::view_checked].
+    AttrMismatch {
+        /// The GLSL name of the first attribute whose component type, component count
+        /// or offset differs, or `None` if the number of attributes or the vertex size differs.
+        attribute: Option<&'static str>,
+    },
+    /// A field of the attribute struct has a component type not supported by the context,
+    /// e.g. `half::f16` attributes in a WebGL 1 context.
+    /// Returned when the buffer is allocated.
+    UnsupportedAttribType {
+        /// The GLSL name of the attribute.
+        attribute: &'static str,
+        /// The component type, e.g. `WebGl2RenderingContext::HALF_FLOAT`.
+        ty: u32,
+    },
+    /// Instanced drawing is not supported,
+    /// i.e. the context is neither WebGL 2 nor supports the `ANGLE_instanced_arrays` extension.
+    InstancingUnsupported,
+}
+
+impl fmt::Display for DrawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ContextLost => write!(f, "The WebGL context is lost"),
+            Self::NoProgram => write!(f, "No program is in use"),
+            Self::BufferEmpty => write!(f, "Cannot draw from an empty buffer"),
+            Self::IndexOutOfRange { start, end, len } => write!(
+                f,
+                "Cannot draw items {}..{} from a buffer of {} items",
+                start, end, len
+            ),
+            Self::AttrMismatch {
+                attribute: Some(attribute),
+            } => write!(
+                f,
+                "The vertex layout of the buffer does not match the attribute {}",
+                attribute
+            ),
+            Self::AttrMismatch { attribute: None } => write!(
+                f,
+                "The vertex layout of the buffer has a different number of attributes or vertex size"
+            ),
+            Self::UnsupportedAttribType { attribute, ty } => write!(
+                f,
+                "Attribute {} has the component type 0x{:x}, which is not supported by this context",
+                attribute, ty
+            ),
+            Self::InstancingUnsupported => write!(
+                f,
+                "Instanced drawing requires WebGL 2 or the ANGLE_instanced_arrays extension"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DrawError {}
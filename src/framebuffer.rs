@@ -0,0 +1,513 @@
+use anyhow::{bail, Context as _, Result};
+use web_sys::{WebGl2RenderingContext, WebGlFramebuffer, WebGlRenderbuffer, WebGlRenderingContext};
+
+use crate::{
+    Context, PixelFormat, PixelType, Renderbuffer, Texture, TextureFilter, TextureOptions,
+};
+
+/// Wraps a WebGL framebuffer that renders to a texture.
+///
+/// Bind it with [`with_bound`][Framebuffer::with_bound]
+/// or use the `draw_to` method of a uniform builder
+/// to draw into [`color`][Framebuffer::color] instead of the canvas.
+pub struct Framebuffer {
+    /// The raw WebGlFramebuffer object.
+    pub native: WebGlFramebuffer,
+    gl: WebGlRenderingContext,
+    color: ColorAttachment,
+    depth_stencil: Option<Renderbuffer>,
+    width: u32,
+    height: u32,
+}
+
+enum ColorAttachment {
+    Texture(Texture),
+    /// A multisampled renderbuffer, which cannot be sampled directly.
+    Renderbuffer(WebGlRenderbuffer),
+}
+
+impl Framebuffer {
+    /// Creates a framebuffer with an RGBA color texture of the given size.
+    pub fn new(context: &Context, width: u32, height: u32) -> Result<Self> {
+        Self::with_color_options(context, width, height, &TextureOptions::default())
+    }
+
+    /// Creates a framebuffer with an sRGB color texture of the given size.
+    ///
+    /// The linear colors written by the fragment shader are converted to sRGB when stored,
+    /// and converted back to linear values when the texture is sampled.
+    /// This keeps more precision in dark colors than an RGBA texture storing linear values.
+    ///
+    /// Requires WebGL 2 or the `EXT_sRGB` extension.
+    /// See [`TextureOptions::srgb`][TextureOptions::srgb].
+    pub fn new_srgb(context: &Context, width: u32, height: u32) -> Result<Self> {
+        let options = TextureOptions {
+            srgb: true,
+            ..TextureOptions::default()
+        };
+        Self::with_color_options(context, width, height, &options)
+    }
+
+    fn with_color_options(
+        context: &Context,
+        width: u32,
+        height: u32,
+        options: &TextureOptions,
+    ) -> Result<Self> {
+        let color = Texture::empty(
+            context,
+            width,
+            height,
+            PixelFormat::Rgba,
+            PixelType::UnsignedByte,
+            options,
+        )?;
+        Self::with_color(context, width, height, ColorAttachment::Texture(color))
+    }
+
+    /// Creates a framebuffer with a multisampled RGBA color renderbuffer of the given size,
+    /// which is antialiased like the default framebuffer.
+    ///
+    /// The renderbuffer cannot be sampled directly,
+    /// so [`color`][Framebuffer::color] returns `None`.
+    /// Use [`resolve_to`][Framebuffer::resolve_to] to copy the result into
+    /// a framebuffer created with [`new`][Framebuffer::new].
+    ///
+    /// This requires WebGL 2.
+    /// Returns an error if `samples` exceeds `MAX_SAMPLES`.
+    pub fn new_multisampled(
+        context: &Context,
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> Result<Self> {
+        let gl2 = context.require_webgl2("Multisampled framebuffer")?;
+
+        context.limits().check_renderbuffer_size(width, height)?;
+        let max_samples = context.limits().max_samples.unwrap_or(0);
+        if samples > max_samples {
+            bail!(
+                "Cannot create a framebuffer with {} samples (MAX_SAMPLES is {})",
+                samples,
+                max_samples
+            );
+        }
+
+        let renderbuffer = gl2
+            .create_renderbuffer()
+            .context("Failed to allocate WebGL renderbuffer")?;
+        gl2.bind_renderbuffer(WebGlRenderingContext::RENDERBUFFER, Some(&renderbuffer));
+        gl2.renderbuffer_storage_multisample(
+            WebGlRenderingContext::RENDERBUFFER,
+            samples as i32,
+            WebGl2RenderingContext::RGBA8,
+            width as i32,
+            height as i32,
+        );
+        gl2.bind_renderbuffer(WebGlRenderingContext::RENDERBUFFER, None);
+
+        Self::with_color(
+            context,
+            width,
+            height,
+            ColorAttachment::Renderbuffer(renderbuffer),
+        )
+    }
+
+    fn with_color(
+        context: &Context,
+        width: u32,
+        height: u32,
+        color: ColorAttachment,
+    ) -> Result<Self> {
+        let gl = &context.native;
+
+        let native = gl
+            .create_framebuffer()
+            .context("Failed to allocate WebGL framebuffer")?;
+        gl.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, Some(&native));
+        match &color {
+            ColorAttachment::Texture(texture) => gl.framebuffer_texture_2d(
+                WebGlRenderingContext::FRAMEBUFFER,
+                WebGlRenderingContext::COLOR_ATTACHMENT0,
+                WebGlRenderingContext::TEXTURE_2D,
+                Some(&texture.native),
+                0,
+            ),
+            ColorAttachment::Renderbuffer(renderbuffer) => gl.framebuffer_renderbuffer(
+                WebGlRenderingContext::FRAMEBUFFER,
+                WebGlRenderingContext::COLOR_ATTACHMENT0,
+                WebGlRenderingContext::RENDERBUFFER,
+                Some(renderbuffer),
+            ),
+        }
+        let status = gl.check_framebuffer_status(WebGlRenderingContext::FRAMEBUFFER);
+        gl.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, None);
+
+        let framebuffer = Self {
+            native,
+            gl: gl.clone(),
+            color,
+            depth_stencil: None,
+            width,
+            height,
+        };
+        if status != WebGlRenderingContext::FRAMEBUFFER_COMPLETE {
+            bail!("Framebuffer is incomplete (status 0x{:x})", status);
+        }
+        Ok(framebuffer)
+    }
+
+    /// The texture that the framebuffer renders colors to,
+    /// or `None` for a multisampled framebuffer.
+    pub fn color(&self) -> Option<&Texture> {
+        match &self.color {
+            ColorAttachment::Texture(texture) => Some(texture),
+            ColorAttachment::Renderbuffer(_) => None,
+        }
+    }
+
+    /// Attaches a depth, stencil or depth-stencil renderbuffer to the framebuffer,
+    /// replacing the previously attached one.
+    ///
+    /// The attachment point is determined by the format of the renderbuffer.
+    /// The framebuffer takes ownership of the renderbuffer and deletes it when dropped.
+    ///
+    /// Returns an error if the renderbuffer has a color format,
+    /// if its size differs from the framebuffer,
+    /// or if the framebuffer is incomplete with the attachment,
+    /// e.g. because the color attachment of a multisampled framebuffer
+    /// has a different number of samples.
+    pub fn attach_depth_stencil(
+        &mut self,
+        context: &Context,
+        renderbuffer: Renderbuffer,
+    ) -> Result<()> {
+        let attachment = renderbuffer.format().attachment();
+        if attachment == WebGlRenderingContext::COLOR_ATTACHMENT0 {
+            bail!(
+                "Cannot attach a {:?} renderbuffer as a depth or stencil buffer",
+                renderbuffer.format()
+            );
+        }
+        if (renderbuffer.width(), renderbuffer.height()) != (self.width, self.height) {
+            bail!(
+                "Cannot attach a {}x{} renderbuffer to a {}x{} framebuffer",
+                renderbuffer.width(),
+                renderbuffer.height(),
+                self.width,
+                self.height
+            );
+        }
+
+        let gl = &context.native;
+        gl.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, Some(&self.native));
+        if let Some(previous) = &self.depth_stencil {
+            gl.framebuffer_renderbuffer(
+                WebGlRenderingContext::FRAMEBUFFER,
+                previous.format().attachment(),
+                WebGlRenderingContext::RENDERBUFFER,
+                None,
+            );
+        }
+        gl.framebuffer_renderbuffer(
+            WebGlRenderingContext::FRAMEBUFFER,
+            attachment,
+            WebGlRenderingContext::RENDERBUFFER,
+            Some(&renderbuffer.native),
+        );
+        let status = gl.check_framebuffer_status(WebGlRenderingContext::FRAMEBUFFER);
+        gl.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, None);
+
+        self.depth_stencil = Some(renderbuffer);
+        if status != WebGlRenderingContext::FRAMEBUFFER_COMPLETE {
+            bail!("Framebuffer is incomplete (status 0x{:x})", status);
+        }
+        Ok(())
+    }
+
+    /// The renderbuffer attached with [`attach_depth_stencil`][Framebuffer::attach_depth_stencil].
+    pub fn depth_stencil(&self) -> Option<&Renderbuffer> {
+        self.depth_stencil.as_ref()
+    }
+
+    /// The width of the framebuffer in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the framebuffer in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Copies the colors of this framebuffer into `target`,
+    /// resolving the samples of a multisampled framebuffer.
+    ///
+    /// This requires WebGL 2, and both framebuffers must have the same size.
+    pub fn resolve_to(&self, context: &Context, target: &Framebuffer) -> Result<()> {
+        let gl2 = context.require_webgl2("Framebuffer resolution")?;
+        if (self.width, self.height) != (target.width, target.height) {
+            bail!(
+                "Cannot resolve a {}x{} framebuffer into a {}x{} framebuffer",
+                self.width,
+                self.height,
+                target.width,
+                target.height
+            );
+        }
+
+        gl2.bind_framebuffer(WebGl2RenderingContext::READ_FRAMEBUFFER, Some(&self.native));
+        gl2.bind_framebuffer(
+            WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+            Some(&target.native),
+        );
+        gl2.blit_framebuffer(
+            0,
+            0,
+            self.width as i32,
+            self.height as i32,
+            0,
+            0,
+            target.width as i32,
+            target.height as i32,
+            WebGlRenderingContext::COLOR_BUFFER_BIT,
+            WebGlRenderingContext::NEAREST,
+        );
+        gl2.bind_framebuffer(WebGl2RenderingContext::READ_FRAMEBUFFER, None);
+        gl2.bind_framebuffer(WebGl2RenderingContext::DRAW_FRAMEBUFFER, None);
+
+        Ok(())
+    }
+
+    /// Clears the draw buffer `index` of this framebuffer to `color`,
+    /// without affecting the other draw buffers.
+    ///
+    /// Unlike [`Context::clear`][Context::clear], this allows each attachment
+    /// of a framebuffer with multiple draw buffers to be cleared to a different value,
+    /// e.g. the attachments of a G-buffer.
+    /// The draw buffer must have a floating point or normalized format;
+    /// draw buffers that are not mapped to an attachment are left unchanged.
+    ///
+    /// This requires WebGL 2.
+    /// Returns an error if `index` is not less than `MAX_DRAW_BUFFERS` (at least 4).
+    pub fn clear_color_buffer(&self, context: &Context, index: u32, color: [f32; 4]) -> Result<()> {
+        let gl2 = context.require_webgl2("Clearing a single draw buffer")?;
+        let max_draw_buffers = context.limits().max_draw_buffers.unwrap_or(0);
+        if index >= max_draw_buffers {
+            bail!(
+                "Cannot clear draw buffer {} (MAX_DRAW_BUFFERS is {})",
+                index,
+                max_draw_buffers
+            );
+        }
+
+        crate::__trace!("clearBufferfv COLOR {} {:?}", index, color);
+        gl2.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, Some(&self.native));
+        gl2.clear_bufferfv_with_f32_array(WebGl2RenderingContext::COLOR, index as i32, &color);
+        gl2.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, None);
+        Ok(())
+    }
+
+    /// Clears the depth and stencil attachments of this framebuffer
+    /// to `depth` and `stencil` respectively.
+    ///
+    /// This has no effect on buffers that are not attached,
+    /// e.g. the stencil buffer if only a depth renderbuffer was attached with
+    /// [`attach_depth_stencil`][Framebuffer::attach_depth_stencil].
+    ///
+    /// This requires WebGL 2.
+    pub fn clear_depth_stencil(&self, context: &Context, depth: f32, stencil: i32) -> Result<()> {
+        let gl2 =
+            context.require_webgl2("Clearing the depth and stencil buffers with clearBufferfi")?;
+        debug_assert!(
+            (0.0..=1.0).contains(&depth),
+            "Clear depth {} is outside the range [0, 1]",
+            depth
+        );
+
+        crate::__trace!("clearBufferfi DEPTH_STENCIL {} {}", depth, stencil);
+        gl2.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, Some(&self.native));
+        gl2.clear_bufferfi(WebGl2RenderingContext::DEPTH_STENCIL, 0, depth, stencil);
+        gl2.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, None);
+        Ok(())
+    }
+
+    /// Reads the RGBA color of the pixel at `(x, y)` in this framebuffer,
+    /// e.g. for color-based object picking.
+    ///
+    /// See [`Context::pick`][Context::pick] for the coordinate system.
+    /// Pixels cannot be read from a multisampled framebuffer,
+    /// which must be resolved with [`resolve_to`][Framebuffer::resolve_to] first.
+    pub fn pick(&self, context: &Context, x: i32, y: i32) -> Result<[u8; 4]> {
+        self.with_bound(context, || context.pick(x, y))
+    }
+
+    /// Runs `f` with this framebuffer bound and the viewport set to its size,
+    /// then binds the default framebuffer and restores the previous viewport.
+    pub fn with_bound<T>(&self, context: &Context, f: impl FnOnce() -> T) -> T {
+        let gl = &context.native;
+
+        let viewport = context.query_viewport();
+        gl.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, Some(&self.native));
+        gl.viewport(0, 0, self.width() as i32, self.height() as i32);
+
+        let ret = f();
+
+        gl.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, None);
+        if let Some([x, y, width, height]) = viewport {
+            gl.viewport(x, y, width, height);
+        }
+        ret
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        self.gl.delete_framebuffer(Some(&self.native));
+        if let ColorAttachment::Renderbuffer(renderbuffer) = &self.color {
+            self.gl.delete_renderbuffer(Some(renderbuffer));
+        }
+    }
+}
+
+/// The buffers copied by [`Context::blit`][Context::blit].
+///
+/// Corresponds to the [`mask` parameter in `blitFramebuffer`][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/blitFramebuffer#parameters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlitMask {
+    /// Copies the color buffer.
+    pub color: bool,
+    /// Copies the depth buffer.
+    pub depth: bool,
+    /// Copies the stencil buffer.
+    pub stencil: bool,
+}
+
+impl BlitMask {
+    fn to_const(self) -> u32 {
+        let mut mask = 0;
+        if self.color {
+            mask |= WebGlRenderingContext::COLOR_BUFFER_BIT;
+        }
+        if self.depth {
+            mask |= WebGlRenderingContext::DEPTH_BUFFER_BIT;
+        }
+        if self.stencil {
+            mask |= WebGlRenderingContext::STENCIL_BUFFER_BIT;
+        }
+        mask
+    }
+}
+
+impl Context {
+    /// Copies the rectangle `src_rect` of `src` into the rectangle `dst_rect` of `dst`,
+    /// scaling it if the rectangles have different sizes.
+    ///
+    /// The rectangles are specified as `[x0, y0, x1, y1]` in pixels,
+    /// where `(x0, y0)` is inclusive and `(x1, y1)` is exclusive.
+    /// This is useful for downsampling, e.g. for bloom passes.
+    /// [`Framebuffer::resolve_to`][Framebuffer::resolve_to] is a shorthand
+    /// for copying the whole color buffer into a framebuffer of the same size.
+    ///
+    /// This requires WebGL 2.
+    /// Returns an error if `filter` is [`Linear`][TextureFilter::Linear]
+    /// while copying the depth or stencil buffer, which only supports `Nearest`.
+    pub fn blit(
+        &self,
+        src: &Framebuffer,
+        dst: &Framebuffer,
+        src_rect: [i32; 4],
+        dst_rect: [i32; 4],
+        mask: BlitMask,
+        filter: TextureFilter,
+    ) -> Result<()> {
+        let gl2 = self.require_webgl2("Framebuffer blitting")?;
+        if filter == TextureFilter::Linear && (mask.depth || mask.stencil) {
+            bail!("Depth and stencil buffers can only be blitted with TextureFilter::Nearest");
+        }
+
+        let [src_x0, src_y0, src_x1, src_y1] = src_rect;
+        let [dst_x0, dst_y0, dst_x1, dst_y1] = dst_rect;
+        gl2.bind_framebuffer(WebGl2RenderingContext::READ_FRAMEBUFFER, Some(&src.native));
+        gl2.bind_framebuffer(WebGl2RenderingContext::DRAW_FRAMEBUFFER, Some(&dst.native));
+        gl2.blit_framebuffer(
+            src_x0,
+            src_y0,
+            src_x1,
+            src_y1,
+            dst_x0,
+            dst_y0,
+            dst_x1,
+            dst_y1,
+            mask.to_const(),
+            filter.to_const(),
+        );
+        gl2.bind_framebuffer(WebGl2RenderingContext::READ_FRAMEBUFFER, None);
+        gl2.bind_framebuffer(WebGl2RenderingContext::DRAW_FRAMEBUFFER, None);
+
+        Ok(())
+    }
+
+    /// Reads the RGBA color of the pixel at `(x, y)` in the currently bound framebuffer,
+    /// which is the canvas unless called inside [`Framebuffer::with_bound`][Framebuffer::with_bound].
+    ///
+    /// This is useful for color-based object picking:
+    /// draw each object with its ID encoded as a color into a [`Framebuffer`][Framebuffer],
+    /// then pick the pixel under the cursor with [`Framebuffer::pick`][Framebuffer::pick].
+    ///
+    /// The coordinates are in pixels of the drawing buffer with the origin at the bottom left,
+    /// so `y` must be flipped relative to CSS coordinates,
+    /// i.e. `y = drawing_buffer_height - 1 - css_y * drawing_buffer_height / client_height`.
+    /// Pixels outside the framebuffer are read as `[0, 0, 0, 0]`.
+    ///
+    /// The canvas is cleared after it is composited unless
+    /// [`ContextAttributes::preserve_drawing_buffer`][crate::ContextAttributes::preserve_drawing_buffer]
+    /// is set, so picking from the canvas must happen in the same frame as the draw.
+    /// Reading pixels stalls until the GPU has finished all previous draws.
+    pub fn pick(&self, x: i32, y: i32) -> Result<[u8; 4]> {
+        let mut pixel = [0; 4];
+        crate::texture::with_pack_alignment(&self.native, pixel.len(), || {
+            self.native.read_pixels_with_opt_u8_array(
+                x,
+                y,
+                1,
+                1,
+                WebGlRenderingContext::RGBA,
+                WebGlRenderingContext::UNSIGNED_BYTE,
+                Some(&mut pixel),
+            )
+        })
+        .ok()
+        .context("Failed to read pixels")?;
+        Ok(pixel)
+    }
+
+    /// Sets `PACK_ALIGNMENT`, the alignment of the start of each row
+    /// in the pixel data written by `readPixels`.
+    ///
+    /// `alignment` must be 1, 2, 4 or 8; the default is 4.
+    /// For example, tightly packed RGB rows of an odd width require an alignment of 1.
+    /// The readback helpers of this crate set the alignment to match the row size
+    /// and restore the previous value afterwards,
+    /// so this is only needed when calling `readPixels` directly.
+    pub fn set_pack_alignment(&self, alignment: u8) {
+        debug_assert!(
+            matches!(alignment, 1 | 2 | 4 | 8),
+            "Pack alignment must be 1, 2, 4 or 8"
+        );
+        self.native
+            .pixel_storei(WebGlRenderingContext::PACK_ALIGNMENT, alignment.into());
+    }
+
+    /// The current `PACK_ALIGNMENT`.
+    pub fn pack_alignment(&self) -> u8 {
+        self.native
+            .get_parameter(WebGlRenderingContext::PACK_ALIGNMENT)
+            .ok()
+            .and_then(|value| value.as_f64())
+            .map_or(4, |value| value as u8)
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{Backend, Context, DrawError, Program, RenderPrimitiveType};
+
+impl Context {
+    /// Draws a single triangle covering the whole viewport with `program`,
+    /// without binding any attribute buffer.
+    ///
+    /// This is the usual primitive for post-processing passes.
+    /// The vertex shader generates the positions from `gl_VertexID`,
+    /// which is only available in GLSL ES 3.00, i.e. WebGL 2:
+    /// ```glsl
+    /// #version 300 es
+    /// out vec2 v_uv;
+    /// void main() {
+    ///     v_uv = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    ///     gl_Position = vec4(v_uv * 2.0 - 1.0, 0.0, 1.0);
+    /// }
+    /// ```
+    /// The triangle extends beyond the viewport, which is clipped away,
+    /// so every pixel is covered exactly once without the diagonal seam of a quad.
+    ///
+    /// The program should not have any attributes.
+    /// In WebGL 1, draw a buffer with the three vertices `(-1, -1)`, `(3, -1)` and `(-1, 3)`
+    /// through the regular draw methods instead.
+    ///
+    /// This method does not assign uniforms.
+    /// Call [`DrawBuilder::apply_uniforms`][crate::DrawBuilder::apply_uniforms] first
+    /// to assign them, since uniform values persist in the program.
+    pub fn draw_fullscreen(
+        &self,
+        program: &impl Program<Backend = Context>,
+    ) -> Result<(), DrawError> {
+        if self.is_lost() {
+            return Err(DrawError::ContextLost);
+        }
+        program.use_program(self);
+        self.draw_arrays(RenderPrimitiveType::Triangles.to_const(), 0, 3);
+        Ok(())
+    }
+}
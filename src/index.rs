@@ -1,32 +1,34 @@
+use std::mem;
 use std::ops::{self, RangeBounds};
 
 use anyhow::{Context as _, Result};
 use cfg_if::cfg_if;
 use js_sys::{Uint16Array, Uint32Array};
-use web_sys::{WebGlBuffer, WebGlRenderingContext};
+use web_sys::WebGlRenderingContext;
 
-use crate::{resolve_range, Buffer, BufferDataUsage, Context, Program, RenderPrimitiveType};
+use crate::{
+    resolve_range, AttrStruct, Backend, Buffer, BufferDataUsage, Context, DrawError, Program,
+    RenderPrimitiveType,
+};
 
 /// Stores the indices of a buffer.
-pub struct Indices {
-    buffer: WebGlBuffer,
+pub struct Indices<B: Backend = Context> {
+    buffer: B::Buffer,
     len: usize,
     ty: u32,
 }
 
-impl Indices {
+impl<B: Backend> Indices<B> {
     /// Allocates a buffer to store indices for a buffer of up to 65536 vertices.
-    pub fn new(context: &Context, indices: &[u16], usage: BufferDataUsage) -> Result<Self> {
-        let gl = &context.native;
-        let buffer = gl
+    pub fn new(context: &B, indices: &[u16], usage: BufferDataUsage) -> Result<Self> {
+        check_context(context)?;
+        let buffer = context
             .create_buffer()
             .context("Failed to allocate WebGL buffer")?;
-        gl.bind_buffer(WebGlRenderingContext::ELEMENT_ARRAY_BUFFER, Some(&buffer));
-
-        let array = Uint16Array::from(indices);
-        gl.buffer_data_with_array_buffer_view(
+        context.bind_buffer(WebGlRenderingContext::ELEMENT_ARRAY_BUFFER, Some(&buffer));
+        context.buffer_data_u16(
             WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
-            &array,
+            indices,
             usage.to_const(),
         );
 
@@ -41,41 +43,34 @@ impl Indices {
     ///
     /// This always fails on browsers that do not support the
     /// [`OES_element_index_uint`](https://developer.mozilla.org/en-US/docs/Web/API/OES_element_index_uint) extension.
-    pub fn new_with_usize(
-        context: &Context,
-        indices: &[usize],
-        usage: BufferDataUsage,
-    ) -> Result<Self> {
-        let gl = &context.native;
-        gl.get_extension("OES_element_index_uint")
-            .ok()
-            .flatten()
-            .context("Failed to enable extension for u32 element index")?;
+    pub fn new_with_usize(context: &B, indices: &[usize], usage: BufferDataUsage) -> Result<Self> {
+        check_context(context)?;
+        if !context.enable_extension("OES_element_index_uint") {
+            anyhow::bail!("Failed to enable extension for u32 element index");
+        }
+
+        let buffer = context
+            .create_buffer()
+            .context("Failed ot allocate WebGL buffer")?;
+        context.bind_buffer(WebGlRenderingContext::ELEMENT_ARRAY_BUFFER, Some(&buffer));
 
-        let array;
         cfg_if! {
             if #[cfg(target_pointer_width = "32")] {
                 // In wasm32-unknown-unknown, usize == u32.
                 // Let's try to optimize this majority use case.
 
-                let indices = unsafe {
+                let array = unsafe {
                     std::slice::from_raw_parts(indices.as_ptr() as *const u32, indices.len())
                 };
-                array = Uint32Array::from(indices);
             } else {
                 use std::convert::TryFrom;
-                let indices: Vec<u32> = indices.iter().map(|&v| u32::try_from(v).expect("Index is unreasonably large")).collect();
-                array = Uint32Array::from(&indices[..]);
+                let array: Vec<u32> = indices.iter().map(|&v| u32::try_from(v).expect("Index is unreasonably large")).collect();
+                let array = &array[..];
             }
         };
-
-        let buffer = gl
-            .create_buffer()
-            .context("Failed ot allocate WebGL buffer")?;
-        gl.bind_buffer(WebGlRenderingContext::ELEMENT_ARRAY_BUFFER, Some(&buffer));
-        gl.buffer_data_with_array_buffer_view(
+        context.buffer_data_u32(
             WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
-            &array,
+            array,
             usage.to_const(),
         );
 
@@ -86,26 +81,285 @@ impl Indices {
         })
     }
 
-    /// Calls the draw operation on a
-    pub(crate) fn draw(
+    /// The number of indices in the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the buffer contains no indices.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Overwrites the indices starting from index `offset` with `data`.
+    ///
+    /// This only works on indices created with [`new`][Indices::new].
+    /// Returns an error if the updated range exceeds the length of the buffer.
+    pub fn update(&self, context: &B, offset: usize, data: &[u16]) -> Result<()> {
+        check_context(context)?;
+        self.check_update(offset, data.len(), WebGlRenderingContext::UNSIGNED_SHORT)?;
+
+        context.bind_buffer(
+            WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+            Some(&self.buffer),
+        );
+        context.buffer_sub_data_u16(
+            WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+            (offset * mem::size_of::<u16>()) as i32,
+            data,
+        );
+        Ok(())
+    }
+
+    /// Overwrites the indices starting from index `offset` with `data`.
+    ///
+    /// This only works on indices created with [`new_with_usize`][Indices::new_with_usize].
+    /// Returns an error if the updated range exceeds the length of the buffer,
+    /// or if an index does not fit in `u32`.
+    pub fn update_with_usize(&self, context: &B, offset: usize, data: &[usize]) -> Result<()> {
+        use std::convert::TryFrom;
+
+        check_context(context)?;
+        self.check_update(offset, data.len(), WebGlRenderingContext::UNSIGNED_INT)?;
+
+        let array = data
+            .iter()
+            .map(|&v| u32::try_from(v))
+            .collect::<Result<Vec<u32>, _>>()
+            .context("Index is unreasonably large")?;
+
+        context.bind_buffer(
+            WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+            Some(&self.buffer),
+        );
+        context.buffer_sub_data_u32(
+            WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+            (offset * mem::size_of::<u32>()) as i32,
+            &array,
+        );
+        Ok(())
+    }
+
+    fn check_update(&self, offset: usize, len: usize, ty: u32) -> Result<()> {
+        if self.ty != ty {
+            anyhow::bail!(
+                "Cannot update {} indices with {} values",
+                if self.ty == WebGlRenderingContext::UNSIGNED_SHORT {
+                    "u16"
+                } else {
+                    "u32"
+                },
+                if ty == WebGlRenderingContext::UNSIGNED_SHORT {
+                    "u16"
+                } else {
+                    "u32"
+                },
+            );
+        }
+        match offset.checked_add(len) {
+            Some(end) if end <= self.len => Ok(()),
+            _ => anyhow::bail!(
+                "Cannot update {} indices from index {} in a buffer of {} indices",
+                len,
+                offset,
+                self.len
+            ),
+        }
+    }
+
+    /// The offset in bytes of the index at position `start`.
+    fn byte_offset(&self, start: i32) -> i32 {
+        let index_size = match self.ty {
+            WebGlRenderingContext::UNSIGNED_INT => mem::size_of::<u32>(),
+            _ => mem::size_of::<u16>(),
+        };
+        start * index_size as i32
+    }
+
+    /// Calls the draw operation on a range of indices.
+    fn draw(
+        &self,
+        mode: RenderPrimitiveType,
+        context: &B,
+        items: impl RangeBounds<usize>,
+    ) -> Result<(), DrawError> {
+        let (start, end) = checked_range(items, self.len)?;
+
+        context.bind_buffer(
+            WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+            Some(&self.buffer),
+        );
+        context.draw_elements(
+            mode.to_const(),
+            end - start,
+            self.ty,
+            self.byte_offset(start),
+        );
+        Ok(())
+    }
+
+    /// Calls the instanced draw operation on a range of indices.
+    fn draw_instanced(
         &self,
         mode: RenderPrimitiveType,
-        context: &Context,
+        context: &B,
         items: impl RangeBounds<usize>,
-    ) {
-        let gl = &context.native;
+        instances: usize,
+    ) -> Result<(), DrawError> {
+        check_instancing(context)?;
+        let (start, end) = checked_range(items, self.len)?;
 
-        let (start, end) = resolve_range(items, self.len);
+        context.bind_buffer(
+            WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+            Some(&self.buffer),
+        );
+        context.draw_elements_instanced(
+            mode.to_const(),
+            end - start,
+            self.ty,
+            self.byte_offset(start),
+            instances as i32,
+        );
+        Ok(())
+    }
 
-        gl.bind_buffer(
+    /// Draws each range of indices in `ranges` with a separate draw call,
+    /// applying the attributes in `buffer` only once.
+    ///
+    /// This is equivalent to drawing each [`subindex`][Indices::subindex] of `ranges` in turn,
+    /// and is useful for meshes with multiple submeshes.
+    ///
+    /// Call [`Program::use_program`][Program::use_program] before calling this method.
+    /// Returns an error on the first range that cannot be drawn;
+    /// the ranges before it are still drawn.
+    pub fn draw_multi<P: Program<Backend = B>>(
+        &self,
+        mode: RenderPrimitiveType,
+        context: &B,
+        program: &P,
+        buffer: &Buffer<P::AttrStruct, B>,
+        ranges: &[ops::Range<usize>],
+    ) -> Result<(), DrawError> {
+        check_draw(context, buffer)?;
+        program.apply_attrs(context, buffer);
+        for range in ranges {
+            self.draw(mode, context, range.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Uses `program` and draws the vertices in `buffer` indexed by all indices.
+    ///
+    /// The index buffer only stores the index type and count,
+    /// which are independent of the program and the vertex layout,
+    /// so the same `Indices` can be drawn against multiple programs and vertex buffers,
+    /// e.g. a shared quad index buffer for a batch of sprites with different attributes.
+    ///
+    /// This method does not assign uniforms;
+    /// use the `with_uniforms` builder derived by the [`Program`][crate::Program] macro
+    /// to draw with uniforms.
+    pub fn draw_with<P: Program<Backend = B>>(
+        &self,
+        program: &P,
+        buffer: &Buffer<P::AttrStruct, B>,
+        mode: RenderPrimitiveType,
+        context: &B,
+    ) -> Result<(), DrawError> {
+        program.use_program(context);
+        AbstractIndices::draw(self, mode, context, program, buffer)
+    }
+
+    /// Draws the range `items` of the indices with the attributes currently bound,
+    /// e.g. by [`RawAttrStruct::bind`][crate::RawAttrStruct::bind].
+    ///
+    /// Unlike [`AbstractIndices::draw`][AbstractIndices::draw],
+    /// this does not apply the attributes of a program.
+    /// Call [`Program::use_program`][Program::use_program] before calling this method.
+    pub fn draw_bound(
+        &self,
+        mode: RenderPrimitiveType,
+        context: &B,
+        items: impl RangeBounds<usize>,
+    ) -> Result<(), DrawError> {
+        if context.is_context_lost() {
+            return Err(DrawError::ContextLost);
+        }
+        if !context.has_current_program() {
+            return Err(DrawError::NoProgram);
+        }
+        self.draw(mode, context, items)
+    }
+
+    /// Draws `count` indices starting at index `offset_elements`
+    /// with the attributes and program currently bound.
+    ///
+    /// This is the low-level counterpart of [`draw_bound`][Indices::draw_bound]
+    /// for index buffers packing multiple meshes, where the offset and count of each mesh
+    /// are stored as plain integers.
+    /// The offset is counted in indices, not bytes;
+    /// it is converted to a byte offset according to the index type.
+    /// Call [`Program::use_program`][Program::use_program] before calling this method.
+    pub fn draw_raw(
+        &self,
+        context: &B,
+        mode: RenderPrimitiveType,
+        count: i32,
+        offset_elements: i32,
+    ) -> Result<(), DrawError> {
+        if context.is_context_lost() {
+            return Err(DrawError::ContextLost);
+        }
+        if !context.has_current_program() {
+            return Err(DrawError::NoProgram);
+        }
+        let end = offset_elements.saturating_add(count);
+        if offset_elements < 0 || count < 0 || end as usize > self.len {
+            return Err(DrawError::IndexOutOfRange {
+                start: offset_elements.max(0) as usize,
+                end: end.max(0) as usize,
+                len: self.len,
+            });
+        }
+
+        context.bind_buffer(
             WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
             Some(&self.buffer),
         );
-        gl.draw_elements_with_i32(mode.to_const(), end - start, self.ty, start);
+        context.draw_elements(
+            mode.to_const(),
+            count,
+            self.ty,
+            self.byte_offset(offset_elements),
+        );
+        Ok(())
+    }
+
+    /// Allocates a buffer with the edges of each triangle in `triangles`,
+    /// e.g. for drawing a wireframe overlay for debugging.
+    ///
+    /// Each triangle `(a, b, c)` is expanded into the lines `(a, b)`, `(b, c)` and `(c, a)`,
+    /// so the result should be drawn with [`RenderPrimitiveType::Lines`][RenderPrimitiveType::Lines].
+    ///
+    /// Returns an error if the number of indices is not a multiple of 3.
+    pub fn wireframe(context: &B, triangles: &[u16], usage: BufferDataUsage) -> Result<Self> {
+        Self::new(context, &triangle_edges(triangles)?, usage)
+    }
+
+    /// Allocates a buffer with the edges of each triangle in `triangles`,
+    /// like [`wireframe`][Self::wireframe] but for indices that can exceed 65536 vertices.
+    ///
+    /// This always fails on browsers that do not support the
+    /// [`OES_element_index_uint`](https://developer.mozilla.org/en-US/docs/Web/API/OES_element_index_uint) extension.
+    pub fn wireframe_with_usize(
+        context: &B,
+        triangles: &[usize],
+        usage: BufferDataUsage,
+    ) -> Result<Self> {
+        Self::new_with_usize(context, &triangle_edges(triangles)?, usage)
     }
 
     /// Creates a subindex that implements [`AbstractIndices`](AbstractIndices).
-    pub fn subindex<B: RangeBounds<usize> + Copy>(&self, bounds: B) -> SubIndices<'_, B> {
+    pub fn subindex<R: RangeBounds<usize> + Clone>(&self, bounds: R) -> SubIndices<'_, R, B> {
         SubIndices {
             indices: self,
             bounds,
@@ -113,15 +367,83 @@ impl Indices {
     }
 }
 
+impl Indices<Context> {
+    /// Creates an index buffer with the edges of each triangle in this buffer,
+    /// reading the indices back from the GPU.
+    ///
+    /// This is a convenience for when the source indices are no longer available,
+    /// and requires WebGL 2.
+    /// Prefer [`wireframe`][Self::wireframe] or [`wireframe_with_usize`][Self::wireframe_with_usize]
+    /// with the source indices, which also work on WebGL 1.
+    /// The index type of this buffer is preserved.
+    ///
+    /// Returns an error if the context is WebGL 1,
+    /// or if the number of indices is not a multiple of 3.
+    pub fn as_wireframe(&self, context: &Context) -> Result<Indices> {
+        let indices = self.read_back(context)?;
+
+        if self.ty == WebGlRenderingContext::UNSIGNED_INT {
+            let indices: Vec<usize> = indices.into_iter().map(|index| index as usize).collect();
+            Indices::wireframe_with_usize(context, &indices, BufferDataUsage::StaticDraw)
+        } else {
+            let indices: Vec<u16> = indices.into_iter().map(|index| index as u16).collect();
+            Indices::wireframe(context, &indices, BufferDataUsage::StaticDraw)
+        }
+    }
+
+    /// Reads the indices back from the GPU, which requires a WebGL 2 context.
+    pub(crate) fn read_back(&self, context: &Context) -> Result<Vec<u32>> {
+        let gl2 = context.require_webgl2("Reading back indices")?;
+
+        gl2.bind_buffer(
+            WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+            Some(&self.buffer),
+        );
+        if self.ty == WebGlRenderingContext::UNSIGNED_INT {
+            let array = Uint32Array::new_with_length(self.len as u32);
+            gl2.get_buffer_sub_data_with_i32_and_array_buffer_view(
+                WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+                0,
+                &array,
+            );
+            Ok(array.to_vec())
+        } else {
+            let array = Uint16Array::new_with_length(self.len as u32);
+            gl2.get_buffer_sub_data_with_i32_and_array_buffer_view(
+                WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+                0,
+                &array,
+            );
+            Ok(array.to_vec().into_iter().map(u32::from).collect())
+        }
+    }
+}
+
+/// Expands each triangle `(a, b, c)` into the lines `(a, b)`, `(b, c)` and `(c, a)`.
+///
+/// Returns an error if the number of indices is not a multiple of 3.
+fn triangle_edges<T: Copy>(triangles: &[T]) -> Result<Vec<T>> {
+    if triangles.len() % 3 != 0 {
+        anyhow::bail!(
+            "Cannot build a wireframe from {} indices, which is not a multiple of 3",
+            triangles.len()
+        );
+    }
+    Ok(triangles
+        .chunks_exact(3)
+        .flat_map(|tri| [tri[0], tri[1], tri[1], tri[2], tri[2], tri[0]])
+        .collect())
+}
+
 /// A contiguous subsequence of an [`Indices`][Indices] buffer,
 /// used to implement [`AbstractIndices`][AbstractIndices].
-pub struct SubIndices<'t, B: RangeBounds<usize> + Copy> {
-    indices: &'t Indices,
-    bounds: B,
+pub struct SubIndices<'t, R: RangeBounds<usize> + Clone, B: Backend = Context> {
+    indices: &'t Indices<B>,
+    bounds: R,
 }
 
 /// Types implementing this trait can be used to specify which vertices of a buffer to draw.
-pub trait AbstractIndices {
+pub trait AbstractIndices<B: Backend = Context> {
     /// Draws the vertices in `buffer` indexed by `self`.
     ///
     /// Call [`Program::use_program`][Program::use_program] before calling this method.
@@ -129,54 +451,193 @@ pub trait AbstractIndices {
     /// This method does not reassign uniforms.
     /// Use the `with_uniforms` method (derived by the [`Program`][super::Program] macro)
     /// to draw with uniforms specified.
-    fn draw<P: Program>(
+    ///
+    /// Returns an error without drawing if the context is lost, no program is in use,
+    /// `buffer` or the indices are empty, or the indices are out of range.
+    fn draw<P: Program<Backend = B>>(
         &self,
         mode: RenderPrimitiveType,
-        context: &Context,
+        context: &B,
         program: &P,
-        buffer: &Buffer<P::AttrStruct>,
-    );
+        buffer: &Buffer<P::AttrStruct, B>,
+    ) -> Result<(), DrawError>;
+
+    /// Draws `instances` instances of the vertices in `buffer` indexed by `self`.
+    ///
+    /// Returns an error for the same reasons as [`draw`][AbstractIndices::draw],
+    /// or if instancing is not supported,
+    /// i.e. the context is neither WebGL 2 nor supports the `ANGLE_instanced_arrays` extension.
+    ///
+    /// Call [`Program::use_program`][Program::use_program] before calling this method.
+    fn draw_instanced<P: Program<Backend = B>>(
+        &self,
+        mode: RenderPrimitiveType,
+        context: &B,
+        program: &P,
+        buffer: &Buffer<P::AttrStruct, B>,
+        instances: usize,
+    ) -> Result<(), DrawError>;
 }
 
-impl AbstractIndices for Indices {
-    fn draw<P: Program>(
+/// Draws each section of `(mode, indices)` in turn,
+/// applying the attributes in `buffer` only once.
+pub(crate) fn draw_sections<'t, P, B>(
+    context: &B,
+    program: &P,
+    buffer: &Buffer<P::AttrStruct, B>,
+    sections: impl IntoIterator<Item = (RenderPrimitiveType, &'t Indices<B>)>,
+) -> Result<(), DrawError>
+where
+    P: Program<Backend = B>,
+    B: Backend + 't,
+{
+    check_draw(context, buffer)?;
+    program.apply_attrs(context, buffer);
+    for (mode, indices) in sections {
+        indices.draw(mode, context, ..)?;
+    }
+    Ok(())
+}
+
+/// Returns an error if the context is lost.
+pub(crate) fn check_context<B: Backend>(context: &B) -> Result<()> {
+    if context.is_context_lost() {
+        return Err(DrawError::ContextLost.into());
+    }
+    Ok(())
+}
+
+/// Checks the state shared by all draw calls.
+fn check_draw<T: AttrStruct, B: Backend>(
+    context: &B,
+    buffer: &Buffer<T, B>,
+) -> Result<(), DrawError> {
+    if context.is_context_lost() {
+        return Err(DrawError::ContextLost);
+    }
+    if !context.has_current_program() {
+        return Err(DrawError::NoProgram);
+    }
+    if buffer.is_empty() {
+        return Err(DrawError::BufferEmpty);
+    }
+    Ok(())
+}
+
+fn check_instancing<B: Backend>(context: &B) -> Result<(), DrawError> {
+    if !context.supports_instancing() {
+        return Err(DrawError::InstancingUnsupported);
+    }
+    Ok(())
+}
+
+/// Resolves `items` into a range of `i32`s, checking that it lies within `0..len`.
+fn checked_range(items: impl RangeBounds<usize>, len: usize) -> Result<(i32, i32), DrawError> {
+    if len == 0 {
+        return Err(DrawError::BufferEmpty);
+    }
+    let (start, end) = resolve_range(items, len);
+    if start < 0 || start > end || end as usize > len {
+        return Err(DrawError::IndexOutOfRange {
+            start: start.max(0) as usize,
+            end: end.max(0) as usize,
+            len,
+        });
+    }
+    Ok((start, end))
+}
+
+impl<B: Backend> AbstractIndices<B> for Indices<B> {
+    fn draw<P: Program<Backend = B>>(
         &self,
         mode: RenderPrimitiveType,
-        context: &Context,
+        context: &B,
         program: &P,
-        buffer: &Buffer<P::AttrStruct>,
-    ) {
+        buffer: &Buffer<P::AttrStruct, B>,
+    ) -> Result<(), DrawError> {
+        check_draw(context, buffer)?;
         program.apply_attrs(context, buffer);
-        self.draw(mode, context, ..);
+        self.draw(mode, context, ..)
+    }
+
+    fn draw_instanced<P: Program<Backend = B>>(
+        &self,
+        mode: RenderPrimitiveType,
+        context: &B,
+        program: &P,
+        buffer: &Buffer<P::AttrStruct, B>,
+        instances: usize,
+    ) -> Result<(), DrawError> {
+        check_draw(context, buffer)?;
+        program.apply_attrs(context, buffer);
+        self.draw_instanced(mode, context, .., instances)
     }
 }
 
-impl<'t, B: RangeBounds<usize> + Copy> AbstractIndices for SubIndices<'t, B> {
-    fn draw<P: Program>(
+impl<'t, R: RangeBounds<usize> + Clone, B: Backend> AbstractIndices<B> for SubIndices<'t, R, B> {
+    fn draw<P: Program<Backend = B>>(
+        &self,
+        mode: RenderPrimitiveType,
+        context: &B,
+        program: &P,
+        buffer: &Buffer<P::AttrStruct, B>,
+    ) -> Result<(), DrawError> {
+        check_draw(context, buffer)?;
+        program.apply_attrs(context, buffer);
+        self.indices.draw(mode, context, self.bounds.clone())
+    }
+
+    fn draw_instanced<P: Program<Backend = B>>(
         &self,
         mode: RenderPrimitiveType,
-        context: &Context,
+        context: &B,
         program: &P,
-        buffer: &Buffer<P::AttrStruct>,
-    ) {
+        buffer: &Buffer<P::AttrStruct, B>,
+        instances: usize,
+    ) -> Result<(), DrawError> {
+        check_draw(context, buffer)?;
         program.apply_attrs(context, buffer);
-        self.indices.draw(mode, context, self.bounds);
+        self.indices
+            .draw_instanced(mode, context, self.bounds.clone(), instances)
     }
 }
 
 macro_rules! impl_bounds {
     ($ty:ty) => {
-        impl AbstractIndices for $ty {
-            fn draw<P: Program>(
+        impl<B: Backend> AbstractIndices<B> for $ty {
+            fn draw<P: Program<Backend = B>>(
+                &self,
+                mode: RenderPrimitiveType,
+                context: &B,
+                program: &P,
+                buffer: &Buffer<P::AttrStruct, B>,
+            ) -> Result<(), DrawError> {
+                check_draw(context, buffer)?;
+                let (start, end) = checked_range(self.clone(), buffer.count)?;
+                program.apply_attrs(context, buffer);
+                context.draw_arrays(mode.to_const(), start, end - start);
+                Ok(())
+            }
+
+            fn draw_instanced<P: Program<Backend = B>>(
                 &self,
                 mode: RenderPrimitiveType,
-                context: &Context,
+                context: &B,
                 program: &P,
-                buffer: &Buffer<P::AttrStruct>,
-            ) {
+                buffer: &Buffer<P::AttrStruct, B>,
+                instances: usize,
+            ) -> Result<(), DrawError> {
+                check_draw(context, buffer)?;
+                check_instancing(context)?;
+                let (start, end) = checked_range(self.clone(), buffer.count)?;
                 program.apply_attrs(context, buffer);
-                let (start, end) = resolve_range(self.clone(), buffer.count);
-                context.native.draw_arrays(mode.to_const(), start, end);
+                context.draw_arrays_instanced(
+                    mode.to_const(),
+                    start,
+                    end - start,
+                    instances as i32,
+                );
+                Ok(())
             }
         }
     };
@@ -189,14 +650,276 @@ impl_bounds!(ops::RangeInclusive<usize>);
 impl_bounds!(ops::RangeTo<usize>);
 impl_bounds!(ops::RangeToInclusive<usize>);
 
-impl<'t, T: AbstractIndices> AbstractIndices for &'t T {
-    fn draw<P: Program>(
+impl<B: Backend, T: AbstractIndices<B>> AbstractIndices<B> for &T {
+    fn draw<P: Program<Backend = B>>(
         &self,
         mode: RenderPrimitiveType,
-        context: &Context,
+        context: &B,
         program: &P,
-        buffer: &Buffer<P::AttrStruct>,
-    ) {
-        (&**self).draw(mode, context, program, buffer);
+        buffer: &Buffer<P::AttrStruct, B>,
+    ) -> Result<(), DrawError> {
+        (**self).draw(mode, context, program, buffer)
+    }
+
+    fn draw_instanced<P: Program<Backend = B>>(
+        &self,
+        mode: RenderPrimitiveType,
+        context: &B,
+        program: &P,
+        buffer: &Buffer<P::AttrStruct, B>,
+        instances: usize,
+    ) -> Result<(), DrawError> {
+        (**self).draw_instanced(mode, context, program, buffer, instances)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use web_sys::WebGlRenderingContext;
+
+    use super::triangle_edges;
+    use crate::{
+        AbstractIndices, Attribute, Buffer, BufferDataUsage, GlCall, Indices, MockContext, Program,
+        ProgramData, RenderPrimitiveType,
+    };
+
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Points {
+        data: ProgramData<MockContext>,
+        a_position: Attribute<f32>,
+    }
+
+    fn setup() -> (MockContext, Points, Buffer<PointsAttr, MockContext>) {
+        let context = MockContext::default();
+        let program = Points::create(&context).unwrap();
+        let buffer = Points::prepare_buffer(
+            &context,
+            &[PointsAttr { a_position: 0. }; 8],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+        context.take_calls();
+        (context, program, buffer)
+    }
+
+    fn draw_calls(context: &MockContext) -> Vec<GlCall> {
+        context
+            .take_calls()
+            .into_iter()
+            .filter(|call| {
+                matches!(
+                    call,
+                    GlCall::DrawArrays { .. }
+                        | GlCall::DrawElements { .. }
+                        | GlCall::DrawArraysInstanced { .. }
+                        | GlCall::DrawElementsInstanced { .. }
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn u16_subindex_offset_is_in_bytes() {
+        let (context, program, buffer) = setup();
+        let indices = Indices::new(
+            &context,
+            &[0, 1, 2, 3, 4, 5, 6],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+
+        program
+            .draw(
+                &context,
+                RenderPrimitiveType::Triangles,
+                &buffer,
+                indices.subindex(2..5),
+            )
+            .unwrap();
+        assert_eq!(
+            draw_calls(&context),
+            [GlCall::DrawElements {
+                mode: WebGlRenderingContext::TRIANGLES,
+                count: 3,
+                ty: WebGlRenderingContext::UNSIGNED_SHORT,
+                offset: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn u32_subindex_offset_is_in_bytes() {
+        let (context, program, buffer) = setup();
+        let indices = Indices::new_with_usize(
+            &context,
+            &[0, 1, 2, 3, 4, 5, 6],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+
+        program
+            .draw(
+                &context,
+                RenderPrimitiveType::Triangles,
+                &buffer,
+                indices.subindex(2..5),
+            )
+            .unwrap();
+        indices
+            .subindex(3..)
+            .draw_instanced(
+                RenderPrimitiveType::Triangles,
+                &context,
+                &program,
+                &buffer,
+                2,
+            )
+            .unwrap();
+        assert_eq!(
+            draw_calls(&context),
+            [
+                GlCall::DrawElements {
+                    mode: WebGlRenderingContext::TRIANGLES,
+                    count: 3,
+                    ty: WebGlRenderingContext::UNSIGNED_INT,
+                    offset: 8,
+                },
+                GlCall::DrawElementsInstanced {
+                    mode: WebGlRenderingContext::TRIANGLES,
+                    count: 4,
+                    ty: WebGlRenderingContext::UNSIGNED_INT,
+                    offset: 12,
+                    instances: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn update_rejects_overflowing_range() {
+        let context = MockContext::default();
+        let indices = Indices::new(&context, &[0, 1, 2], BufferDataUsage::StaticDraw).unwrap();
+
+        assert!(indices.update(&context, 1, &[2, 0]).is_ok());
+        assert!(indices.update(&context, 2, &[2, 0]).is_err());
+        assert!(indices.update(&context, usize::MAX, &[0]).is_err());
+    }
+
+    #[test]
+    fn draw_arrays_receives_count_of_range() {
+        let (context, program, buffer) = setup();
+        let mode = RenderPrimitiveType::Points;
+
+        program.draw(&context, mode, &buffer, 2..5).unwrap();
+        program.draw(&context, mode, &buffer, 2..=4).unwrap();
+        program.draw(&context, mode, &buffer, 3..).unwrap();
+        let draw = |first, count| GlCall::DrawArrays {
+            mode: WebGlRenderingContext::POINTS,
+            first,
+            count,
+        };
+        assert_eq!(draw_calls(&context), [draw(2, 3), draw(2, 3), draw(3, 5)]);
+    }
+
+    const MODES: [(RenderPrimitiveType, u32); 7] = [
+        (RenderPrimitiveType::Points, WebGlRenderingContext::POINTS),
+        (
+            RenderPrimitiveType::LineStrip,
+            WebGlRenderingContext::LINE_STRIP,
+        ),
+        (
+            RenderPrimitiveType::LineLoop,
+            WebGlRenderingContext::LINE_LOOP,
+        ),
+        (RenderPrimitiveType::Lines, WebGlRenderingContext::LINES),
+        (
+            RenderPrimitiveType::TriangleStrip,
+            WebGlRenderingContext::TRIANGLE_STRIP,
+        ),
+        (
+            RenderPrimitiveType::TriangleFan,
+            WebGlRenderingContext::TRIANGLE_FAN,
+        ),
+        (
+            RenderPrimitiveType::Triangles,
+            WebGlRenderingContext::TRIANGLES,
+        ),
+    ];
+
+    #[test]
+    fn every_mode_draws_vertex_subrange() {
+        let (context, program, buffer) = setup();
+        for &(mode, gl_mode) in &MODES {
+            program.draw(&context, mode, &buffer, 2..5).unwrap();
+            assert_eq!(
+                draw_calls(&context),
+                [GlCall::DrawArrays {
+                    mode: gl_mode,
+                    first: 2,
+                    count: 3,
+                }],
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn every_mode_draws_index_subrange() {
+        let (context, program, buffer) = setup();
+        let indices = Indices::new(
+            &context,
+            &[0, 1, 2, 3, 4, 5, 6],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+        for &(mode, gl_mode) in &MODES {
+            program
+                .draw(&context, mode, &buffer, indices.subindex(2..5))
+                .unwrap();
+            assert_eq!(
+                draw_calls(&context),
+                [GlCall::DrawElements {
+                    mode: gl_mode,
+                    count: 3,
+                    ty: WebGlRenderingContext::UNSIGNED_SHORT,
+                    offset: 4,
+                }],
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn triangle_edges_expands_each_triangle() {
+        assert_eq!(
+            triangle_edges(&[0, 1, 2, 2, 1, 3]).unwrap(),
+            [0, 1, 1, 2, 2, 0, 2, 1, 1, 3, 3, 2]
+        );
+        assert!(triangle_edges::<u16>(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn triangle_edges_rejects_incomplete_triangles() {
+        assert!(triangle_edges(&[0, 1]).is_err());
+        assert!(triangle_edges(&[0, 1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn wireframe_uploads_edges() {
+        let context = MockContext::default();
+        Indices::wireframe(&context, &[0, 1, 2], BufferDataUsage::StaticDraw).unwrap();
+
+        let data: Vec<u8> = [0u16, 1, 1, 2, 2, 0]
+            .iter()
+            .flat_map(|index| index.to_le_bytes())
+            .collect();
+        assert!(context.take_calls().contains(&GlCall::BufferData {
+            target: WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+            data,
+            usage: WebGlRenderingContext::STATIC_DRAW,
+        }));
     }
 }
@@ -0,0 +1,84 @@
+use once_cell::unsync::OnceCell;
+use wasm_bindgen::JsCast;
+use web_sys::{AngleInstancedArrays, WebGl2RenderingContext};
+
+use crate::{Context, DrawError};
+
+/// The API used for instanced drawing, detected once per context.
+pub(crate) enum Instancing {
+    WebGl2(WebGl2RenderingContext),
+    Angle(AngleInstancedArrays),
+}
+
+/// Lazily detected instancing support of a context.
+#[derive(Default)]
+pub(crate) struct InstancingCache(OnceCell<Option<Instancing>>);
+
+impl Context {
+    /// Whether instanced drawing is supported,
+    /// i.e. this is a WebGL 2 context or the
+    /// [`ANGLE_instanced_arrays`](https://developer.mozilla.org/en-US/docs/Web/API/ANGLE_instanced_arrays)
+    /// extension is supported.
+    ///
+    /// Instanced draw calls return an error if this returns `false`.
+    /// The result is cached after the first call.
+    pub fn supports_instancing(&self) -> bool {
+        self.instancing().is_some()
+    }
+
+    /// Sets the number of instances drawn before the vertex attribute at `index` advances,
+    /// or 0 to advance it per vertex.
+    ///
+    /// This is called by the `draw_with_instances` method generated by the
+    /// [`Program`][crate::Program] macro for `#[willow(instanced)]` attributes,
+    /// which restores the divisors to 0 after drawing.
+    /// Returns an error if instancing is not supported.
+    pub fn vertex_attrib_divisor(&self, index: u32, divisor: u32) -> Result<(), DrawError> {
+        crate::__trace!("vertexAttribDivisor index={} divisor={}", index, divisor);
+        match self.instancing() {
+            Some(Instancing::WebGl2(gl)) => gl.vertex_attrib_divisor(index, divisor),
+            Some(Instancing::Angle(ext)) => ext.vertex_attrib_divisor_angle(index, divisor),
+            None => return Err(DrawError::InstancingUnsupported),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn instancing(&self) -> Option<&Instancing> {
+        self.instancing
+            .0
+            .get_or_init(|| match self.webgl2() {
+                Some(gl) => Some(Instancing::WebGl2(gl.clone())),
+                None => self
+                    .get_extension("ANGLE_instanced_arrays")
+                    .map(|ext| Instancing::Angle(ext.unchecked_into())),
+            })
+            .as_ref()
+    }
+}
+
+impl Instancing {
+    pub(crate) fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32) {
+        match self {
+            Self::WebGl2(gl) => gl.draw_arrays_instanced(mode, first, count, instances),
+            Self::Angle(ext) => ext.draw_arrays_instanced_angle(mode, first, count, instances),
+        }
+    }
+
+    pub(crate) fn draw_elements_instanced(
+        &self,
+        mode: u32,
+        count: i32,
+        ty: u32,
+        offset: i32,
+        instances: i32,
+    ) {
+        match self {
+            Self::WebGl2(gl) => {
+                gl.draw_elements_instanced_with_i32(mode, count, ty, offset, instances)
+            }
+            Self::Angle(ext) => {
+                ext.draw_elements_instanced_angle_with_i32(mode, count, ty, offset, instances)
+            }
+        }
+    }
+}
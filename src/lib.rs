@@ -10,12 +10,20 @@
 
 #![warn(missing_docs)]
 
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::mem;
-use std::ops::{Bound, RangeBounds};
+use std::ops::{Bound, Deref, RangeBounds};
+
+use once_cell::unsync::OnceCell;
 
 pub use willow_codegen::Program;
 
+// Allows the tests to derive `Program`, whose generated code refers to `::willow`.
+#[cfg(test)]
+extern crate self as willow;
+
 pub use anyhow::{self, Error, Result};
 #[doc(hidden)]
 pub use field_offset::offset_of;
@@ -25,58 +33,246 @@ pub use log;
 pub use paste::paste;
 #[doc(hidden)]
 pub use web_sys::{
-    WebGlBuffer, WebGlProgram, WebGlRenderingContext, WebGlShader, WebGlUniformLocation,
+    WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlRenderingContext, WebGlShader,
+    WebGlUniformLocation,
 };
 
+mod backend;
+pub use backend::*;
+
+mod blend;
+pub use blend::*;
+
+mod builder;
+pub use builder::*;
+
+mod capability;
+pub use capability::*;
+
+mod depth;
+pub use depth::*;
+
+#[cfg(feature = "dev")]
+mod dev;
+#[cfg(feature = "dev")]
+pub use dev::*;
+
+mod error;
+pub use error::*;
+
+mod framebuffer;
+pub use framebuffer::*;
+
+mod fullscreen;
+
 mod index;
 pub use index::*;
 
+mod instancing;
+use instancing::InstancingCache;
+
+mod limits;
+pub use limits::*;
+
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "mock")]
+pub use mock::*;
+
+mod mesh;
+pub use mesh::*;
+
 mod types;
 pub use types::*;
 
+mod parameter;
+
 mod program;
 pub use program::*;
 
+mod query;
+pub use query::*;
+
+mod raw;
+pub use raw::*;
+
+mod renderbuffer;
+pub use renderbuffer::*;
+
+mod sampler;
+pub use sampler::*;
+
+mod screenshot;
+
+mod state;
+pub use state::*;
+
+mod stats;
+pub use stats::*;
+
+mod sync;
+pub use sync::*;
+
+mod texture;
+pub use texture::*;
+
+mod texture_array;
+pub use texture_array::*;
+
 mod traits;
 pub use traits::*;
 
 /// A wrapper for a WebGL rendering context.
 pub struct Context {
     /// The raw WebGlRenderingContext object.
+    ///
+    /// For WebGL 2 contexts, this is the same object as [`webgl2`][Context::webgl2].
     pub native: WebGlRenderingContext,
+    /// The raw WebGl2RenderingContext object, if this is a WebGL 2 context.
+    webgl2: Option<WebGl2RenderingContext>,
+    /// The canvas that the context was created on.
+    canvas: ContextCanvas,
     /// Ratio of width/height
     aspect: f32,
+    /// The attributes requested when the context was created.
+    attributes: ContextAttributes,
+    /// Extension objects retrieved so far, `None` if unsupported.
+    extensions: RefCell<HashMap<String, Option<js_sys::Object>>>,
+    /// The instancing API, detected upon first use.
+    instancing: InstancingCache,
+    /// The implementation limits, queried upon first use.
+    limits: OnceCell<GlLimits>,
+    /// The draw statistics since the last reset.
+    stats: Cell<DrawStats>,
+    /// Whether a program was last bound through [`Backend::use_program`][Backend::use_program],
+    /// so that draws do not need to query `CURRENT_PROGRAM`.
+    program_bound: Cell<bool>,
 }
 
 impl Context {
     /// Creates a context on the canvas element.
+    ///
+    /// This is a shorthand for `Context::builder(canvas).aspect(aspect_fix).build()`.
     pub fn from_canvas(canvas: web_sys::Element, aspect_fix: AspectFix) -> Result<Self> {
+        Self::builder(canvas).aspect(aspect_fix).build()
+    }
+
+    /// Creates a context on an `OffscreenCanvas`.
+    ///
+    /// This allows rendering off the main thread:
+    /// transfer control of a `<canvas>` to an `OffscreenCanvas` with
+    /// `canvas.transferControlToOffscreen()` on the main thread,
+    /// post it to a worker, and create the context in the worker.
+    /// Frames rendered in the worker are displayed on the original `<canvas>` automatically.
+    ///
+    /// Since an `OffscreenCanvas` has no layout, the rendered aspect ratio (width/height)
+    /// of the displayed canvas must be passed as `aspect`.
+    ///
+    /// APIs that depend on DOM elements, such as uploading textures from `<img>` elements,
+    /// are not available in workers.
+    ///
+    /// This is a shorthand for `Context::offscreen_builder(canvas, aspect).build()`.
+    pub fn from_offscreen_canvas(canvas: web_sys::OffscreenCanvas, aspect: f32) -> Result<Self> {
+        Self::offscreen_builder(canvas, aspect).build()
+    }
+
+    /// The raw WebGl2RenderingContext object, or `None` if this is a WebGL 1 context.
+    pub fn webgl2(&self) -> Option<&WebGl2RenderingContext> {
+        self.webgl2.as_ref()
+    }
+
+    /// The `<canvas>` element that the context was created on,
+    /// e.g. for handling resizes or attaching event listeners.
+    ///
+    /// Returns `None` if the context was created on an `OffscreenCanvas`.
+    pub fn canvas(&self) -> Option<&web_sys::HtmlCanvasElement> {
+        match &self.canvas {
+            ContextCanvas::Element(canvas) => Some(canvas),
+            ContextCanvas::Offscreen(_) => None,
+        }
+    }
+
+    /// The `OffscreenCanvas` that the context was created on,
+    /// or `None` if the context was created on a `<canvas>` element.
+    pub fn offscreen_canvas(&self) -> Option<&web_sys::OffscreenCanvas> {
+        match &self.canvas {
+            ContextCanvas::Element(_) => None,
+            ContextCanvas::Offscreen(canvas) => Some(canvas),
+        }
+    }
+
+    /// Returns the WebGL 2 context, or an error mentioning `feature` if this is a WebGL 1 context.
+    pub(crate) fn require_webgl2(&self, feature: &str) -> Result<&WebGl2RenderingContext> {
         use anyhow::Context;
-        use wasm_bindgen::JsCast;
 
-        let canvas = canvas
-            .dyn_into::<web_sys::HtmlCanvasElement>()
-            .ok()
-            .context("The element is not a <canvas>")?;
-        let aspect = canvas.client_width() as f32 / canvas.client_height() as f32;
+        self.webgl2
+            .as_ref()
+            .with_context(|| format!("{} requires a WebGL 2 context", feature))
+    }
 
-        match aspect_fix {
-            AspectFix::None => (),
-            AspectFix::FromWidth => canvas.set_height((canvas.width() as f32 / aspect) as u32),
-            AspectFix::FromHeight => canvas.set_width((canvas.height() as f32 * aspect) as u32),
+    /// The attributes requested when the context was created.
+    pub fn attributes(&self) -> ContextAttributes {
+        self.attributes
+    }
+
+    /// Retrieves the extension object with the given name,
+    /// or `None` if the extension is not supported.
+    ///
+    /// The result is cached, so this method can be called repeatedly without additional cost.
+    pub fn get_extension(&self, name: &str) -> Option<js_sys::Object> {
+        if let Some(ext) = self.extensions.borrow().get(name) {
+            return ext.clone();
         }
 
-        Ok(Self {
-            native: canvas
-                .get_context("webgl")
-                .ok()
-                .flatten()
-                .context("Could not initialize WebGL context")?
-                .dyn_into()
-                .ok()
-                .context("WebGL context has an incorrect type")?,
-            aspect,
-        })
+        let ext = self.native.get_extension(name).ok().flatten();
+        self.extensions
+            .borrow_mut()
+            .insert(name.to_string(), ext.clone());
+        ext
+    }
+
+    /// Whether the context is lost, e.g. because the GPU was reset.
+    ///
+    /// While the context is lost, all WebGL calls are ignored,
+    /// so draw calls fail with [`DrawError::ContextLost`][DrawError::ContextLost]
+    /// and allocating new resources fails.
+    pub fn is_lost(&self) -> bool {
+        self.native.is_context_lost()
+    }
+
+    /// Simulates losing the context with the `WEBGL_lose_context` extension,
+    /// e.g. to test the context loss recovery code of an application.
+    ///
+    /// The browser dispatches a `webglcontextlost` event on the canvas,
+    /// and [`is_lost`][Context::is_lost] returns `true` afterwards.
+    /// This is intended for testing only.
+    ///
+    /// Returns an error if the extension is not supported.
+    pub fn simulate_lose_context(&self) -> Result<()> {
+        self.lose_context_extension()?.lose_context();
+        Ok(())
+    }
+
+    /// Simulates restoring a context lost by
+    /// [`simulate_lose_context`][Context::simulate_lose_context].
+    ///
+    /// The browser dispatches a `webglcontextrestored` event on the canvas asynchronously,
+    /// after which all resources must be recreated.
+    /// This is intended for testing only.
+    ///
+    /// Returns an error if the extension is not supported.
+    pub fn simulate_restore_context(&self) -> Result<()> {
+        self.lose_context_extension()?.restore_context();
+        Ok(())
+    }
+
+    fn lose_context_extension(&self) -> Result<web_sys::WebglLoseContext> {
+        use anyhow::Context;
+        use wasm_bindgen::JsCast;
+
+        self.get_extension("WEBGL_lose_context")
+            .map(|ext| ext.unchecked_into())
+            .context("WEBGL_lose_context is not supported")
     }
 
     /// Aspect ratio of the canvas.
@@ -86,23 +282,122 @@ impl Context {
     }
 
     /// Clears the color, depth and stencil buffers.
+    ///
+    /// Buffers with a `None` value in `clear` are not cleared.
+    /// The stencil buffer only exists if [`ContextAttributes::stencil`][ContextAttributes::stencil]
+    /// was set when creating the context.
+    ///
+    /// # Panics
+    /// In debug builds, panics if the depth value is outside `[0, 1]`,
+    /// e.g. a world-space depth passed by mistake,
+    /// or if the stencil value does not fit in the bits of the stencil buffer.
+    /// Release builds pass the values through, and WebGL clamps or masks them.
     pub fn clear(&self, clear: Clear) {
+        crate::__trace!("clear {:?}", clear);
+
         let mut mask = 0;
         if let Some([r, g, b, a]) = clear.color {
             mask |= WebGlRenderingContext::COLOR_BUFFER_BIT;
             self.native.clear_color(r, g, b, a);
         }
         if let Some(depth) = clear.depth {
+            debug_assert!(
+                (0.0..=1.0).contains(&depth),
+                "Clear depth {} is outside the range [0, 1]",
+                depth
+            );
             mask |= WebGlRenderingContext::DEPTH_BUFFER_BIT;
             self.native.clear_depth(depth);
         }
         if let Some(stencil) = clear.stencil {
+            #[cfg(debug_assertions)]
+            {
+                if !self.attributes.stencil {
+                    log::warn!(
+                        "Clearing the stencil buffer has no effect \
+                        because the context was created without a stencil buffer"
+                    );
+                }
+
+                let bits = self
+                    .native
+                    .get_parameter(WebGlRenderingContext::STENCIL_BITS)
+                    .ok()
+                    .and_then(|value| value.as_f64())
+                    .map_or(0, |value| value as u32);
+                if bits > 0 {
+                    assert!(
+                        (0..1_i64 << bits).contains(&i64::from(stencil)),
+                        "Clear stencil {} does not fit in the {}-bit stencil buffer",
+                        stencil,
+                        bits
+                    );
+                }
+            }
             mask |= WebGlRenderingContext::STENCIL_BUFFER_BIT;
             self.native.clear_stencil(stencil);
         }
 
+        if mask == 0 {
+            return;
+        }
         self.native.clear(mask);
     }
+
+    /// Clears the color, depth and stencil buffers within the rectangle `(x, y, width, height)`,
+    /// where `(x, y)` is the lower left corner in pixels.
+    ///
+    /// This temporarily enables the scissor test around the clear,
+    /// then restores the previous scissor test state and scissor box.
+    pub fn clear_rect(&self, rect: (i32, i32, i32, i32), clear: Clear) {
+        use wasm_bindgen::JsCast;
+
+        let gl = &self.native;
+
+        let was_enabled = gl.is_enabled(WebGlRenderingContext::SCISSOR_TEST);
+        let prev_box = gl
+            .get_parameter(WebGlRenderingContext::SCISSOR_BOX)
+            .ok()
+            .and_then(|value| value.dyn_into::<js_sys::Int32Array>().ok())
+            .map(|array| array.to_vec());
+
+        self.set_scissor(Some(rect));
+        self.clear(clear);
+
+        if let Some(&[x, y, width, height]) = prev_box.as_deref() {
+            gl.scissor(x, y, width, height);
+        }
+        if !was_enabled {
+            gl.disable(WebGlRenderingContext::SCISSOR_TEST);
+        }
+    }
+
+    /// Restricts drawing and clearing to the rectangle `(x, y, width, height)`,
+    /// where `(x, y)` is the lower left corner in pixels.
+    ///
+    /// Passing `None` disables the scissor test.
+    pub fn set_scissor(&self, rect: Option<(i32, i32, i32, i32)>) {
+        match rect {
+            Some((x, y, width, height)) => {
+                self.native.enable(WebGlRenderingContext::SCISSOR_TEST);
+                self.native.scissor(x, y, width, height);
+            }
+            None => self.native.disable(WebGlRenderingContext::SCISSOR_TEST),
+        }
+    }
+
+    /// Flushes the issued commands to the GPU without waiting for them to complete.
+    pub fn flush(&self) {
+        self.native.flush();
+    }
+
+    /// Blocks until all issued commands have been completed by the GPU.
+    ///
+    /// This stalls the whole pipeline and should be avoided in the render loop.
+    /// Use a [`Fence`][Fence] to check for completion without blocking.
+    pub fn finish(&self) {
+        self.native.finish();
+    }
 }
 
 /// Policy for correcting canvas size.
@@ -130,85 +425,397 @@ pub struct Clear {
     pub stencil: Option<i32>,
 }
 
+impl Clear {
+    /// Creates a `Clear` that clears nothing,
+    /// to be extended with the chainable setters:
+    /// ```ignore
+    /// context.clear(Clear::new().color([0., 0., 0., 1.]).depth(1.));
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clears the color buffer to the RGBA values (in the range `[0, 1]`).
+    pub fn color(mut self, color: [f32; 4]) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Clears the depth buffer to the value (in the range `[0, 1]`).
+    pub fn depth(mut self, depth: f32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Clears the stencil buffer to the value.
+    pub fn stencil(mut self, stencil: i32) -> Self {
+        self.stencil = Some(stencil);
+        self
+    }
+}
+
 /// This macro allows efficient batch creation of programs by compiling and linking in parallel.
 ///
 /// Example:
 /// ```ignore
-/// let (foo, bar, qux) = create_programs![context => Foo, Bar, Qux];
+/// let (foo, bar, qux) = create_programs![context => Foo, Bar, Qux]?;
 /// ```
 ///
 /// This is more efficient than
 /// ```ignore
-/// let foo = Foo::create(context);
-/// let bar = Bar::create(context);
-/// let qux = Qux::create(context);
+/// let foo = Foo::create(context)?;
+/// let bar = Bar::create(context)?;
+/// let qux = Qux::create(context)?;
 /// ```
+///
+/// Like [`Program::create`][Program::create], this evaluates to a `Result`.
 #[macro_export]
 macro_rules! create_programs {
     ($context:expr => $($ty:ty),* $(,)?) => {
-        {
+        (|| -> $crate::Result<_> {
             $crate::paste! {
                 $(
                     #[allow(non_snake_case)]
-                    let [<var_ $ty>] = $ty::create_internally(&$context);
+                    let [<var_ $ty>] = $ty::create_internally(&$context)?;
                 )*;
                 $(
-                    [<var_ $ty>].compile_shaders(&$context);
+                    [<var_ $ty>].compile_shaders(&$context)?;
                 )*
                 $(
-                    [<var_ $ty>].link_shaders(&$context);
+                    [<var_ $ty>].link_shaders(&$context)?;
                 )*
 
-                ($(
+                Ok(($(
                     [<var_ $ty>],
-                )*)
+                )*))
             }
-        }
+        })()
     }
 }
 
+/// Logs a WebGL operation at the trace level if the `trace` feature is enabled.
+///
+/// This expands to nothing if the feature is disabled,
+/// including in code generated by the [`Program`][Program] macro.
+#[cfg(feature = "trace")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace {
+    ($($arg:tt)*) => {
+        $crate::log::trace!($($arg)*)
+    };
+}
+
+/// Logs a WebGL operation at the trace level if the `trace` feature is enabled.
+#[cfg(not(feature = "trace"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace {
+    ($($arg:tt)*) => {};
+}
+
 /// Wraps a WebGL buffer.
-pub struct Buffer<T: AttrStruct> {
+pub struct Buffer<T: AttrStruct, B: Backend = Context> {
     #[doc(hidden)]
-    pub buf: WebGlBuffer,
+    pub buf: B::Buffer,
     count: usize, // number of elements
+    usage: BufferDataUsage,
     _ph: PhantomData<*const T>,
 }
 
-impl<T: AttrStruct> Buffer<T> {
+impl<T: AttrStruct, B: Backend> Buffer<T, B> {
     /// Allocates a WebGL buffer with the contents in `slice`.
-    pub fn from_slice(context: &Context, slice: &[T], usage: BufferDataUsage) -> Self {
-        let gl = &context.native;
-
-        let buf = gl.create_buffer().expect("Failed to create WebGL buffer");
-        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&buf));
-
+    ///
+    /// Returns [`DrawError::ContextLost`][DrawError::ContextLost] if the context is lost,
+    /// or [`DrawError::UnsupportedAttribType`][DrawError::UnsupportedAttribType]
+    /// if a field of `T` has a component type not supported by the context,
+    /// e.g. `half::f16` attributes in a WebGL 1 context.
+    pub fn from_slice(context: &B, slice: &[T], usage: BufferDataUsage) -> Result<Self, DrawError> {
+        Self::check_attrib_types(context)?;
         let bytes = unsafe {
-            std::slice::from_raw_parts(
-                slice.as_ptr() as *const u8,
-                slice.len() * mem::size_of::<T>(),
-            )
+            std::slice::from_raw_parts(slice.as_ptr() as *const u8, mem::size_of_val(slice))
         };
-        gl.buffer_data_with_u8_array(WebGlRenderingContext::ARRAY_BUFFER, bytes, usage.to_const());
+        Self::allocate(context, bytes, slice.len(), usage)
+    }
+
+    /// Allocates a WebGL buffer with the vertices yielded by `iter`.
+    ///
+    /// The vertices are written directly into a byte buffer sized from `iter.len()`,
+    /// so procedurally generated geometry does not need to be collected into a `Vec<T>` first.
+    ///
+    /// Returns an error in the same cases as [`from_slice`][Buffer::from_slice].
+    pub fn from_iter(
+        context: &B,
+        iter: impl ExactSizeIterator<Item = T>,
+        usage: BufferDataUsage,
+    ) -> Result<Self, DrawError> {
+        Self::check_attrib_types(context)?;
+        let mut bytes = Vec::with_capacity(iter.len() * mem::size_of::<T>());
+        let mut count = 0;
+        for item in iter {
+            let item_bytes = unsafe {
+                std::slice::from_raw_parts(&item as *const T as *const u8, mem::size_of::<T>())
+            };
+            bytes.extend_from_slice(item_bytes);
+            count += 1;
+        }
+        Self::allocate(context, &bytes, count, usage)
+    }
 
-        Self {
+    fn check_attrib_types(context: &B) -> Result<(), DrawError> {
+        for i in 0..T::fields_count() {
+            if !context.supports_attrib_type(T::field_type(i)) {
+                return Err(DrawError::UnsupportedAttribType {
+                    attribute: T::field_gl_name(i),
+                    ty: T::field_type(i),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn allocate(
+        context: &B,
+        bytes: &[u8],
+        count: usize,
+        usage: BufferDataUsage,
+    ) -> Result<Self, DrawError> {
+        let buffer = Self::create_bound(context, count, usage)?;
+        context.buffer_data_u8(WebGlRenderingContext::ARRAY_BUFFER, bytes, usage.to_const());
+        Ok(buffer)
+    }
+
+    /// Allocates a buffer of `count` zeroed vertices without uploading any data.
+    fn allocate_zeroed(
+        context: &B,
+        count: usize,
+        usage: BufferDataUsage,
+    ) -> Result<Self, DrawError> {
+        let buffer = Self::create_bound(context, count, usage)?;
+        buffer.orphan_bound(context);
+        Ok(buffer)
+    }
+
+    /// Creates a buffer bound to `ARRAY_BUFFER` without allocating its storage.
+    fn create_bound(context: &B, count: usize, usage: BufferDataUsage) -> Result<Self, DrawError> {
+        if context.is_context_lost() {
+            return Err(DrawError::ContextLost);
+        }
+        // `createBuffer` only fails if the context is lost.
+        let buf = context.create_buffer().ok_or(DrawError::ContextLost)?;
+        context.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&buf));
+
+        Ok(Self {
             buf,
-            count: slice.len(),
+            count,
+            usage,
             _ph: PhantomData,
+        })
+    }
+
+    /// The number of vertices in the buffer.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Whether the buffer contains no vertices.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The size of the buffer in bytes.
+    pub fn byte_len(&self) -> usize {
+        self.count * mem::size_of::<T>()
+    }
+
+    /// Overwrites the vertices starting from vertex `offset` with `slice`.
+    ///
+    /// If `orphan` is true, the buffer is [orphaned][Buffer::orphan] before the upload,
+    /// which avoids waiting for draw calls still reading the previous contents.
+    /// Since orphaning discards the previous contents,
+    /// this requires `slice` to replace the whole buffer.
+    ///
+    /// Returns an error if the updated range exceeds the length of the buffer,
+    /// or if `orphan` is true but `slice` does not cover the whole buffer.
+    pub fn update_slice(
+        &self,
+        context: &B,
+        offset: usize,
+        slice: &[T],
+        orphan: bool,
+    ) -> Result<()> {
+        index::check_context(context)?;
+        match offset.checked_add(slice.len()) {
+            Some(end) if end <= self.count => {}
+            _ => anyhow::bail!(
+                "Cannot update {} vertices from vertex {} in a buffer of {} vertices",
+                slice.len(),
+                offset,
+                self.count
+            ),
         }
+        if orphan && (offset != 0 || slice.len() != self.count) {
+            anyhow::bail!("Orphaning requires the update to replace the whole buffer");
+        }
+
+        context.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&self.buf));
+        if orphan {
+            self.orphan_bound(context);
+        }
+        let bytes = unsafe {
+            std::slice::from_raw_parts(slice.as_ptr() as *const u8, mem::size_of_val(slice))
+        };
+        context.buffer_sub_data_u8(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            (offset * mem::size_of::<T>()) as i32,
+            bytes,
+        );
+        Ok(())
     }
 
-    /// Binds the buffer to a specified attribute.
-    pub fn bind_to_attr(&self, context: &Context, attr_index: u32, field_index: usize) {
-        context.native.vertex_attrib_pointer_with_i32(
-            attr_index,
-            T::field_num_comps(field_index) as i32, // component count
-            T::field_type(field_index),             // type
-            T::field_normalized(field_index),       // normalized
-            mem::size_of::<T>() as i32,             // stride
-            T::field_offset(field_index) as i32,    // offset
+    /// Reallocates the storage of the buffer at the same size,
+    /// discarding the previous contents.
+    ///
+    /// Uploading to a buffer that is still read by pending draw calls
+    /// may stall until the GPU finishes with it.
+    /// Orphaning the buffer before re-uploading all of its contents
+    /// lets the driver allocate a new backing store instead,
+    /// which reduces stalls for buffers updated every frame.
+    ///
+    /// The contents are zeroed until they are overwritten,
+    /// e.g. with [`update_slice`][Buffer::update_slice].
+    pub fn orphan(&self, context: &B) {
+        context.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&self.buf));
+        self.orphan_bound(context);
+    }
+
+    fn orphan_bound(&self, context: &B) {
+        context.buffer_data_size(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            self.byte_len() as i32,
+            self.usage.to_const(),
         );
     }
+
+    /// Binds the buffer to a specified attribute.
+    ///
+    /// Matrix fields occupy one location per column starting from `attr_index`.
+    /// The locations after `attr_index` are also enabled here,
+    /// since only the first location is returned by `getAttribLocation`.
+    pub fn bind_to_attr(&self, context: &B, attr_index: u32, field_index: usize) {
+        let num_comps = T::field_num_comps(field_index);
+        let ty = T::field_type(field_index);
+        let column_bytes = num_comps * raw::component_size(ty).map_or(0, |size| size as usize);
+        for column in 0..T::field_num_columns(field_index) {
+            let location = attr_index + column as u32;
+            if column > 0 {
+                context.enable_vertex_attrib_array(location);
+            }
+            context.vertex_attrib_pointer(
+                location,
+                num_comps as i32,                 // component count
+                ty,                               // type
+                T::field_normalized(field_index), // normalized
+                mem::size_of::<T>() as i32,       // stride
+                (T::field_offset(field_index) + column * column_bytes) as i32, // offset
+            );
+        }
+    }
+
+    /// Binds the buffer to a specified attribute with an explicit layout,
+    /// ignoring the layout of `T`.
+    ///
+    /// This is an escape hatch for buffers filled with data laid out by other tools,
+    /// e.g. interleaved vertex data from a glTF file.
+    /// `ty` is the component type, e.g. `WebGlRenderingContext::FLOAT`,
+    /// and `stride` and `offset` are in bytes.
+    /// Unlike [`bind_to_attr`][Buffer::bind_to_attr],
+    /// this also binds the buffer to `ARRAY_BUFFER`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn bind_to_attr_raw(
+        &self,
+        context: &B,
+        attr_index: u32,
+        num_comps: i32,
+        ty: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    ) {
+        context.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&self.buf));
+        context.vertex_attrib_pointer(attr_index, num_comps, ty, normalized, stride, offset);
+    }
+
+    /// Reinterprets the contents of the buffer as vertices of type `U` without copying.
+    ///
+    /// The view dereferences to a `Buffer<U>`,
+    /// so it can be drawn by programs with `U` as the attribute struct,
+    /// which read the same bytes with the stride and offsets of `U`.
+    /// The number of vertices in the view is the size of the buffer divided by the size of `U`.
+    ///
+    /// This is useful for packing data for multiple attribute layouts in one buffer.
+    /// The bytes are only interpreted by the GPU,
+    /// so a mismatching layout draws garbage instead of causing undefined behaviour.
+    /// Use [`view_checked`][Buffer::view_checked] if `U` is expected to have the same layout as `T`.
+    pub fn view<U: AttrStruct>(&self) -> BufferView<'_, U, B> {
+        let count = match mem::size_of::<U>() {
+            0 => 0,
+            size => self.byte_len() / size,
+        };
+        BufferView {
+            buffer: Buffer {
+                buf: self.buf.clone(),
+                count,
+                usage: self.usage,
+                _ph: PhantomData,
+            },
+            _source: PhantomData,
+        }
+    }
+
+    /// Reinterprets the buffer like [`view`][Buffer::view],
+    /// but checks that `U` has the same vertex layout as `T`.
+    ///
+    /// This is intended for sharing a buffer between programs with the same attributes,
+    /// whose derived attribute structs are distinct types.
+    /// Returns [`DrawError::AttrMismatch`][DrawError::AttrMismatch]
+    /// if the structs differ in their number of fields or size,
+    /// or if a field differs in its component type, component count, column count,
+    /// normalization or offset.
+    /// The GLSL names of the fields are not compared.
+    pub fn view_checked<U: AttrStruct>(&self) -> Result<BufferView<'_, U, B>, DrawError> {
+        if T::fields_count() != U::fields_count() || mem::size_of::<T>() != mem::size_of::<U>() {
+            return Err(DrawError::AttrMismatch { attribute: None });
+        }
+        for i in 0..U::fields_count() {
+            if T::field_type(i) != U::field_type(i)
+                || T::field_num_comps(i) != U::field_num_comps(i)
+                || T::field_num_columns(i) != U::field_num_columns(i)
+                || T::field_normalized(i) != U::field_normalized(i)
+                || T::field_offset(i) != U::field_offset(i)
+            {
+                return Err(DrawError::AttrMismatch {
+                    attribute: Some(U::field_gl_name(i)),
+                });
+            }
+        }
+        Ok(self.view())
+    }
+}
+
+/// A view of a [`Buffer`][Buffer] with a different attribute struct,
+/// returned by [`Buffer::view`][Buffer::view].
+pub struct BufferView<'t, U: AttrStruct, B: Backend = Context> {
+    buffer: Buffer<U, B>,
+    _source: PhantomData<&'t B::Buffer>,
+}
+
+impl<'t, U: AttrStruct, B: Backend> Deref for BufferView<'t, U, B> {
+    type Target = Buffer<U, B>;
+
+    fn deref(&self) -> &Buffer<U, B> {
+        &self.buffer
+    }
 }
 
 /// The `usage` parameter passed to `bufferData`.
@@ -234,9 +841,33 @@ impl BufferDataUsage {
             Self::StreamDraw => WebGlRenderingContext::STREAM_DRAW,
         }
     }
+
+    /// The corresponding GL constant, e.g. `WebGlRenderingContext::STATIC_DRAW`.
+    pub fn as_gl(self) -> u32 {
+        self.to_const()
+    }
+
+    /// The usage corresponding to a GL constant,
+    /// or `None` if the constant is not a usage supported by this type.
+    pub fn from_gl(value: u32) -> Option<Self> {
+        Some(match value {
+            WebGlRenderingContext::STATIC_DRAW => Self::StaticDraw,
+            WebGlRenderingContext::DYNAMIC_DRAW => Self::DynamicDraw,
+            WebGlRenderingContext::STREAM_DRAW => Self::StreamDraw,
+            _ => return None,
+        })
+    }
 }
 
 /// The type of rendering primitive.
+///
+/// When a range of vertices or indices is drawn,
+/// e.g. with a `Range<usize>` or a [`SubIndices`][SubIndices] as [`AbstractIndices`][AbstractIndices],
+/// the primitives are assembled from the drawn range only, as if it were the whole buffer:
+/// strips and fans start at the first vertex of the range,
+/// `LineLoop` connects the last vertex of the range back to the first vertex of the range,
+/// and vertices left over at the end of the range that do not form a complete primitive
+/// (e.g. the last vertex of `Lines` with an odd count) are not drawn.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RenderPrimitiveType {
     /// Draws a single dot.
@@ -267,6 +898,26 @@ impl RenderPrimitiveType {
             Self::Triangles => WebGlRenderingContext::TRIANGLES,
         }
     }
+
+    /// The corresponding GL constant, e.g. `WebGlRenderingContext::TRIANGLES`.
+    pub fn as_gl(self) -> u32 {
+        self.to_const()
+    }
+
+    /// The primitive type corresponding to a GL constant,
+    /// or `None` if the constant is not a primitive type.
+    pub fn from_gl(value: u32) -> Option<Self> {
+        Some(match value {
+            WebGlRenderingContext::POINTS => Self::Points,
+            WebGlRenderingContext::LINE_STRIP => Self::LineStrip,
+            WebGlRenderingContext::LINE_LOOP => Self::LineLoop,
+            WebGlRenderingContext::LINES => Self::Lines,
+            WebGlRenderingContext::TRIANGLE_STRIP => Self::TriangleStrip,
+            WebGlRenderingContext::TRIANGLE_FAN => Self::TriangleFan,
+            WebGlRenderingContext::TRIANGLES => Self::Triangles,
+            _ => return None,
+        })
+    }
 }
 
 fn resolve_range(items: impl RangeBounds<usize>, len: usize) -> (i32, i32) {
@@ -285,3 +936,381 @@ fn resolve_range(items: impl RangeBounds<usize>, len: usize) -> (i32, i32) {
 
     (start, end)
 }
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use crate::{
+        Attribute, BufferDataUsage, DrawError, GlCall, MockContext, PackedColor, Program,
+        ProgramData, RenderPrimitiveType, WebGlRenderingContext,
+    };
+
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Normalized {
+        data: ProgramData<MockContext>,
+        #[willow(normalized)]
+        a_signed: Attribute<[i8; 4]>,
+        a_unsigned: Attribute<[u16; 2]>,
+        a_color: Attribute<PackedColor>,
+    }
+
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Renamed {
+        data: ProgramData<MockContext>,
+        #[willow(normalized)]
+        b_signed: Attribute<[i8; 4]>,
+        b_unsigned: Attribute<[u16; 2]>,
+        b_color: Attribute<PackedColor>,
+    }
+
+    // Only the attribute structs of `Signed` and `Shorter` are used.
+    #[allow(dead_code)]
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Signed {
+        data: ProgramData<MockContext>,
+        #[willow(normalized)]
+        a_signed: Attribute<[i8; 4]>,
+        a_unsigned: Attribute<[i16; 2]>,
+        a_color: Attribute<PackedColor>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Shorter {
+        data: ProgramData<MockContext>,
+        #[willow(normalized)]
+        a_signed: Attribute<[i8; 4]>,
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Colors {
+        data: ProgramData<MockContext>,
+        #[willow(normalized)]
+        a_color: Attribute<nalgebra::Vector4<u8>>,
+        a_raw: Attribute<nalgebra::Vector4<u8>>,
+    }
+
+    #[cfg(feature = "mint")]
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Mint {
+        data: ProgramData<MockContext>,
+        a_position: Attribute<mint::Vector2<f32>>,
+        a_model: Attribute<mint::ColumnMatrix3<f32>>,
+    }
+
+    fn attrib_pointers(calls: &[GlCall]) -> Vec<(u32, bool)> {
+        calls
+            .iter()
+            .filter_map(|call| match *call {
+                GlCall::VertexAttribPointer { ty, normalized, .. } => Some((ty, normalized)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn normalized_flag_reaches_vertex_attrib_pointer() {
+        let context = MockContext::default();
+        let program = Normalized::create(&context).unwrap();
+        let buffer = Normalized::prepare_buffer(
+            &context,
+            &[NormalizedAttr {
+                a_signed: [-128, -1, 0, 127],
+                a_unsigned: [0, 65535],
+                a_color: PackedColor::from_rgba(255, 0, 0, 255),
+            }; 3],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+        context.take_calls();
+
+        program
+            .draw(&context, RenderPrimitiveType::Triangles, &buffer, ..)
+            .unwrap();
+        assert_eq!(
+            attrib_pointers(&context.take_calls()),
+            [
+                (WebGlRenderingContext::BYTE, true),
+                (WebGlRenderingContext::UNSIGNED_SHORT, false),
+                (WebGlRenderingContext::UNSIGNED_BYTE, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn field_normalized_is_passed_by_bind_to_attr() {
+        let context = MockContext::default();
+        let buffer = Normalized::prepare_buffer(
+            &context,
+            &[NormalizedAttr::default()],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+        context.take_calls();
+
+        for field in 0..3 {
+            buffer.bind_to_attr(&context, field as u32, field);
+        }
+        let calls = context.take_calls();
+        assert_eq!(
+            calls[0],
+            GlCall::VertexAttribPointer {
+                index: 0,
+                size: 4,
+                ty: WebGlRenderingContext::BYTE,
+                normalized: true,
+                stride: 12,
+                offset: 0,
+            }
+        );
+        assert_eq!(
+            attrib_pointers(&calls),
+            [
+                (WebGlRenderingContext::BYTE, true),
+                (WebGlRenderingContext::UNSIGNED_SHORT, false),
+                (WebGlRenderingContext::UNSIGNED_BYTE, true),
+            ]
+        );
+    }
+
+    #[cfg(feature = "half")]
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct HalfFloat {
+        data: ProgramData<MockContext>,
+        a_position: Attribute<[f32; 2]>,
+        a_height: Attribute<half::f16>,
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn unsupported_attrib_type_is_an_error() {
+        use crate::{DrawError, WebGl2RenderingContext};
+
+        let context = MockContext::default();
+        let result = HalfFloat::prepare_buffer(
+            &context,
+            &[HalfFloatAttr::default()],
+            BufferDataUsage::StaticDraw,
+        );
+        assert_eq!(
+            result.err(),
+            Some(DrawError::UnsupportedAttribType {
+                attribute: "a_height",
+                ty: WebGl2RenderingContext::HALF_FLOAT,
+            })
+        );
+        assert!(context.calls().is_empty());
+    }
+
+    #[test]
+    fn allocate_in_lost_context_is_an_error() {
+        let context = MockContext::default();
+        context.lose_context();
+
+        let result = Normalized::prepare_buffer(
+            &context,
+            &[NormalizedAttr::default()],
+            BufferDataUsage::StaticDraw,
+        );
+        assert_eq!(result.err(), Some(crate::DrawError::ContextLost));
+        assert!(context.calls().is_empty());
+    }
+
+    /// An attribute type without `Debug` or `Default`.
+    #[derive(Clone, Copy)]
+    struct Opaque(u32);
+
+    impl crate::AttributeType for Opaque {
+        fn num_comps() -> usize {
+            1
+        }
+
+        fn gl_type() -> u32 {
+            WebGlRenderingContext::UNSIGNED_INT
+        }
+    }
+
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct OpaqueAttrs {
+        data: ProgramData<MockContext>,
+        a_id: Attribute<Opaque>,
+    }
+
+    #[test]
+    fn attr_struct_does_not_require_debug_or_default() {
+        let context = MockContext::default();
+        let vertex = OpaqueAttrsAttr { a_id: Opaque(7) };
+        let buffer =
+            OpaqueAttrs::prepare_buffer(&context, &[vertex], BufferDataUsage::StaticDraw).unwrap();
+        assert_eq!((buffer.len(), vertex.a_id.0), (1, 7));
+
+        let attr = NormalizedAttr::default();
+        assert_eq!(attr.a_signed, [0; 4]);
+        assert!(format!("{:?}", attr).starts_with("NormalizedAttr { a_signed: [0, 0, 0, 0]"));
+    }
+
+    #[cfg(feature = "mint")]
+    #[test]
+    fn mint_matrix_attribute_binds_each_column() {
+        let context = MockContext::default();
+        let program = Mint::create(&context).unwrap();
+        let identity = mint::ColumnMatrix3::from([[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]]);
+        let buffer = Mint::prepare_buffer(
+            &context,
+            &[MintAttr {
+                a_position: mint::Vector2 { x: 0., y: 1. },
+                a_model: identity,
+            }],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+        context.take_calls();
+
+        program
+            .draw(&context, RenderPrimitiveType::Points, &buffer, ..)
+            .unwrap();
+        let calls = context.take_calls();
+        let pointers: Vec<_> = calls
+            .iter()
+            .filter_map(|call| match *call {
+                GlCall::VertexAttribPointer {
+                    index,
+                    size,
+                    stride,
+                    offset,
+                    ..
+                } => Some((index, size, stride, offset)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            pointers,
+            [(0, 2, 44, 0), (1, 3, 44, 8), (2, 3, 44, 20), (3, 3, 44, 32)]
+        );
+        for location in 1..4 {
+            assert!(calls.contains(&GlCall::EnableVertexAttribArray(location)));
+        }
+    }
+
+    #[test]
+    fn update_slice_rejects_overflowing_offset() {
+        let context = MockContext::default();
+        let buffer = Normalized::prepare_buffer(
+            &context,
+            &[NormalizedAttr::default(); 2],
+            BufferDataUsage::DynamicDraw,
+        )
+        .unwrap();
+        context.take_calls();
+
+        let slice = [NormalizedAttr::default()];
+        assert!(buffer
+            .update_slice(&context, usize::MAX, &slice, false)
+            .is_err());
+        assert!(buffer.update_slice(&context, 2, &slice, false).is_err());
+        assert!(context.take_calls().is_empty());
+
+        buffer.update_slice(&context, 1, &slice, false).unwrap();
+        assert!(context
+            .take_calls()
+            .iter()
+            .any(|call| matches!(call, GlCall::BufferSubData { .. })));
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn normalized_nalgebra_color_reaches_vertex_attrib_pointer() {
+        let context = MockContext::default();
+        let program = Colors::create(&context).unwrap();
+        let color = nalgebra::Vector4::new(255, 128, 0, 255);
+        let buffer = Colors::prepare_buffer(
+            &context,
+            &[ColorsAttr {
+                a_color: color,
+                a_raw: color,
+            }],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+        context.take_calls();
+
+        program
+            .draw(&context, RenderPrimitiveType::Points, &buffer, ..)
+            .unwrap();
+        let pointers: Vec<_> = context
+            .take_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                GlCall::VertexAttribPointer {
+                    size,
+                    ty,
+                    normalized,
+                    ..
+                } => Some((size, ty, normalized)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            pointers,
+            [
+                (4, WebGlRenderingContext::UNSIGNED_BYTE, true),
+                (4, WebGlRenderingContext::UNSIGNED_BYTE, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn view_checked_accepts_matching_layout() {
+        let context = MockContext::default();
+        let buffer = Normalized::prepare_buffer(
+            &context,
+            &[NormalizedAttr::default(); 3],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+
+        let view = buffer.view_checked::<RenamedAttr>().unwrap();
+        assert_eq!(view.len(), 3);
+
+        let program = Renamed::create(&context).unwrap();
+        context.take_calls();
+        program
+            .draw(&context, RenderPrimitiveType::Points, &view, ..)
+            .unwrap();
+        assert!(context.calls().contains(&GlCall::DrawArrays {
+            mode: WebGlRenderingContext::POINTS,
+            first: 0,
+            count: 3,
+        }));
+    }
+
+    #[test]
+    fn view_checked_rejects_mismatching_layout() {
+        let context = MockContext::default();
+        let buffer = Normalized::prepare_buffer(
+            &context,
+            &[NormalizedAttr::default(); 3],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            buffer.view_checked::<SignedAttr>().err(),
+            Some(DrawError::AttrMismatch {
+                attribute: Some("a_unsigned")
+            })
+        ));
+        assert!(matches!(
+            buffer.view_checked::<ShorterAttr>().err(),
+            Some(DrawError::AttrMismatch { attribute: None })
+        ));
+    }
+}
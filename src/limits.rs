@@ -0,0 +1,158 @@
+use anyhow::{bail, Result};
+use web_sys::{WebGl2RenderingContext, WebGlRenderingContext};
+
+use crate::{Backend, Context};
+
+/// Implementation limits of a context, returned by [`Context::limits`][Context::limits].
+///
+/// The WebGL specification guarantees minimum values for these limits,
+/// but devices usually support more.
+/// If a limit cannot be queried, e.g. because the context is lost,
+/// it falls back to the minimum value guaranteed by the specification,
+/// and `MAX_VIEWPORT_DIMS` falls back to `MAX_TEXTURE_SIZE` in both dimensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlLimits {
+    /// The maximum width and height of a 2D texture (`MAX_TEXTURE_SIZE`).
+    pub max_texture_size: u32,
+    /// The maximum width and height of a cube map texture face (`MAX_CUBE_MAP_TEXTURE_SIZE`).
+    pub max_cube_map_texture_size: u32,
+    /// The maximum width and height of a renderbuffer (`MAX_RENDERBUFFER_SIZE`).
+    pub max_renderbuffer_size: u32,
+    /// The maximum width and height of the viewport (`MAX_VIEWPORT_DIMS`).
+    pub max_viewport_dims: (u32, u32),
+    /// The maximum number of vertex attributes (`MAX_VERTEX_ATTRIBS`).
+    pub max_vertex_attribs: u32,
+    /// The maximum number of textures sampled by a fragment shader (`MAX_TEXTURE_IMAGE_UNITS`).
+    pub max_texture_image_units: u32,
+    /// The maximum number of textures sampled by a vertex shader
+    /// (`MAX_VERTEX_TEXTURE_IMAGE_UNITS`), which may be 0.
+    pub max_vertex_texture_image_units: u32,
+    /// The maximum number of textures sampled by both shaders combined
+    /// (`MAX_COMBINED_TEXTURE_IMAGE_UNITS`).
+    pub max_combined_texture_image_units: u32,
+    /// The maximum number of samples in a multisampled renderbuffer (`MAX_SAMPLES`).
+    /// `None` in WebGL 1.
+    pub max_samples: Option<u32>,
+    /// The maximum width, height and depth of a 3D texture (`MAX_3D_TEXTURE_SIZE`).
+    /// `None` in WebGL 1.
+    pub max_3d_texture_size: Option<u32>,
+    /// The maximum number of layers in an array texture (`MAX_ARRAY_TEXTURE_LAYERS`).
+    /// `None` in WebGL 1.
+    pub max_array_texture_layers: Option<u32>,
+    /// The maximum number of draw buffers of a framebuffer (`MAX_DRAW_BUFFERS`).
+    /// `None` in WebGL 1.
+    pub max_draw_buffers: Option<u32>,
+}
+
+impl GlLimits {
+    pub(crate) fn query<B: Backend>(context: &B) -> Self {
+        // Falls back to the minimum required by the WebGL specification.
+        let get = |param, minimum| {
+            context
+                .get_parameter_i32(param)
+                .map_or(minimum, |value| value as u32)
+        };
+        let max_texture_size = get(WebGlRenderingContext::MAX_TEXTURE_SIZE, 64);
+        let viewport_dims = context
+            .get_parameter_i32_array(WebGlRenderingContext::MAX_VIEWPORT_DIMS)
+            .unwrap_or_default();
+        let webgl2 = context.is_webgl2();
+
+        Self {
+            max_texture_size,
+            max_cube_map_texture_size: get(WebGlRenderingContext::MAX_CUBE_MAP_TEXTURE_SIZE, 16),
+            max_renderbuffer_size: get(WebGlRenderingContext::MAX_RENDERBUFFER_SIZE, 1),
+            max_viewport_dims: match viewport_dims.as_slice() {
+                &[width, height] => (width as u32, height as u32),
+                _ => (max_texture_size, max_texture_size),
+            },
+            max_vertex_attribs: get(WebGlRenderingContext::MAX_VERTEX_ATTRIBS, 8),
+            max_texture_image_units: get(WebGlRenderingContext::MAX_TEXTURE_IMAGE_UNITS, 8),
+            max_vertex_texture_image_units: get(
+                WebGlRenderingContext::MAX_VERTEX_TEXTURE_IMAGE_UNITS,
+                0,
+            ),
+            max_combined_texture_image_units: get(
+                WebGlRenderingContext::MAX_COMBINED_TEXTURE_IMAGE_UNITS,
+                8,
+            ),
+            max_samples: webgl2.then(|| get(WebGl2RenderingContext::MAX_SAMPLES, 4)),
+            max_3d_texture_size: webgl2
+                .then(|| get(WebGl2RenderingContext::MAX_3D_TEXTURE_SIZE, 256)),
+            max_array_texture_layers: webgl2
+                .then(|| get(WebGl2RenderingContext::MAX_ARRAY_TEXTURE_LAYERS, 256)),
+            max_draw_buffers: webgl2.then(|| get(WebGl2RenderingContext::MAX_DRAW_BUFFERS, 4)),
+        }
+    }
+
+    /// Returns an error if a `width` by `height` 2D texture exceeds `MAX_TEXTURE_SIZE`.
+    pub(crate) fn check_texture_size(&self, width: u32, height: u32) -> Result<()> {
+        if width > self.max_texture_size || height > self.max_texture_size {
+            bail!(
+                "Cannot allocate a {}x{} texture (MAX_TEXTURE_SIZE = {})",
+                width,
+                height,
+                self.max_texture_size
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns an error if a `width` by `height` renderbuffer exceeds `MAX_RENDERBUFFER_SIZE`.
+    pub(crate) fn check_renderbuffer_size(&self, width: u32, height: u32) -> Result<()> {
+        if width > self.max_renderbuffer_size || height > self.max_renderbuffer_size {
+            bail!(
+                "Cannot allocate a {}x{} renderbuffer (MAX_RENDERBUFFER_SIZE = {})",
+                width,
+                height,
+                self.max_renderbuffer_size
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Context {
+    /// The implementation limits of this context.
+    ///
+    /// The limits are queried on the first call and cached afterwards.
+    /// Texture and framebuffer constructors validate their sizes against these limits.
+    pub fn limits(&self) -> &GlLimits {
+        self.limits.get_or_init(|| GlLimits::query(self))
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use web_sys::WebGlRenderingContext;
+
+    use crate::{Backend, MockContext};
+
+    #[test]
+    fn limits_are_queried_through_backend() {
+        let context = MockContext::default();
+        context.set_parameter(WebGlRenderingContext::MAX_TEXTURE_SIZE, &[4096]);
+        context.set_parameter(WebGlRenderingContext::MAX_VIEWPORT_DIMS, &[8192, 4096]);
+
+        let limits = context.limits();
+        assert_eq!(limits.max_texture_size, 4096);
+        assert_eq!(limits.max_viewport_dims, (8192, 4096));
+        assert_eq!(limits.max_samples, None);
+    }
+
+    #[test]
+    fn unavailable_limits_fall_back_to_minimums() {
+        let context = MockContext::default();
+        context.set_parameter(WebGlRenderingContext::MAX_TEXTURE_SIZE, &[2048]);
+
+        let limits = context.limits();
+        assert_eq!(limits.max_texture_size, 2048);
+        assert_eq!(limits.max_cube_map_texture_size, 16);
+        assert_eq!(limits.max_renderbuffer_size, 1);
+        assert_eq!(limits.max_viewport_dims, (2048, 2048));
+        assert_eq!(limits.max_vertex_attribs, 8);
+        assert_eq!(limits.max_texture_image_units, 8);
+        assert_eq!(limits.max_vertex_texture_image_units, 0);
+        assert_eq!(limits.max_combined_texture_image_units, 8);
+    }
+}
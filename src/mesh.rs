@@ -0,0 +1,164 @@
+use std::mem;
+
+use web_sys::WebGl2RenderingContext;
+
+use crate::index::draw_sections;
+use crate::{
+    Buffer, Context, DrawBuilder, DrawError, Indices, Program, RenderPrimitiveType, Result,
+};
+
+/// A program together with the vertex buffer and the indices it draws.
+///
+/// The indices are split into sections with their own primitive types,
+/// e.g. the triangles and the outlines of a model sharing the same vertices.
+/// All sections are drawn with one program bind and one attribute setup,
+/// switching only the index buffer between sections.
+///
+/// ```ignore
+/// let mut mesh = Mesh::new(&program, buffer, triangles, RenderPrimitiveType::Triangles);
+/// mesh.add_section(RenderPrimitiveType::Lines, outlines);
+/// mesh.draw(&context, |program| program.with_uniforms().transform(transform))?;
+/// ```
+pub struct Mesh<'p, P: Program<Backend = Context>> {
+    program: &'p P,
+    /// The vertex buffer of the mesh.
+    pub buffer: Buffer<P::AttrStruct>,
+    /// The sections of the mesh, drawn in order,
+    /// each with the primitive type and the indices of the vertices to draw.
+    pub sections: Vec<(RenderPrimitiveType, Indices)>,
+}
+
+impl<'p, P: Program<Backend = Context>> Mesh<'p, P> {
+    /// Creates a mesh drawn by `program` with a single section.
+    pub fn new(
+        program: &'p P,
+        buffer: Buffer<P::AttrStruct>,
+        indices: Indices,
+        mode: RenderPrimitiveType,
+    ) -> Self {
+        Self {
+            program,
+            buffer,
+            sections: vec![(mode, indices)],
+        }
+    }
+
+    /// Appends a section drawn after the existing sections.
+    pub fn add_section(&mut self, mode: RenderPrimitiveType, indices: Indices) {
+        self.sections.push((mode, indices));
+    }
+
+    /// The program that draws the mesh.
+    pub fn program(&self) -> &'p P {
+        self.program
+    }
+
+    /// Creates a vertex buffer where each triangle of the mesh has its own three vertices,
+    /// e.g. for flat shading.
+    ///
+    /// WebGL has no control over the provoking vertex,
+    /// so a `flat` varying (WebGL 2) of a triangle always takes the value of its last vertex,
+    /// which is shared with the adjacent triangles in an indexed mesh.
+    /// In the copy, vertex `3 * i + j` of the buffer is the `j`th corner of the `i`th triangle,
+    /// counting the triangles of all sections in order,
+    /// so per-face values such as face normals can be written to the vertices of each triangle
+    /// with [`Buffer::update_slice`][Buffer::update_slice].
+    ///
+    /// The copy is not indexed, so it is drawn with `drawArrays` by passing `..` as the indices:
+    /// ```ignore
+    /// let flat = mesh.to_flat(&context)?;
+    /// program.with_uniforms().draw(&context, RenderPrimitiveType::Triangles, &flat, &..)?;
+    /// ```
+    /// A single section can be drawn with the range of its vertices,
+    /// which starts after the indices of the previous sections.
+    ///
+    /// Flat shading from positions alone does not need this copy:
+    /// the fragment shader can compute the face normal as
+    /// `normalize(cross(dFdx(v_position), dFdy(v_position)))`,
+    /// which requires WebGL 2 or the `OES_standard_derivatives` extension.
+    ///
+    /// The vertices are copied on the GPU and the indices are read back from the GPU,
+    /// which requires WebGL 2.
+    /// Returns an error if the context is WebGL 1,
+    /// if a section is not drawn as [`Triangles`][RenderPrimitiveType::Triangles],
+    /// or if an index is out of range of the vertex buffer.
+    pub fn to_flat(&self, context: &Context) -> Result<Buffer<P::AttrStruct>> {
+        let gl2 = context.require_webgl2("Flattening a mesh")?;
+
+        let mut corners = Vec::with_capacity(self.sections.len());
+        for (mode, indices) in &self.sections {
+            if *mode != RenderPrimitiveType::Triangles {
+                anyhow::bail!("Cannot flatten a section drawn as {:?}", mode);
+            }
+            let section = indices.read_back(context)?;
+            if let Some(&index) = section.iter().find(|&&i| i as usize >= self.buffer.len()) {
+                anyhow::bail!(
+                    "Index {} is out of range of the {} vertices in the buffer",
+                    index,
+                    self.buffer.len()
+                );
+            }
+            corners.push(section);
+        }
+
+        let total: usize = corners.iter().map(Vec::len).sum();
+        let stride = mem::size_of::<P::AttrStruct>();
+        let buffer = Buffer::allocate_zeroed(context, total, self.buffer.usage)?;
+
+        gl2.bind_buffer(
+            WebGl2RenderingContext::COPY_READ_BUFFER,
+            Some(&self.buffer.buf),
+        );
+        gl2.bind_buffer(WebGl2RenderingContext::COPY_WRITE_BUFFER, Some(&buffer.buf));
+        // copy runs of consecutive indices with a single call
+        let indices: Vec<u32> = corners.iter().flatten().copied().collect();
+        let mut start = 0;
+        while start < indices.len() {
+            let mut end = start + 1;
+            while end < indices.len() && indices[end] == indices[end - 1] + 1 {
+                end += 1;
+            }
+            gl2.copy_buffer_sub_data_with_i32_and_i32_and_i32(
+                WebGl2RenderingContext::COPY_READ_BUFFER,
+                WebGl2RenderingContext::COPY_WRITE_BUFFER,
+                (indices[start] as usize * stride) as i32,
+                (start * stride) as i32,
+                ((end - start) * stride) as i32,
+            );
+            start = end;
+        }
+
+        Ok(buffer)
+    }
+
+    /// Draws all sections of the mesh with the uniforms set by `uniforms`.
+    ///
+    /// `uniforms` receives the program and should return the builder
+    /// from its `with_uniforms` method after all uniforms are set.
+    pub fn draw<U, F>(&self, context: &Context, uniforms: F) -> Result<()>
+    where
+        U: DrawBuilder<Program = P>,
+        F: FnOnce(&'p P) -> U,
+    {
+        uniforms(self.program).apply_uniforms(context)?;
+        self.draw_sections(context)?;
+        Ok(())
+    }
+
+    /// Draws all sections of the mesh without reassigning uniforms.
+    ///
+    /// See [`Program::draw`][Program::draw].
+    pub fn draw_without_uniforms(&self, context: &Context) -> Result<(), DrawError> {
+        self.program.use_program(context);
+        self.draw_sections(context)
+    }
+
+    fn draw_sections(&self, context: &Context) -> Result<(), DrawError> {
+        draw_sections(
+            context,
+            self.program,
+            &self.buffer,
+            self.sections.iter().map(|(mode, indices)| (*mode, indices)),
+        )
+    }
+}
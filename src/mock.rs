@@ -0,0 +1,641 @@
+//! A headless [`Backend`][Backend] that records calls instead of rendering.
+//!
+//! This module is only available with the `mock` feature.
+//! It allows unit tests (outside a browser) to assert the calls issued by the draw path.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+
+use once_cell::unsync::OnceCell;
+use web_sys::WebGl2RenderingContext;
+
+use crate::{ActiveInfo, Backend, DrawError, GlLimits, UniformValue};
+
+/// A call recorded by [`MockContext`][MockContext].
+#[derive(Debug, Clone, PartialEq)]
+pub enum GlCall {
+    /// `createBuffer`, returning the buffer ID allocated by the mock.
+    CreateBuffer(u32),
+    /// `bindBuffer`
+    BindBuffer {
+        /// The binding point, e.g. `WebGlRenderingContext::ARRAY_BUFFER`.
+        target: u32,
+        /// The ID of the bound buffer, or `None` if unbound.
+        buffer: Option<u32>,
+    },
+    /// `bufferData`
+    BufferData {
+        /// The binding point of the updated buffer.
+        target: u32,
+        /// The uploaded data in little-endian byte order.
+        data: Vec<u8>,
+        /// The usage hint.
+        usage: u32,
+    },
+    /// `bufferData` with a size instead of data
+    BufferDataSize {
+        /// The binding point of the reallocated buffer.
+        target: u32,
+        /// The new size in bytes.
+        size: i32,
+        /// The usage hint.
+        usage: u32,
+    },
+    /// `bufferSubData`
+    BufferSubData {
+        /// The binding point of the updated buffer.
+        target: u32,
+        /// The byte offset of the updated range.
+        offset: i32,
+        /// The uploaded data in little-endian byte order.
+        data: Vec<u8>,
+    },
+    /// `getExtension`
+    GetExtension(String),
+    /// `vertexAttribPointer`
+    VertexAttribPointer {
+        /// The attribute location.
+        index: u32,
+        /// The number of components.
+        size: i32,
+        /// The component type.
+        ty: u32,
+        /// Whether the components are normalized.
+        normalized: bool,
+        /// The stride in bytes.
+        stride: i32,
+        /// The offset in bytes.
+        offset: i32,
+    },
+    /// `drawArrays`
+    DrawArrays {
+        /// The primitive type.
+        mode: u32,
+        /// The first vertex to draw.
+        first: i32,
+        /// The number of vertices to draw.
+        count: i32,
+    },
+    /// `drawElements`
+    DrawElements {
+        /// The primitive type.
+        mode: u32,
+        /// The number of indices to draw.
+        count: i32,
+        /// The index type.
+        ty: u32,
+        /// The offset into the index buffer in bytes.
+        offset: i32,
+    },
+    /// `drawArraysInstanced`
+    DrawArraysInstanced {
+        /// The primitive type.
+        mode: u32,
+        /// The first vertex to draw.
+        first: i32,
+        /// The number of vertices to draw.
+        count: i32,
+        /// The number of instances to draw.
+        instances: i32,
+    },
+    /// `drawElementsInstanced`
+    DrawElementsInstanced {
+        /// The primitive type.
+        mode: u32,
+        /// The number of indices to draw.
+        count: i32,
+        /// The index type.
+        ty: u32,
+        /// The offset into the index buffer in bytes.
+        offset: i32,
+        /// The number of instances to draw.
+        instances: i32,
+    },
+    /// `vertexAttribDivisor`
+    VertexAttribDivisor {
+        /// The attribute location.
+        index: u32,
+        /// The number of instances drawn before the attribute advances.
+        divisor: u32,
+    },
+    /// `createProgram`, returning the program ID allocated by the mock.
+    CreateProgram(u32),
+    /// `createShader`
+    CreateShader {
+        /// The shader type, e.g. `WebGlRenderingContext::VERTEX_SHADER`.
+        ty: u32,
+        /// The shader ID allocated by the mock.
+        shader: u32,
+    },
+    /// `deleteProgram` with the ID of the program.
+    DeleteProgram(u32),
+    /// `deleteShader` with the ID of the shader.
+    DeleteShader(u32),
+    /// `shaderSource`
+    ShaderSource {
+        /// The ID of the shader.
+        shader: u32,
+        /// The new source code.
+        source: String,
+    },
+    /// `compileShader` with the ID of the shader.
+    CompileShader(u32),
+    /// `attachShader`
+    AttachShader {
+        /// The ID of the program.
+        program: u32,
+        /// The ID of the shader.
+        shader: u32,
+    },
+    /// `linkProgram` with the ID of the program.
+    LinkProgram(u32),
+    /// `validateProgram` with the ID of the program.
+    ValidateProgram(u32),
+    /// `useProgram` with the ID of the program, or `None` if unbound.
+    UseProgram(Option<u32>),
+    /// `getAttribLocation`
+    GetAttribLocation {
+        /// The ID of the program.
+        program: u32,
+        /// The GLSL name of the attribute.
+        name: String,
+    },
+    /// `enableVertexAttribArray` with the attribute location.
+    EnableVertexAttribArray(u32),
+    /// `getUniformLocation`
+    GetUniformLocation {
+        /// The ID of the program.
+        program: u32,
+        /// The GLSL name of the uniform.
+        name: String,
+    },
+    /// `uniform*` and `uniformMatrix*` calls.
+    Uniform {
+        /// The GLSL name that the location was retrieved with.
+        location: String,
+        /// The assigned value.
+        value: UniformValue<'static>,
+    },
+}
+
+/// A mock rendering context that records all calls into a list of [`GlCall`][GlCall]s.
+///
+/// All extensions are reported as supported.
+/// A program is always reported as in use,
+/// and the context is reported as lost only after [`lose_context`][MockContext::lose_context] is called.
+///
+/// Shaders always compile and programs always link and validate.
+/// Every attribute and uniform is reported as active
+/// unless it is deactivated with [`deactivate`][MockContext::deactivate].
+/// Attributes are assigned consecutive locations in the order they are queried.
+/// Parameters such as implementation limits are reported as unavailable
+/// unless they are set with [`set_parameter`][MockContext::set_parameter].
+///
+/// A program derived with the [`Program`][crate::Program] macro runs on the mock context
+/// if its data field has the type `ProgramData<MockContext>`.
+/// Its uniforms are then declared as `Uniform<T, MockContext>`.
+///
+/// # Example
+/// ```
+/// use willow::{
+///     BufferDataUsage, GlCall, Indices, MockContext, Program, ProgramData, RenderPrimitiveType,
+///     Uniform, UniformValue, VectorSize, WebGlRenderingContext,
+/// };
+///
+/// #[derive(Program)]
+/// #[willow(vert = "void main() {}", frag = "void main() {}")]
+/// struct Flat {
+///     data: ProgramData<MockContext>,
+///     a_position: willow::Attribute<[f32; 2]>,
+///     u_depth: Uniform<f32, MockContext>,
+/// }
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let context = MockContext::default();
+/// let program = Flat::create(&context)?;
+/// let buffer = Flat::prepare_buffer(
+///     &context,
+///     &[FlatAttr { a_position: [0., 0.] }; 4],
+///     BufferDataUsage::StaticDraw,
+/// )?;
+/// let indices = Indices::new(&context, &[0, 1, 2, 2, 1, 3], BufferDataUsage::StaticDraw)?;
+/// context.take_calls();
+///
+/// program
+///     .with_uniforms()
+///     .u_depth(0.5)
+///     .draw(&context, RenderPrimitiveType::Triangles, &buffer, &indices.subindex(3..))?;
+/// let calls = context.take_calls();
+/// assert!(calls.contains(&GlCall::Uniform {
+///     location: "u_depth".to_string(),
+///     value: UniformValue::Float {
+///         size: VectorSize::One,
+///         data: vec![0.5].into(),
+///     },
+/// }));
+/// assert!(calls.contains(&GlCall::DrawElements {
+///     mode: WebGlRenderingContext::TRIANGLES,
+///     count: 3,
+///     ty: WebGlRenderingContext::UNSIGNED_SHORT,
+///     // The offset is in bytes, i.e. 3 indices of 2 bytes each.
+///     offset: 6,
+/// }));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct MockContext {
+    calls: RefCell<Vec<GlCall>>,
+    next_buffer: Cell<u32>,
+    next_object: Cell<u32>,
+    context_lost: Cell<bool>,
+    webgl2: Cell<bool>,
+    inactive: RefCell<HashSet<String>>,
+    fail_links: Cell<bool>,
+    failed_links: RefCell<HashSet<u32>>,
+    attrib_locations: RefCell<HashMap<(u32, String), u32>>,
+    shader_sources: RefCell<HashMap<u32, String>>,
+    parameters: RefCell<HashMap<u32, Vec<i32>>>,
+    active_uniforms: RefCell<Vec<ActiveInfo>>,
+    active_attributes: RefCell<Vec<ActiveInfo>>,
+    limits: OnceCell<GlLimits>,
+}
+
+impl MockContext {
+    /// Returns a copy of the calls recorded so far.
+    pub fn calls(&self) -> Vec<GlCall> {
+        self.calls.borrow().clone()
+    }
+
+    /// Returns the calls recorded so far and clears the record.
+    pub fn take_calls(&self) -> Vec<GlCall> {
+        self.calls.take()
+    }
+
+    /// Simulates losing the context, so that subsequent draw calls fail with
+    /// [`DrawError::ContextLost`][crate::DrawError::ContextLost].
+    pub fn lose_context(&self) {
+        self.context_lost.set(true);
+    }
+
+    /// Makes the context report WebGL 2 support if `webgl2` is true.
+    /// The context reports WebGL 1 by default.
+    pub fn set_webgl2(&self, webgl2: bool) {
+        self.webgl2.set(webgl2);
+    }
+
+    /// Marks the attribute or uniform with the GLSL name `name` as inactive,
+    /// e.g. as if it were optimized out by the GLSL compiler,
+    /// so that querying its location returns `None`.
+    pub fn deactivate(&self, name: &str) {
+        self.inactive.borrow_mut().insert(name.to_string());
+    }
+
+    /// Makes subsequent `linkProgram` calls fail if `fail` is true,
+    /// e.g. as if the varyings of the shaders did not match.
+    pub fn fail_links(&self, fail: bool) {
+        self.fail_links.set(fail);
+    }
+
+    /// Sets the value reported for the parameter `pname`,
+    /// e.g. `WebGlRenderingContext::MAX_TEXTURE_IMAGE_UNITS`.
+    ///
+    /// [`limits`][Backend::limits] are cached when first queried,
+    /// so they must be set before using the context.
+    pub fn set_parameter(&self, pname: u32, value: &[i32]) {
+        self.parameters.borrow_mut().insert(pname, value.to_vec());
+    }
+
+    /// Sets the active uniforms reported for every linked program.
+    pub fn set_active_uniforms(&self, uniforms: &[ActiveInfo]) {
+        *self.active_uniforms.borrow_mut() = uniforms.to_vec();
+    }
+
+    /// Sets the active attributes reported for every linked program.
+    pub fn set_active_attributes(&self, attributes: &[ActiveInfo]) {
+        *self.active_attributes.borrow_mut() = attributes.to_vec();
+    }
+
+    fn next_object(&self) -> u32 {
+        let id = self.next_object.get() + 1;
+        self.next_object.set(id);
+        id
+    }
+
+    fn record(&self, call: GlCall) {
+        self.calls.borrow_mut().push(call);
+    }
+}
+
+impl Backend for MockContext {
+    type Buffer = u32;
+    type Program = u32;
+    type Shader = u32;
+    type UniformLocation = String;
+
+    fn create_buffer(&self) -> Option<u32> {
+        let id = self.next_buffer.get() + 1;
+        self.next_buffer.set(id);
+        self.record(GlCall::CreateBuffer(id));
+        Some(id)
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<&u32>) {
+        self.record(GlCall::BindBuffer {
+            target,
+            buffer: buffer.copied(),
+        });
+    }
+
+    fn buffer_data_u8(&self, target: u32, data: &[u8], usage: u32) {
+        self.record(GlCall::BufferData {
+            target,
+            data: data.to_vec(),
+            usage,
+        });
+    }
+
+    fn buffer_data_size(&self, target: u32, size: i32, usage: u32) {
+        self.record(GlCall::BufferDataSize {
+            target,
+            size,
+            usage,
+        });
+    }
+
+    fn buffer_data_u16(&self, target: u32, data: &[u16], usage: u32) {
+        self.record(GlCall::BufferData {
+            target,
+            data: data.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            usage,
+        });
+    }
+
+    fn buffer_data_u32(&self, target: u32, data: &[u32], usage: u32) {
+        self.record(GlCall::BufferData {
+            target,
+            data: data.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            usage,
+        });
+    }
+
+    fn buffer_sub_data_u8(&self, target: u32, offset: i32, data: &[u8]) {
+        self.record(GlCall::BufferSubData {
+            target,
+            offset,
+            data: data.to_vec(),
+        });
+    }
+
+    fn buffer_sub_data_u16(&self, target: u32, offset: i32, data: &[u16]) {
+        self.record(GlCall::BufferSubData {
+            target,
+            offset,
+            data: data.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        });
+    }
+
+    fn buffer_sub_data_u32(&self, target: u32, offset: i32, data: &[u32]) {
+        self.record(GlCall::BufferSubData {
+            target,
+            offset,
+            data: data.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        });
+    }
+
+    fn is_context_lost(&self) -> bool {
+        self.context_lost.get()
+    }
+
+    fn has_current_program(&self) -> bool {
+        true
+    }
+
+    fn enable_extension(&self, name: &str) -> bool {
+        self.record(GlCall::GetExtension(name.to_string()));
+        true
+    }
+
+    fn supports_attrib_type(&self, ty: u32) -> bool {
+        ty != WebGl2RenderingContext::HALF_FLOAT || self.is_webgl2()
+    }
+
+    fn vertex_attrib_pointer(
+        &self,
+        index: u32,
+        size: i32,
+        ty: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    ) {
+        self.record(GlCall::VertexAttribPointer {
+            index,
+            size,
+            ty,
+            normalized,
+            stride,
+            offset,
+        });
+    }
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        self.record(GlCall::DrawArrays { mode, first, count });
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, ty: u32, offset: i32) {
+        self.record(GlCall::DrawElements {
+            mode,
+            count,
+            ty,
+            offset,
+        });
+    }
+
+    fn supports_instancing(&self) -> bool {
+        true
+    }
+
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32) {
+        self.record(GlCall::DrawArraysInstanced {
+            mode,
+            first,
+            count,
+            instances,
+        });
+    }
+
+    fn draw_elements_instanced(&self, mode: u32, count: i32, ty: u32, offset: i32, instances: i32) {
+        self.record(GlCall::DrawElementsInstanced {
+            mode,
+            count,
+            ty,
+            offset,
+            instances,
+        });
+    }
+
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32) -> Result<(), DrawError> {
+        self.record(GlCall::VertexAttribDivisor { index, divisor });
+        Ok(())
+    }
+
+    fn create_program(&self) -> Option<u32> {
+        let id = self.next_object();
+        self.record(GlCall::CreateProgram(id));
+        Some(id)
+    }
+
+    fn delete_program(&self, program: &u32) {
+        self.record(GlCall::DeleteProgram(*program));
+    }
+
+    fn create_shader(&self, ty: u32) -> Option<u32> {
+        let shader = self.next_object();
+        self.record(GlCall::CreateShader { ty, shader });
+        Some(shader)
+    }
+
+    fn delete_shader(&self, shader: &u32) {
+        self.record(GlCall::DeleteShader(*shader));
+    }
+
+    fn shader_source(&self, shader: &u32, source: &str) {
+        self.record(GlCall::ShaderSource {
+            shader: *shader,
+            source: source.to_string(),
+        });
+        self.shader_sources
+            .borrow_mut()
+            .insert(*shader, source.to_string());
+    }
+
+    fn get_shader_source(&self, shader: &u32) -> String {
+        self.shader_sources
+            .borrow()
+            .get(shader)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn compile_shader(&self, shader: &u32) {
+        self.record(GlCall::CompileShader(*shader));
+    }
+
+    fn shader_compile_status(&self, _shader: &u32) -> bool {
+        true
+    }
+
+    fn shader_info_log(&self, _shader: &u32) -> String {
+        String::new()
+    }
+
+    fn attach_shader(&self, program: &u32, shader: &u32) {
+        self.record(GlCall::AttachShader {
+            program: *program,
+            shader: *shader,
+        });
+    }
+
+    fn link_program(&self, program: &u32) {
+        self.record(GlCall::LinkProgram(*program));
+        let mut failed_links = self.failed_links.borrow_mut();
+        if self.fail_links.get() {
+            failed_links.insert(*program);
+        } else {
+            failed_links.remove(program);
+        }
+    }
+
+    fn program_link_status(&self, program: &u32) -> bool {
+        !self.failed_links.borrow().contains(program)
+    }
+
+    fn validate_program(&self, program: &u32) -> bool {
+        self.record(GlCall::ValidateProgram(*program));
+        true
+    }
+
+    fn program_info_log(&self, _program: &u32) -> String {
+        String::new()
+    }
+
+    fn use_program(&self, program: Option<&u32>) {
+        self.record(GlCall::UseProgram(program.copied()));
+    }
+
+    fn active_uniform_count(&self, _program: &u32) -> u32 {
+        self.active_uniforms.borrow().len() as u32
+    }
+
+    fn get_active_uniform(&self, _program: &u32, index: u32) -> Option<ActiveInfo> {
+        self.active_uniforms.borrow().get(index as usize).cloned()
+    }
+
+    fn active_attrib_count(&self, _program: &u32) -> u32 {
+        self.active_attributes.borrow().len() as u32
+    }
+
+    fn get_active_attrib(&self, _program: &u32, index: u32) -> Option<ActiveInfo> {
+        self.active_attributes.borrow().get(index as usize).cloned()
+    }
+
+    fn get_attrib_location(&self, program: &u32, name: &str) -> Option<u32> {
+        self.record(GlCall::GetAttribLocation {
+            program: *program,
+            name: name.to_string(),
+        });
+        if self.inactive.borrow().contains(name) {
+            return None;
+        }
+        let mut locations = self.attrib_locations.borrow_mut();
+        let next = locations.keys().filter(|(id, _)| id == program).count() as u32;
+        Some(
+            *locations
+                .entry((*program, name.to_string()))
+                .or_insert(next),
+        )
+    }
+
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        self.record(GlCall::EnableVertexAttribArray(index));
+    }
+
+    fn get_uniform_location(&self, program: &u32, name: &str) -> Option<String> {
+        self.record(GlCall::GetUniformLocation {
+            program: *program,
+            name: name.to_string(),
+        });
+        if self.inactive.borrow().contains(name) {
+            return None;
+        }
+        Some(name.to_string())
+    }
+
+    fn uniform(&self, location: &String, value: UniformValue<'_>) {
+        self.record(GlCall::Uniform {
+            location: location.clone(),
+            value: value.into_owned(),
+        });
+    }
+
+    fn fragment_highp_supported(&self) -> bool {
+        true
+    }
+
+    fn is_webgl2(&self) -> bool {
+        self.webgl2.get()
+    }
+
+    fn get_parameter_i32(&self, pname: u32) -> Option<i32> {
+        self.parameters.borrow().get(&pname)?.first().copied()
+    }
+
+    fn get_parameter_i32_array(&self, pname: u32) -> Option<Vec<i32>> {
+        self.parameters.borrow().get(&pname).cloned()
+    }
+
+    fn limits(&self) -> &GlLimits {
+        self.limits.get_or_init(|| GlLimits::query(self))
+    }
+}
@@ -0,0 +1,89 @@
+use wasm_bindgen::JsCast;
+use web_sys::WebGlRenderingContext;
+
+use crate::Context;
+
+/// `UNMASKED_VENDOR_WEBGL` from the `WEBGL_debug_renderer_info` extension.
+const UNMASKED_VENDOR_WEBGL: u32 = 0x9245;
+/// `UNMASKED_RENDERER_WEBGL` from the `WEBGL_debug_renderer_info` extension.
+const UNMASKED_RENDERER_WEBGL: u32 = 0x9246;
+
+impl Context {
+    /// The maximum width and height of a 2D texture (`MAX_TEXTURE_SIZE`).
+    ///
+    /// This queries the context on every call;
+    /// [`limits`][Context::limits] caches this and other limits.
+    pub fn max_texture_size(&self) -> i32 {
+        self.get_i32_parameter(WebGlRenderingContext::MAX_TEXTURE_SIZE)
+    }
+
+    /// The current viewport as `[x, y, width, height]`,
+    /// or all zeros if the context is lost.
+    pub fn viewport(&self) -> [i32; 4] {
+        self.query_viewport().unwrap_or_default()
+    }
+
+    /// The vendor of the graphics driver.
+    ///
+    /// This is the unmasked vendor from the
+    /// [`WEBGL_debug_renderer_info`](https://developer.mozilla.org/en-US/docs/Web/API/WEBGL_debug_renderer_info)
+    /// extension if it is supported, otherwise the (usually masked) `VENDOR` string.
+    pub fn vendor(&self) -> String {
+        self.get_string_parameter(
+            self.debug_renderer_param(UNMASKED_VENDOR_WEBGL, WebGlRenderingContext::VENDOR),
+        )
+    }
+
+    /// The name of the graphics renderer, e.g. the GPU model.
+    ///
+    /// This is the unmasked renderer from the
+    /// [`WEBGL_debug_renderer_info`](https://developer.mozilla.org/en-US/docs/Web/API/WEBGL_debug_renderer_info)
+    /// extension if it is supported, otherwise the (usually masked) `RENDERER` string.
+    pub fn renderer(&self) -> String {
+        self.get_string_parameter(
+            self.debug_renderer_param(UNMASKED_RENDERER_WEBGL, WebGlRenderingContext::RENDERER),
+        )
+    }
+
+    /// The WebGL version string (`VERSION`), e.g. `"WebGL 2.0"`.
+    pub fn version_string(&self) -> String {
+        self.get_string_parameter(WebGlRenderingContext::VERSION)
+    }
+
+    /// The current viewport, or `None` if the context is lost.
+    pub(crate) fn query_viewport(&self) -> Option<[i32; 4]> {
+        let array = self
+            .native
+            .get_parameter(WebGlRenderingContext::VIEWPORT)
+            .ok()?
+            .dyn_into::<js_sys::Int32Array>()
+            .ok()?;
+        match array.to_vec()[..] {
+            [x, y, width, height] => Some([x, y, width, height]),
+            _ => None,
+        }
+    }
+
+    fn debug_renderer_param(&self, unmasked: u32, masked: u32) -> u32 {
+        match self.get_extension("WEBGL_debug_renderer_info") {
+            Some(_) => unmasked,
+            None => masked,
+        }
+    }
+
+    fn get_i32_parameter(&self, param: u32) -> i32 {
+        self.native
+            .get_parameter(param)
+            .ok()
+            .and_then(|value| value.as_f64())
+            .map_or(0, |value| value as i32)
+    }
+
+    fn get_string_parameter(&self, param: u32) -> String {
+        self.native
+            .get_parameter(param)
+            .ok()
+            .and_then(|value| value.as_string())
+            .unwrap_or_default()
+    }
+}
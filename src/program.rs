@@ -1,34 +1,458 @@
 //! Types in this module are used as fields in the [`Program`][super::Program] impl
 //! to hold resources allocated from the `WebGlRenderingContext`.
 
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::mem;
 
+use anyhow::{bail, Context as _, Result};
 use once_cell::unsync::OnceCell;
-use web_sys::{WebGlProgram, WebGlShader, WebGlUniformLocation};
+use web_sys::{WebGlActiveInfo, WebGlRenderingContext};
 
-use crate::{AttributeType, Context, UniformType};
+use crate::{Backend, Context, DrawError, UniformType};
 
 /// An internal type used to hold program-specific resources.
 /// There must be exactly one field in a [`Program`][super::Program]-deriving struct
 /// holding `ProgramData` as the value.
-pub struct ProgramData {
+///
+/// The type parameter is the [`Backend`][Backend] that the program runs on.
+/// A program with a `ProgramData<MockContext>` field runs on a
+/// [`MockContext`][crate::MockContext], e.g. in unit tests.
+pub struct ProgramData<B: Backend = Context> {
     #[doc(hidden)]
-    pub program: WebGlProgram,
+    pub program: B::Program,
     #[doc(hidden)]
-    pub vertex_shader: WebGlShader,
+    pub vertex_shader: B::Shader,
     #[doc(hidden)]
-    pub fragment_shader: WebGlShader,
+    pub fragment_shader: B::Shader,
+    #[doc(hidden)]
+    pub source_hashes: Cell<(u64, u64)>,
+    #[doc(hidden)]
+    pub validated: Cell<bool>,
+}
+
+impl<B: Backend> ProgramData<B> {
+    /// Internal method used to allocate the program and its shaders.
+    ///
+    /// Returns [`DrawError::ContextLost`][DrawError::ContextLost] if the context is lost.
+    #[doc(hidden)]
+    pub fn create_from_macro(context: &B) -> Result<Self, DrawError> {
+        if context.is_context_lost() {
+            return Err(DrawError::ContextLost);
+        }
+        // Creating programs and shaders only fails if the context is lost.
+        Ok(Self {
+            program: context.create_program().ok_or(DrawError::ContextLost)?,
+            vertex_shader: context
+                .create_shader(WebGlRenderingContext::VERTEX_SHADER)
+                .ok_or(DrawError::ContextLost)?,
+            fragment_shader: context
+                .create_shader(WebGlRenderingContext::FRAGMENT_SHADER)
+                .ok_or(DrawError::ContextLost)?,
+            source_hashes: Cell::default(),
+            validated: Cell::default(),
+        })
+    }
+
+    /// Internal method used to compile the shaders of a newly created program.
+    ///
+    /// Panics with the info log of `program_name` if compiling fails in debug builds.
+    #[doc(hidden)]
+    pub fn compile_from_macro(&self, context: &B, program_name: &str, vert: &str, frag: &str) {
+        context.shader_source(&self.vertex_shader, vert);
+        context.compile_shader(&self.vertex_shader);
+        context.shader_source(&self.fragment_shader, frag);
+        context.compile_shader(&self.fragment_shader);
+        self.set_source_hashes(vert, frag);
+
+        // Compilation always fails in a lost context, so there is nothing to diagnose.
+        #[cfg(debug_assertions)]
+        if !context.is_context_lost() {
+            warn_unsupported_precision(context, frag);
+
+            for (debug_name, shader, source) in &[
+                ("vertex shader", &self.vertex_shader, vert),
+                ("fragment shader", &self.fragment_shader, frag),
+            ] {
+                if !context.shader_compile_status(shader) {
+                    panic!(
+                        "Error compiling {} of {}: {}\n{}",
+                        debug_name,
+                        program_name,
+                        context.shader_info_log(shader),
+                        numbered_source(source)
+                    );
+                }
+            }
+        }
+    }
+
+    /// Internal method used to link the shaders of a newly created program.
+    ///
+    /// Panics with the info log of `program_name` if linking fails in debug builds.
+    #[doc(hidden)]
+    pub fn link_from_macro(&self, context: &B, program_name: &str) {
+        context.attach_shader(&self.program, &self.vertex_shader);
+        context.attach_shader(&self.program, &self.fragment_shader);
+        context.link_program(&self.program);
+
+        #[cfg(debug_assertions)]
+        if !context.is_context_lost() && !context.program_link_status(&self.program) {
+            panic!(
+                "Error linking {}: {}",
+                program_name,
+                context.program_info_log(&self.program)
+            );
+        }
+    }
+
+    /// Returns the vertex and fragment shader sources of the current program.
+    ///
+    /// Unlike the sources passed to the [`Program`][super::Program] macro or `reload`,
+    /// these include the injected `#version` and `precision` directives,
+    /// so the line numbers in shader info logs refer to these sources.
+    /// Compile errors include these sources with line numbers.
+    ///
+    /// The sources are queried from the driver rather than kept in memory.
+    pub fn dump_sources(&self, context: &B) -> (String, String) {
+        (
+            context.get_shader_source(&self.vertex_shader),
+            context.get_shader_source(&self.fragment_shader),
+        )
+    }
+
+    /// Internal method used to record the hashes of the sources submitted to the driver.
+    #[doc(hidden)]
+    pub fn set_source_hashes(&self, vert: &str, frag: &str) {
+        self.source_hashes
+            .set((source_hash(vert), source_hash(frag)));
+    }
+
+    /// Replaces the shader sources, then recompiles and relinks the program.
+    ///
+    /// Returns an error with the info log if compiling or linking fails.
+    ///
+    /// The new shaders are linked into a new program,
+    /// which only replaces the current program if linking succeeds,
+    /// so the previous program stays usable if the new shaders fail to compile or link.
+    ///
+    /// Attribute and uniform locations may change after relinking,
+    /// so the locations cached in the `Attribute` and `Uniform` fields must be reset.
+    /// Use the `reload` method derived by the [`Program`][super::Program] macro,
+    /// which resets them automatically.
+    ///
+    /// A shader whose source is identical to the source of the current program
+    /// is not recompiled, so editing only the fragment shader
+    /// does not recompile the vertex shader and vice versa.
+    /// Sources are compared by their hashes, so no copy of them is kept.
+    /// If neither shader changed, the program is not relinked either.
+    pub fn recompile(&mut self, context: &B, vert: &str, frag: &str) -> Result<()> {
+        if context.is_context_lost() {
+            bail!("Cannot recompile a program in a lost context");
+        }
+        let (previous_vert, previous_frag) = self.source_hashes.get();
+        let vert_unchanged = source_hash(vert) == previous_vert
+            && context.shader_compile_status(&self.vertex_shader);
+        let frag_unchanged = source_hash(frag) == previous_frag
+            && context.shader_compile_status(&self.fragment_shader);
+        if vert_unchanged && frag_unchanged && context.program_link_status(&self.program) {
+            crate::__trace!("Skipping relinking of unchanged shaders");
+            return Ok(());
+        }
+
+        let vertex_shader = if vert_unchanged {
+            crate::__trace!("Skipping recompilation of unchanged vertex shader");
+            None
+        } else {
+            Some(compile_shader(
+                context,
+                WebGlRenderingContext::VERTEX_SHADER,
+                "vertex shader",
+                vert,
+            )?)
+        };
+        let fragment_shader = if frag_unchanged {
+            crate::__trace!("Skipping recompilation of unchanged fragment shader");
+            None
+        } else {
+            match compile_shader(
+                context,
+                WebGlRenderingContext::FRAGMENT_SHADER,
+                "fragment shader",
+                frag,
+            ) {
+                Ok(shader) => Some(shader),
+                Err(err) => {
+                    if let Some(shader) = &vertex_shader {
+                        context.delete_shader(shader);
+                    }
+                    return Err(err);
+                }
+            }
+        };
+
+        #[cfg(debug_assertions)]
+        warn_unsupported_precision(context, frag);
+
+        // `createProgram` only fails if the context is lost.
+        let program = context
+            .create_program()
+            .context("Cannot recompile a program in a lost context")?;
+        context.attach_shader(
+            &program,
+            vertex_shader.as_ref().unwrap_or(&self.vertex_shader),
+        );
+        context.attach_shader(
+            &program,
+            fragment_shader.as_ref().unwrap_or(&self.fragment_shader),
+        );
+        context.link_program(&program);
+        if !context.program_link_status(&program) {
+            let log = context.program_info_log(&program);
+            context.delete_program(&program);
+            for shader in vertex_shader.iter().chain(&fragment_shader) {
+                context.delete_shader(shader);
+            }
+            bail!("Error linking program: {}", log);
+        }
+
+        context.delete_program(&mem::replace(&mut self.program, program));
+        if let Some(shader) = vertex_shader {
+            context.delete_shader(&mem::replace(&mut self.vertex_shader, shader));
+        }
+        if let Some(shader) = fragment_shader {
+            context.delete_shader(&mem::replace(&mut self.fragment_shader, shader));
+        }
+        self.set_source_hashes(vert, frag);
+        self.validated.set(false);
+
+        Ok(())
+    }
+
+    /// Checks whether the program can execute in the current WebGL state
+    /// with [`validateProgram`][mdn], returning the info log on failure.
+    ///
+    /// This detects problems that only occur at draw time,
+    /// e.g. samplers of different types assigned to the same texture unit.
+    /// Validation is slow, so this is intended for debugging.
+    ///
+    /// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/validateProgram
+    pub fn validate(&self, context: &B) -> Result<()> {
+        if !context.validate_program(&self.program) {
+            bail!(
+                "Error validating program: {}",
+                context.program_info_log(&self.program)
+            );
+        }
+        Ok(())
+    }
+
+    /// Internal method used to validate the program before its first draw in debug builds.
+    #[doc(hidden)]
+    pub fn validate_once(&self, context: &B) -> Result<()> {
+        if !self.validated.get() {
+            self.validate(context)?;
+            self.validated.set(true);
+        }
+        Ok(())
+    }
+
+    /// Lists the active uniforms in the linked program.
+    ///
+    /// Uniforms that are declared but unused by the shaders may be optimized out
+    /// and are not listed.
+    pub fn active_uniforms(&self, context: &B) -> Vec<ActiveInfo> {
+        (0..context.active_uniform_count(&self.program))
+            .filter_map(|index| context.get_active_uniform(&self.program, index))
+            .collect()
+    }
+
+    /// Lists the active attributes in the linked program.
+    ///
+    /// Attributes that are declared but unused by the vertex shader may be optimized out
+    /// and are not listed.
+    pub fn active_attributes(&self, context: &B) -> Vec<ActiveInfo> {
+        (0..context.active_attrib_count(&self.program))
+            .filter_map(|index| context.get_active_attrib(&self.program, index))
+            .collect()
+    }
+}
+
+/// Describes an active uniform or attribute in a linked program,
+/// returned by [`ProgramData::active_uniforms`][ProgramData::active_uniforms]
+/// and [`ProgramData::active_attributes`][ProgramData::active_attributes].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveInfo {
+    /// The GLSL name of the variable.
+    ///
+    /// Array uniforms are named with the `[0]` suffix, e.g. `lights[0]`.
+    pub name: String,
+    /// The number of elements if the variable is an array, otherwise 1.
+    pub size: i32,
+    /// The GLSL type of the variable, e.g. `WebGlRenderingContext::FLOAT_VEC3`.
+    pub ty: u32,
+}
+
+impl From<WebGlActiveInfo> for ActiveInfo {
+    fn from(info: WebGlActiveInfo) -> Self {
+        Self {
+            name: info.name(),
+            size: info.size(),
+            ty: info.type_(),
+        }
+    }
+}
+
+impl Context {
+    /// Whether fragment shaders support `highp` float precision.
+    ///
+    /// Some mobile devices only support `mediump` in fragment shaders,
+    /// where a fragment shader declaring `highp` fails to compile.
+    pub fn fragment_highp_supported(&self) -> bool {
+        self.native
+            .get_shader_precision_format(
+                WebGlRenderingContext::FRAGMENT_SHADER,
+                WebGlRenderingContext::HIGH_FLOAT,
+            )
+            .map_or(false, |format| format.precision() != 0)
+    }
+}
+
+/// Internal function used to warn if the fragment shader `source` uses `highp`
+/// on a device that does not support it in fragment shaders.
+///
+/// Called in debug builds when the shaders are compiled.
+#[doc(hidden)]
+pub fn warn_unsupported_precision(context: &impl Backend, source: &str) {
+    if source.contains("highp") && !context.fragment_highp_supported() {
+        log::warn!(
+            "The fragment shader uses highp precision, \
+            which is not supported in fragment shaders on this device"
+        );
+    }
+}
+
+/// Compiles a new shader of type `ty`, deleting it if compilation fails.
+fn compile_shader<B: Backend>(
+    context: &B,
+    ty: u32,
+    debug_name: &str,
+    source: &str,
+) -> Result<B::Shader> {
+    // `createShader` only fails if the context is lost.
+    let shader = context
+        .create_shader(ty)
+        .context("Cannot recompile a program in a lost context")?;
+    context.shader_source(&shader, source);
+    context.compile_shader(&shader);
+    if !context.shader_compile_status(&shader) {
+        let log = context.shader_info_log(&shader);
+        context.delete_shader(&shader);
+        bail!(
+            "Error compiling {}: {}\n{}",
+            debug_name,
+            log,
+            numbered_source(source)
+        );
+    }
+    Ok(shader)
+}
+
+/// Internal function used to check that the context supports the type `T` of the uniform `name`
+/// when a program is created, instead of failing when the uniform is assigned.
+#[doc(hidden)]
+pub fn check_uniform_support<T: UniformType, B: Backend>(context: &B, name: &str) -> Result<()> {
+    if T::requires_webgl2() && !context.is_webgl2() {
+        bail!("The uniform \"{}\" requires a WebGL 2 context", name);
+    }
+    Ok(())
+}
+
+/// Hashes a shader source to detect whether it changed since the last compilation.
+fn source_hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Internal function used to prefix each line of a shader source with its line number,
+/// which matches the line numbers reported in shader info logs.
+#[doc(hidden)]
+pub fn numbered_source(source: &str) -> String {
+    source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| format!("{:4} | {}\n", index + 1, line))
+        .collect()
+}
+
+/// Internal function used to prepend the `#version` and `precision` directives
+/// specified in the [`Program`][super::Program] macro to the shader source.
+///
+/// Returns an error if `version` is specified but the source already has a `#version` directive.
+/// If only `precision` is specified and the source has its own `#version` directive,
+/// the precision is inserted after it, since `#version` must be the first line.
+#[doc(hidden)]
+pub fn shader_with_header<'t>(
+    source: &'t str,
+    version: Option<&str>,
+    precision: Option<&str>,
+) -> Result<Cow<'t, str>> {
+    if version.is_none() && precision.is_none() {
+        return Ok(Cow::Borrowed(source));
+    }
+
+    let version_line = source
+        .lines()
+        .position(|line| line.trim_start().starts_with("#version"));
+
+    let mut header = String::new();
+    if let Some(version) = version {
+        if version_line.is_some() {
+            bail!(
+                "The shader source already contains a #version directive, \
+                which conflicts with #[willow(glsl_version = \"{}\")]",
+                version
+            );
+        }
+        header += &format!("#version {}\n", version);
+    }
+    if let Some(precision) = precision {
+        header += &format!("precision {};\n", precision);
+    }
+
+    Ok(Cow::Owned(match version_line {
+        Some(line) => {
+            let end = source
+                .split_inclusive('\n')
+                .take(line + 1)
+                .map(str::len)
+                .sum();
+            let (version, rest) = source.split_at(end);
+            let newline = if version.ends_with('\n') { "" } else { "\n" };
+            format!("{}{}{}{}", version, newline, header, rest)
+        }
+        None => header + source,
+    }))
 }
 
 /// In a [`Program`][super::Program]-deriving struct,
 /// a field of type `Attribute<T>` indicates that
 /// the vertex shader has an attribute with the type compatible with `T`.
-pub struct Attribute<T: AttributeType> {
-    location: OnceCell<u32>,
-    _ph: PhantomData<&'static T>,
+///
+/// If the field is annotated with `#[willow(attribute(U))]`,
+/// the attribute has the type `U` instead, and `T` does not need to implement
+/// [`AttributeType`][crate::AttributeType].
+pub struct Attribute<T> {
+    location: OnceCell<Option<u32>>,
+    _ph: PhantomData<fn() -> T>,
 }
 
-impl<T: AttributeType> Attribute<T> {
+impl<T> Attribute<T> {
     /// Internal method used to create a raw `Attribute` value.
     #[doc(hidden)]
     pub fn create_from_macro() -> Self {
@@ -38,32 +462,64 @@ impl<T: AttributeType> Attribute<T> {
         }
     }
 
-    /// Lazily retrieves an attribute location
+    /// Lazily retrieves an attribute location,
+    /// enables the vertex attribute array at that location,
     /// aod stores it in this `Attribute` struct.
-    pub fn get_location(&self, context: &Context, program: &ProgramData, name: &str) -> u32 {
+    ///
+    /// Returns `None` if the program has no active attribute called `name`,
+    /// e.g. if the GLSL compiler optimized out an unused attribute.
+    /// The absence is also cached, so the location is not queried again until
+    /// [`reset`][Attribute::reset] is called.
+    pub fn get_location<B: Backend>(
+        &self,
+        context: &B,
+        program: &ProgramData<B>,
+        name: &str,
+    ) -> Option<u32> {
         *self.location.get_or_init(|| {
-            let location = context.native.get_attrib_location(&program.program, name);
-            let location = location as u32;
-            context.native.enable_vertex_attrib_array(location);
+            let location = context.get_attrib_location(&program.program, name);
+            match location {
+                Some(location) => context.enable_vertex_attrib_array(location),
+                None => log::debug!(
+                    "Attribute \"{}\" is not active in the program and will not be bound",
+                    name
+                ),
+            }
             location
         })
     }
+
+    /// Clears the cached location, e.g. after the program is relinked.
+    pub fn reset(&mut self) {
+        self.location.take();
+    }
 }
 
 /// In a [`Program`][super::Program]-deriving struct,
 /// a field of type `Uniform<T>` indicates that
 /// the vertex shader has a uniform with the type compatible with `T`.
-pub struct Uniform<T: UniformType> {
-    location: OnceCell<Option<WebGlUniformLocation>>,
-    _ph: PhantomData<&'static T>,
+///
+/// If the field is annotated with `#[willow(uniform(U))]`,
+/// the uniform is assigned with values of type `U` instead,
+/// and `T` does not need to implement [`UniformType`][crate::UniformType].
+///
+/// A field of type `Uniform<[T; N]>` corresponds to a GLSL array uniform with `N` elements.
+/// The locations of its elements are cached separately when they are first assigned.
+///
+/// The second type parameter is the [`Backend`][Backend] of the program.
+pub struct Uniform<T: ?Sized, B: Backend = Context> {
+    location: OnceCell<Option<B::UniformLocation>>,
+    elements: RefCell<HashMap<usize, Option<B::UniformLocation>>>,
+    _ph: PhantomData<fn() -> T>,
 }
 
-impl<T: UniformType> Uniform<T> {
+impl<T: ?Sized, B: Backend> Uniform<T, B> {
     /// Internal method used to create a raw `Uniform` value.
     #[doc(hidden)]
     pub fn create_from_macro() -> Self {
         Self {
             location: OnceCell::new(),
+            elements: RefCell::default(),
             _ph: PhantomData,
         }
     }
@@ -72,12 +528,661 @@ impl<T: UniformType> Uniform<T> {
     /// aod stores it in this `Uniform` struct.
     pub fn get_location<'t>(
         &'t self,
-        context: &Context,
-        program: &ProgramData,
+        context: &B,
+        program: &ProgramData<B>,
         name: &str,
-    ) -> Option<&'t WebGlUniformLocation> {
+    ) -> Option<&'t B::UniformLocation> {
         self.location
-            .get_or_init(|| context.native.get_uniform_location(&program.program, name))
+            .get_or_init(|| context.get_uniform_location(&program.program, name))
             .as_ref()
     }
+
+    /// Lazily retrieves the location of the element `index` of the array uniform `name`,
+    /// i.e. the GLSL uniform `name[index]`,
+    /// and stores it in this `Uniform` struct.
+    pub fn get_element_location(
+        &self,
+        context: &B,
+        program: &ProgramData<B>,
+        name: &str,
+        index: usize,
+    ) -> Option<B::UniformLocation> {
+        self.elements
+            .borrow_mut()
+            .entry(index)
+            .or_insert_with(|| {
+                context.get_uniform_location(&program.program, &format!("{}[{}]", name, index))
+            })
+            .clone()
+    }
+
+    /// Clears the cached locations, e.g. after the program is relinked.
+    pub fn reset(&mut self) {
+        self.location.take();
+        self.elements.get_mut().clear();
+    }
+}
+
+/// In a [`Program`][super::Program]-deriving struct,
+/// a field of type `UniformStruct` indicates that
+/// the shader has a uniform of a GLSL struct type.
+///
+/// The members of the struct must be declared in the field attribute:
+/// ```ignore
+/// #[willow(uniform_struct(color: Vector3<f32>, intensity: f32))]
+/// light: UniformStruct,
+/// ```
+///
+/// Each member is assigned like a separate uniform named `{field}_{member}`,
+/// e.g. `light_color` and `light_intensity`,
+/// corresponding to the GLSL uniforms `light.color` and `light.intensity`.
+pub struct UniformStruct<B: Backend = Context> {
+    locations: Box<[OnceCell<Option<B::UniformLocation>>]>,
+}
+
+impl<B: Backend> UniformStruct<B> {
+    /// Internal method used to create a raw `UniformStruct` value.
+    #[doc(hidden)]
+    pub fn create_from_macro(members: usize) -> Self {
+        Self {
+            locations: (0..members).map(|_| OnceCell::new()).collect(),
+        }
+    }
+
+    /// Lazily retrieves the location of the struct member
+    /// with index `member` and the full GLSL name `name` (e.g. `light.color`)
+    /// and stores it in this `UniformStruct`.
+    pub fn get_location<'t>(
+        &'t self,
+        context: &B,
+        program: &ProgramData<B>,
+        member: usize,
+        name: &str,
+    ) -> Option<&'t B::UniformLocation> {
+        self.locations[member]
+            .get_or_init(|| context.get_uniform_location(&program.program, name))
+            .as_ref()
+    }
+
+    /// Clears the cached locations, e.g. after the program is relinked.
+    pub fn reset(&mut self) {
+        for location in self.locations.iter_mut() {
+            location.take();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::shader_with_header;
+    use crate::{
+        ActiveInfo, Attribute, BufferDataUsage, DrawError, GlCall, MatrixShape, MockContext,
+        Program, ProgramData, RenderPrimitiveType, Sampler2D, Uniform, UniformStruct, UniformValue,
+        VectorSize,
+    };
+    use web_sys::WebGlRenderingContext;
+
+    #[cfg(feature = "nalgebra")]
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Skewed {
+        data: ProgramData<MockContext>,
+        a_position: Attribute<[f32; 2]>,
+        u_skew: Uniform<nalgebra::Matrix2x3<f32>, MockContext>,
+    }
+
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Flat {
+        data: ProgramData<MockContext>,
+        a_position: Attribute<[f32; 2]>,
+        u_scale: Uniform<f32, MockContext>,
+        #[willow(sampler_unit = 2)]
+        u_shadow: Uniform<Sampler2D, MockContext>,
+    }
+
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Partial {
+        data: ProgramData<MockContext>,
+        a_position: Attribute<[f32; 2]>,
+        a_unused: Attribute<f32>,
+    }
+
+    type Scale = Uniform<f32, MockContext>;
+    type Position = Attribute<[f32; 2]>;
+
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Aliased {
+        data: ProgramData<MockContext>,
+        #[willow(attribute([f32; 2]))]
+        a_offset: Position,
+        #[willow(uniform(f32))]
+        u_scale: Scale,
+    }
+
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Arrays {
+        data: ProgramData<MockContext>,
+        u_weights: Uniform<[f32; 3], MockContext>,
+    }
+
+    #[derive(Program)]
+    #[willow(
+        vert = "void main() {}",
+        frag = "void main() {}",
+        attr_prefix = "a_",
+        uniform_prefix = "u_"
+    )]
+    struct Prefixed {
+        data: ProgramData<MockContext>,
+        position: Attribute<f32>,
+        scale: Uniform<f32, MockContext>,
+        #[willow(gl_name = "tint")]
+        color: Uniform<f32, MockContext>,
+        #[willow(uniform_struct(intensity: f32))]
+        light: UniformStruct<MockContext>,
+    }
+
+    #[derive(Program)]
+    #[willow(vert = "void main() {}", frag = "void main() {}")]
+    struct Instanced {
+        data: ProgramData<MockContext>,
+        a_position: Attribute<[f32; 2]>,
+        #[willow(instanced)]
+        a_offset: Attribute<[f32; 2]>,
+    }
+
+    #[derive(Program)]
+    #[willow(
+        vert = "#version 300 es\nvoid main() {}",
+        frag = "void main() {}",
+        glsl_version = "300 es"
+    )]
+    struct VersionConflict {
+        data: ProgramData<MockContext>,
+    }
+
+    #[test]
+    fn header_is_prepended() {
+        let source = shader_with_header("void main() {}", Some("300 es"), Some("highp float"));
+        assert_eq!(
+            source.unwrap(),
+            "#version 300 es\nprecision highp float;\nvoid main() {}"
+        );
+    }
+
+    #[test]
+    fn precision_is_inserted_after_existing_version() {
+        let source = "#version 300 es\nout vec4 color;\n";
+        let source = shader_with_header(source, None, Some("mediump float"));
+        assert_eq!(
+            source.unwrap(),
+            "#version 300 es\nprecision mediump float;\nout vec4 color;\n"
+        );
+
+        let source = shader_with_header("#version 300 es", None, Some("mediump float"));
+        assert_eq!(
+            source.unwrap(),
+            "#version 300 es\nprecision mediump float;\n"
+        );
+    }
+
+    #[test]
+    fn duplicate_version_is_an_error() {
+        let source = "#version 300 es\nvoid main() {}";
+        assert!(shader_with_header(source, Some("300 es"), None).is_err());
+
+        let context = MockContext::default();
+        assert!(VersionConflict::create(&context).is_err());
+    }
+
+    #[test]
+    fn create_compiles_and_links_through_backend() {
+        let context = MockContext::default();
+        let _program = Flat::create(&context).unwrap();
+
+        let calls = context.take_calls();
+        assert_eq!(
+            calls[..3],
+            [
+                GlCall::CreateProgram(1),
+                GlCall::CreateShader {
+                    ty: WebGlRenderingContext::VERTEX_SHADER,
+                    shader: 2,
+                },
+                GlCall::CreateShader {
+                    ty: WebGlRenderingContext::FRAGMENT_SHADER,
+                    shader: 3,
+                },
+            ]
+        );
+        for call in &[
+            GlCall::CompileShader(2),
+            GlCall::CompileShader(3),
+            GlCall::AttachShader {
+                program: 1,
+                shader: 2,
+            },
+            GlCall::LinkProgram(1),
+        ] {
+            assert!(calls.contains(call), "{:?} not called", call);
+        }
+    }
+
+    #[test]
+    fn link_assigns_sampler_units() {
+        let context = MockContext::default();
+        let _program = Flat::create(&context).unwrap();
+
+        let calls = context.take_calls();
+        let link = calls
+            .iter()
+            .position(|call| *call == GlCall::LinkProgram(1));
+        let assign = calls.iter().position(|call| {
+            *call
+                == GlCall::Uniform {
+                    location: "u_shadow".to_string(),
+                    value: UniformValue::Int {
+                        size: VectorSize::One,
+                        data: vec![2].into(),
+                    },
+                }
+        });
+        assert!(link.is_some() && assign.is_some() && link < assign);
+    }
+
+    #[test]
+    fn builder_assigns_uniforms_through_backend() {
+        let context = MockContext::default();
+        let program = Flat::create(&context).unwrap();
+        context.take_calls();
+
+        program
+            .with_uniforms()
+            .u_scale(2.)
+            .apply_uniforms(&context)
+            .unwrap();
+
+        let calls = context.take_calls();
+        assert_eq!(calls[0], GlCall::UseProgram(Some(1)));
+        assert!(calls.contains(&GlCall::Uniform {
+            location: "u_scale".to_string(),
+            value: UniformValue::Float {
+                size: VectorSize::One,
+                data: vec![2.].into(),
+            },
+        }));
+    }
+
+    #[test]
+    fn aliased_fields_use_annotated_types() {
+        let context = MockContext::default();
+        let program = Aliased::create(&context).unwrap();
+        let buffer = Aliased::prepare_buffer(
+            &context,
+            &[AliasedAttr { a_offset: [1., 2.] }; 3],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+        context.take_calls();
+
+        program
+            .with_uniforms()
+            .u_scale(0.5)
+            .draw(&context, RenderPrimitiveType::Triangles, &buffer, &..)
+            .unwrap();
+        let calls = context.take_calls();
+        assert!(calls.contains(&GlCall::GetAttribLocation {
+            program: 1,
+            name: "a_offset".to_string(),
+        }));
+        assert!(calls.contains(&GlCall::VertexAttribPointer {
+            index: 0,
+            size: 2,
+            ty: WebGlRenderingContext::FLOAT,
+            normalized: false,
+            stride: 8,
+            offset: 0,
+        }));
+        assert!(calls.contains(&GlCall::Uniform {
+            location: "u_scale".to_string(),
+            value: UniformValue::Float {
+                size: VectorSize::One,
+                data: vec![0.5].into(),
+            },
+        }));
+    }
+
+    #[test]
+    fn default_uniforms_can_be_replaced() {
+        let context = MockContext::default();
+        let program = Aliased::create(&context).unwrap();
+        context.take_calls();
+
+        program
+            .with_default_uniforms()
+            .u_scale(3.)
+            .apply_uniforms(&context)
+            .unwrap();
+        let scales: Vec<_> = context
+            .take_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                GlCall::Uniform { location, value } if location == "u_scale" => Some(value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            scales,
+            [UniformValue::Float {
+                size: VectorSize::One,
+                data: vec![3.].into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn prefixes_apply_to_inferred_names() {
+        let context = MockContext::default();
+        let program = Prefixed::create(&context).unwrap();
+        let buffer = Prefixed::prepare_buffer(
+            &context,
+            &[PrefixedAttr { position: 0. }; 3],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+        context.take_calls();
+
+        program
+            .with_uniforms()
+            .scale(1.)
+            .color(2.)
+            .light_intensity(3.)
+            .draw(&context, RenderPrimitiveType::Triangles, &buffer, &..)
+            .unwrap();
+        let calls = context.take_calls();
+        assert!(calls.contains(&GlCall::GetAttribLocation {
+            program: 1,
+            name: "a_position".to_string(),
+        }));
+        let locations: Vec<_> = calls
+            .iter()
+            .filter_map(|call| match call {
+                GlCall::Uniform { location, .. } => Some(location.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(locations, ["u_scale", "tint", "u_light.intensity"]);
+    }
+
+    #[test]
+    fn array_uniform_is_uploaded_at_once() {
+        let context = MockContext::default();
+        let program = Arrays::create(&context).unwrap();
+        context.take_calls();
+
+        program
+            .with_uniforms()
+            .u_weights([1., 2., 3.])
+            .apply_uniforms(&context)
+            .unwrap();
+        let uniforms: Vec<_> = context
+            .take_calls()
+            .into_iter()
+            .filter(|call| matches!(call, GlCall::Uniform { .. }))
+            .collect();
+        assert_eq!(
+            uniforms,
+            [GlCall::Uniform {
+                location: "u_weights".to_string(),
+                value: UniformValue::Float {
+                    size: VectorSize::One,
+                    data: vec![1., 2., 3.].into(),
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn create_in_lost_context_is_an_error() {
+        let context = MockContext::default();
+        context.lose_context();
+
+        let error = Flat::create(&context).err().unwrap();
+        assert_eq!(
+            error.downcast_ref::<DrawError>(),
+            Some(&DrawError::ContextLost)
+        );
+        assert!(context.calls().is_empty());
+    }
+
+    #[test]
+    fn sampler_unit_beyond_limit_is_an_error() {
+        let context = MockContext::default();
+        context.set_parameter(
+            WebGlRenderingContext::MAX_COMBINED_TEXTURE_IMAGE_UNITS,
+            &[2],
+        );
+
+        assert!(Flat::create(&context).is_err());
+        assert!(!context.calls().iter().any(|call| matches!(
+            call,
+            GlCall::Uniform { location, .. } if location == "u_shadow"
+        )));
+    }
+
+    #[test]
+    fn inactive_attribute_is_skipped() {
+        let context = MockContext::default();
+        context.deactivate("a_unused");
+        let program = Partial::create(&context).unwrap();
+        let buffer = Partial::prepare_buffer(
+            &context,
+            &[PartialAttr {
+                a_position: [0., 0.],
+                a_unused: 0.,
+            }; 3],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+        assert!(program.resolve_locations(&context).is_err());
+        context.take_calls();
+
+        for _ in 0..2 {
+            program
+                .draw(&context, RenderPrimitiveType::Triangles, &buffer, ..)
+                .unwrap();
+        }
+        let calls = context.take_calls();
+        let pointers: Vec<_> = calls
+            .iter()
+            .filter_map(|call| match *call {
+                GlCall::VertexAttribPointer { index, size, .. } => Some((index, size)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(pointers, [(0, 2), (0, 2)]);
+        // The absence of the location is cached, so it is not queried again.
+        assert!(!calls
+            .iter()
+            .any(|call| matches!(call, GlCall::GetAttribLocation { .. })));
+    }
+
+    #[test]
+    fn instance_buffer_has_its_own_usage() {
+        let context = MockContext::default();
+        let program = Instanced::create(&context).unwrap();
+        context.take_calls();
+
+        let vertices = [InstancedAttr {
+            a_position: [0., 1.],
+        }];
+        let buffer =
+            Instanced::prepare_buffer(&context, &vertices, BufferDataUsage::StaticDraw).unwrap();
+        let instances = [InstancedInstance { a_offset: [2., 3.] }];
+        let instances =
+            Instanced::prepare_instance_buffer(&context, &instances, BufferDataUsage::DynamicDraw)
+                .unwrap();
+
+        let usages: Vec<_> = context
+            .take_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                GlCall::BufferData { usage, .. } => Some(usage),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            usages,
+            [
+                WebGlRenderingContext::STATIC_DRAW,
+                WebGlRenderingContext::DYNAMIC_DRAW,
+            ]
+        );
+
+        program
+            .with_uniforms()
+            .draw_with_instances(
+                &context,
+                RenderPrimitiveType::Points,
+                &buffer,
+                &instances,
+                &..,
+            )
+            .unwrap();
+        assert!(context.calls().contains(&GlCall::DrawArraysInstanced {
+            mode: WebGlRenderingContext::POINTS,
+            first: 0,
+            count: 1,
+            instances: 1,
+        }));
+    }
+
+    #[test]
+    fn failed_link_keeps_previous_program() {
+        let context = MockContext::default();
+        let mut program = Flat::create(&context).unwrap();
+        let original = program.data.program;
+        context.take_calls();
+
+        context.fail_links(true);
+        assert!(program
+            .reload(&context, "void main() {}", "void main() { discard; }")
+            .is_err());
+        assert_eq!(program.data.program, original);
+        let calls = context.take_calls();
+        assert!(!calls.contains(&GlCall::DeleteProgram(original)));
+        assert!(calls
+            .iter()
+            .any(|call| matches!(call, GlCall::DeleteProgram(id) if *id != original)));
+
+        context.fail_links(false);
+        program
+            .reload(&context, "void main() {}", "void main() { discard; }")
+            .unwrap();
+        assert_ne!(program.data.program, original);
+        assert!(context
+            .take_calls()
+            .contains(&GlCall::DeleteProgram(original)));
+    }
+
+    #[test]
+    fn reload_with_unchanged_sources_does_not_compile_or_link() {
+        let context = MockContext::default();
+        let mut program = Flat::create(&context).unwrap();
+        let (vert, frag) = ("void main() {}", "void main() {}");
+        context.take_calls();
+
+        program.reload(&context, vert, frag).unwrap();
+        assert!(!context
+            .take_calls()
+            .iter()
+            .any(|call| matches!(call, GlCall::CompileShader(_) | GlCall::LinkProgram(_))));
+
+        program
+            .reload(&context, vert, "void main() { discard; }")
+            .unwrap();
+        let calls = context.take_calls();
+        let compiled = calls
+            .iter()
+            .filter(|call| matches!(call, GlCall::CompileShader(_)))
+            .count();
+        assert_eq!(compiled, 1);
+        assert!(calls
+            .iter()
+            .any(|call| matches!(call, GlCall::LinkProgram(_))));
+    }
+
+    #[test]
+    fn active_variables_are_queried_through_backend() {
+        let context = MockContext::default();
+        let uniforms = [ActiveInfo {
+            name: "u_scale".to_string(),
+            size: 1,
+            ty: WebGlRenderingContext::FLOAT,
+        }];
+        let attributes = [ActiveInfo {
+            name: "a_position".to_string(),
+            size: 1,
+            ty: WebGlRenderingContext::FLOAT_VEC2,
+        }];
+        context.set_active_uniforms(&uniforms);
+        context.set_active_attributes(&attributes);
+        let program = Flat::create(&context).unwrap();
+
+        assert_eq!(program.data.active_uniforms(&context), uniforms);
+        assert_eq!(program.data.active_attributes(&context), attributes);
+    }
+
+    #[test]
+    fn dump_sources_returns_sources_of_current_program() {
+        let context = MockContext::default();
+        let mut program = Flat::create(&context).unwrap();
+        let frag = "void main() { discard; }";
+
+        program.reload(&context, "void main() {}", frag).unwrap();
+        let (_, dumped) = program.data.dump_sources(&context);
+        assert_eq!(dumped, frag);
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn non_square_matrix_uniform_requires_webgl2_at_creation() {
+        let context = MockContext::default();
+        let err = Skewed::create(&context)
+            .err()
+            .expect("WebGL 1 cannot assign mat3x2");
+        assert!(err.to_string().contains("u_skew"));
+        assert!(!context
+            .take_calls()
+            .iter()
+            .any(|call| matches!(call, GlCall::CreateProgram(_))));
+
+        let context = MockContext::default();
+        context.set_webgl2(true);
+        let program = Skewed::create(&context).unwrap();
+        let buffer = Skewed::prepare_buffer(
+            &context,
+            &[SkewedAttr::default(); 3],
+            BufferDataUsage::StaticDraw,
+        )
+        .unwrap();
+        context.take_calls();
+
+        program
+            .with_uniforms()
+            .u_skew(nalgebra::Matrix2x3::new(1., 2., 3., 4., 5., 6.))
+            .draw(&context, RenderPrimitiveType::Triangles, &buffer, &..)
+            .unwrap();
+        assert!(context.take_calls().contains(&GlCall::Uniform {
+            location: "u_skew".to_string(),
+            value: UniformValue::Matrix {
+                shape: MatrixShape::Mat3x2,
+                data: vec![1., 4., 2., 5., 3., 6.].into(),
+            },
+        }));
+    }
 }
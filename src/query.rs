@@ -0,0 +1,82 @@
+use anyhow::{Context as _, Result};
+use wasm_bindgen::JsCast;
+use web_sys::{ExtDisjointTimerQuery, WebGlQuery};
+
+use crate::Context;
+
+/// Measures the time taken by the GPU to execute the commands
+/// issued between [`begin`][TimerQuery::begin] and [`end`][TimerQuery::end].
+///
+/// This requires the
+/// [`EXT_disjoint_timer_query`](https://developer.mozilla.org/en-US/docs/Web/API/EXT_disjoint_timer_query)
+/// extension.
+/// The result only becomes available a few frames later,
+/// so [`poll`][TimerQuery::poll] should be called once per frame until it returns `Some`.
+///
+/// Only one timer query can be active at a time.
+pub struct TimerQuery {
+    ext: ExtDisjointTimerQuery,
+    query: WebGlQuery,
+}
+
+impl TimerQuery {
+    /// Allocates a timer query.
+    ///
+    /// Fails if the browser does not support the `EXT_disjoint_timer_query` extension.
+    pub fn new(context: &Context) -> Result<Self> {
+        let ext: ExtDisjointTimerQuery = context
+            .get_extension("EXT_disjoint_timer_query")
+            .context("Failed to enable extension for timer queries")?
+            .unchecked_into();
+        let query = ext
+            .create_query_ext()
+            .context("Failed to allocate timer query")?;
+        Ok(Self { ext, query })
+    }
+
+    /// Starts measuring the elapsed time.
+    pub fn begin(&self) {
+        self.ext
+            .begin_query_ext(ExtDisjointTimerQuery::TIME_ELAPSED_EXT, &self.query);
+    }
+
+    /// Stops measuring the elapsed time.
+    pub fn end(&self) {
+        self.ext
+            .end_query_ext(ExtDisjointTimerQuery::TIME_ELAPSED_EXT);
+    }
+
+    /// Returns the elapsed time in nanoseconds if the result is available.
+    ///
+    /// Returns `None` if the result is not available yet,
+    /// or if a disjoint operation (e.g. a GPU frequency change) has occurred,
+    /// in which case the measurement is invalid and should be discarded.
+    pub fn poll(&self, context: &Context) -> Option<u64> {
+        let available = self.ext.get_query_object_ext(
+            &self.query,
+            ExtDisjointTimerQuery::QUERY_RESULT_AVAILABLE_EXT,
+        );
+        if !available.is_truthy() {
+            return None;
+        }
+
+        let disjoint = context
+            .native
+            .get_parameter(ExtDisjointTimerQuery::GPU_DISJOINT_EXT)
+            .ok()?;
+        if disjoint.is_truthy() {
+            return None;
+        }
+
+        let elapsed = self
+            .ext
+            .get_query_object_ext(&self.query, ExtDisjointTimerQuery::QUERY_RESULT_EXT);
+        elapsed.as_f64().map(|ns| ns as u64)
+    }
+}
+
+impl Drop for TimerQuery {
+    fn drop(&mut self) {
+        self.ext.delete_query_ext(Some(&self.query));
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::{bail, Result};
+use web_sys::{WebGl2RenderingContext, WebGlRenderingContext};
+
+use crate::{AttrStruct, Backend, Buffer, BufferDataUsage, Context, DrawError, ProgramData};
+
+/// The vertex type of buffers holding raw bytes,
+/// created with [`Buffer::from_bytes`][Buffer::from_bytes].
+///
+/// Each "vertex" of a `Buffer<Raw>` is a single byte,
+/// so [`len`][Buffer::len] is the size of the buffer in bytes.
+/// The layout of the vertices is described at runtime by a [`RawAttrStruct`][RawAttrStruct].
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+pub struct Raw(pub u8);
+
+impl AttrStruct for Raw {
+    fn fields_count() -> usize {
+        0
+    }
+
+    fn field_gl_name(_: usize) -> &'static str {
+        unreachable!("Raw has no fields")
+    }
+
+    fn field_offset(_: usize) -> usize {
+        unreachable!("Raw has no fields")
+    }
+
+    fn field_type(_: usize) -> u32 {
+        unreachable!("Raw has no fields")
+    }
+
+    fn field_num_comps(_: usize) -> usize {
+        unreachable!("Raw has no fields")
+    }
+
+    fn field_normalized(_: usize) -> bool {
+        unreachable!("Raw has no fields")
+    }
+}
+
+impl<B: Backend> Buffer<Raw, B> {
+    /// Allocates a WebGL buffer with raw interleaved vertex data,
+    /// e.g. a buffer view loaded from a glTF file.
+    ///
+    /// The layout of the data is described with a [`RawAttrStruct`][RawAttrStruct].
+    ///
+    /// Returns [`DrawError::ContextLost`][DrawError::ContextLost] if the context is lost.
+    pub fn from_bytes(
+        context: &B,
+        bytes: &[u8],
+        usage: BufferDataUsage,
+    ) -> Result<Self, DrawError> {
+        Self::allocate(context, bytes, bytes.len(), usage)
+    }
+}
+
+/// An attribute in a [`RawAttrStruct`][RawAttrStruct].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawAttribute {
+    /// The GLSL name of the attribute.
+    pub name: String,
+    /// The number of components, from 1 to 4.
+    pub num_comps: i32,
+    /// The component type, e.g. `WebGlRenderingContext::FLOAT`.
+    pub ty: u32,
+    /// Whether integer components are normalized to `[0, 1]` or `[-1, 1]`.
+    pub normalized: bool,
+    /// The offset of the attribute from the start of each vertex in bytes.
+    pub offset: i32,
+}
+
+/// The layout of interleaved vertices in a `Buffer<Raw>`, specified at runtime.
+///
+/// This is the runtime counterpart of the attribute structs generated by the
+/// [`Program`][crate::Program] macro,
+/// which allows drawing data from loaders such as glTF without defining a matching struct:
+/// ```ignore
+/// let buffer = Buffer::from_bytes(&context, &bytes, BufferDataUsage::StaticDraw)?;
+/// let layout = RawAttrStruct::new(32)
+///     .attribute("a_position", 3, WebGlRenderingContext::FLOAT, false, 0)
+///     .attribute("a_normal", 3, WebGlRenderingContext::FLOAT, false, 12)
+///     .attribute("a_uv", 2, WebGlRenderingContext::FLOAT, false, 24);
+///
+/// program.with_uniforms().transform(transform).apply_uniforms(&context)?;
+/// layout.bind(&context, &program.data, &buffer)?;
+/// indices.draw_bound(RenderPrimitiveType::Triangles, &context, ..)?;
+/// ```
+///
+/// # Alignment
+/// WebGL requires the offset of each attribute and the stride
+/// to be multiples of the size of the component type,
+/// e.g. 4 bytes for `FLOAT` and 2 bytes for `UNSIGNED_SHORT`.
+/// The stride must also not exceed 255 bytes.
+/// [`bind`][RawAttrStruct::bind] returns an error if the layout violates these requirements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawAttrStruct {
+    /// The size of each vertex in bytes.
+    pub stride: i32,
+    /// The attributes in each vertex.
+    pub attributes: Vec<RawAttribute>,
+}
+
+impl RawAttrStruct {
+    /// Creates a layout of vertices of `stride` bytes without attributes.
+    pub fn new(stride: i32) -> Self {
+        Self {
+            stride,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Adds an attribute to the layout.
+    pub fn attribute(
+        mut self,
+        name: impl Into<String>,
+        num_comps: i32,
+        ty: u32,
+        normalized: bool,
+        offset: i32,
+    ) -> Self {
+        self.attributes.push(RawAttribute {
+            name: name.into(),
+            num_comps,
+            ty,
+            normalized,
+            offset,
+        });
+        self
+    }
+
+    /// The number of complete vertices in `buffer` with this layout.
+    pub fn vertex_count<B: Backend>(&self, buffer: &Buffer<Raw, B>) -> usize {
+        match self.stride {
+            stride if stride > 0 => buffer.len() / stride as usize,
+            _ => 0,
+        }
+    }
+
+    /// Checks the alignment requirements of the layout.
+    pub fn validate(&self) -> Result<()> {
+        if !(0..=255).contains(&self.stride) {
+            bail!("The stride {} is not in the range 0..=255", self.stride);
+        }
+        for attr in &self.attributes {
+            if !(1..=4).contains(&attr.num_comps) {
+                bail!(
+                    "Attribute {} has {} components, but attributes have 1 to 4 components",
+                    attr.name,
+                    attr.num_comps
+                );
+            }
+            let size = component_size(attr.ty)?;
+            if attr.offset < 0 || attr.offset % size != 0 || self.stride % size != 0 {
+                bail!(
+                    "Attribute {} has the offset {} and the stride {}, \
+                    which must be multiples of its component size {}",
+                    attr.name,
+                    attr.offset,
+                    self.stride,
+                    size
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Binds the attributes of `program` to `buffer` with this layout.
+    ///
+    /// Attributes that are not active in the program are skipped.
+    /// After binding, draw with the attributes left bound,
+    /// e.g. with [`Indices::draw_bound`][crate::Indices::draw_bound].
+    pub fn bind(
+        &self,
+        context: &Context,
+        program: &ProgramData,
+        buffer: &Buffer<Raw>,
+    ) -> Result<()> {
+        self.validate()?;
+        for attr in &self.attributes {
+            let location = match context.get_attrib_location(&program.program, &attr.name) {
+                Some(location) => location,
+                None => {
+                    log::debug!(
+                        "Attribute \"{}\" is not active in the program and will not be bound",
+                        attr.name
+                    );
+                    continue;
+                }
+            };
+            context.enable_vertex_attrib_array(location);
+            buffer.bind_to_attr_raw(
+                context,
+                location,
+                attr.num_comps,
+                attr.ty,
+                attr.normalized,
+                self.stride,
+                attr.offset,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The size of a component of type `ty` in bytes.
+pub(crate) fn component_size(ty: u32) -> Result<i32> {
+    Ok(match ty {
+        WebGlRenderingContext::BYTE | WebGlRenderingContext::UNSIGNED_BYTE => 1,
+        WebGlRenderingContext::SHORT
+        | WebGlRenderingContext::UNSIGNED_SHORT
+        | WebGl2RenderingContext::HALF_FLOAT => 2,
+        WebGlRenderingContext::FLOAT
+        | WebGlRenderingContext::INT
+        | WebGlRenderingContext::UNSIGNED_INT => 4,
+        _ => bail!("Unsupported attribute component type 0x{:x}", ty),
+    })
+}
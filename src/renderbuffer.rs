@@ -0,0 +1,154 @@
+use anyhow::{Context as _, Result};
+use web_sys::{WebGl2RenderingContext, WebGlRenderbuffer, WebGlRenderingContext};
+
+use crate::Context;
+
+/// The internal format of a [`Renderbuffer`][Renderbuffer].
+///
+/// Corresponds to the [`internalFormat` parameter in `renderbufferStorage`][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/renderbufferStorage#parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderbufferFormat {
+    /// 4 bits for each of red, green, blue and alpha.
+    Rgba4,
+    /// 5 bits for red and blue, 6 bits for green.
+    Rgb565,
+    /// 5 bits for each of red, green and blue, 1 bit for alpha.
+    Rgb5A1,
+    /// 8 bits for each of red, green, blue and alpha.
+    /// This requires WebGL 2.
+    Rgba8,
+    /// A 16-bit depth buffer.
+    Depth16,
+    /// A 24-bit depth buffer.
+    /// This requires WebGL 2.
+    Depth24,
+    /// A 32-bit floating point depth buffer.
+    /// This requires WebGL 2.
+    Depth32F,
+    /// An 8-bit stencil buffer.
+    Stencil8,
+    /// A combined depth and stencil buffer,
+    /// with at least 16 bits of depth and 8 bits of stencil.
+    DepthStencil,
+    /// A combined buffer with 24 bits of depth and 8 bits of stencil.
+    /// This requires WebGL 2.
+    Depth24Stencil8,
+}
+
+impl RenderbufferFormat {
+    fn to_const(self) -> u32 {
+        match self {
+            Self::Rgba4 => WebGlRenderingContext::RGBA4,
+            Self::Rgb565 => WebGlRenderingContext::RGB565,
+            Self::Rgb5A1 => WebGlRenderingContext::RGB5_A1,
+            Self::Rgba8 => WebGl2RenderingContext::RGBA8,
+            Self::Depth16 => WebGlRenderingContext::DEPTH_COMPONENT16,
+            Self::Depth24 => WebGl2RenderingContext::DEPTH_COMPONENT24,
+            Self::Depth32F => WebGl2RenderingContext::DEPTH_COMPONENT32F,
+            Self::Stencil8 => WebGlRenderingContext::STENCIL_INDEX8,
+            Self::DepthStencil => WebGlRenderingContext::DEPTH_STENCIL,
+            Self::Depth24Stencil8 => WebGl2RenderingContext::DEPTH24_STENCIL8,
+        }
+    }
+
+    /// Whether the format is only supported in WebGL 2.
+    fn requires_webgl2(self) -> bool {
+        matches!(
+            self,
+            Self::Rgba8 | Self::Depth24 | Self::Depth32F | Self::Depth24Stencil8
+        )
+    }
+
+    /// The framebuffer attachment point for a renderbuffer of this format.
+    pub(crate) fn attachment(self) -> u32 {
+        match self {
+            Self::Rgba4 | Self::Rgb565 | Self::Rgb5A1 | Self::Rgba8 => {
+                WebGlRenderingContext::COLOR_ATTACHMENT0
+            }
+            Self::Depth16 | Self::Depth24 | Self::Depth32F => {
+                WebGlRenderingContext::DEPTH_ATTACHMENT
+            }
+            Self::Stencil8 => WebGlRenderingContext::STENCIL_ATTACHMENT,
+            Self::DepthStencil | Self::Depth24Stencil8 => {
+                WebGlRenderingContext::DEPTH_STENCIL_ATTACHMENT
+            }
+        }
+    }
+}
+
+/// Wraps a WebGL renderbuffer, an image that can be rendered to but not sampled.
+///
+/// Renderbuffers are usually used as the depth or stencil buffer of a
+/// [`Framebuffer`][crate::Framebuffer],
+/// attached with [`Framebuffer::attach_depth_stencil`][crate::Framebuffer::attach_depth_stencil].
+/// The renderbuffer is deleted when dropped.
+pub struct Renderbuffer {
+    /// The raw WebGlRenderbuffer object.
+    pub native: WebGlRenderbuffer,
+    gl: WebGlRenderingContext,
+    format: RenderbufferFormat,
+    width: u32,
+    height: u32,
+}
+
+impl Renderbuffer {
+    /// Allocates a renderbuffer of the given format and size.
+    ///
+    /// Returns an error if the format requires WebGL 2 in a WebGL 1 context,
+    /// or if the size exceeds `MAX_RENDERBUFFER_SIZE`.
+    pub fn new(
+        context: &Context,
+        format: RenderbufferFormat,
+        width: u32,
+        height: u32,
+    ) -> Result<Self> {
+        if format.requires_webgl2() {
+            context.require_webgl2(&format!("{:?} renderbuffers", format))?;
+        }
+        context.limits().check_renderbuffer_size(width, height)?;
+
+        let gl = &context.native;
+        let native = gl
+            .create_renderbuffer()
+            .context("Failed to allocate WebGL renderbuffer")?;
+        gl.bind_renderbuffer(WebGlRenderingContext::RENDERBUFFER, Some(&native));
+        gl.renderbuffer_storage(
+            WebGlRenderingContext::RENDERBUFFER,
+            format.to_const(),
+            width as i32,
+            height as i32,
+        );
+        gl.bind_renderbuffer(WebGlRenderingContext::RENDERBUFFER, None);
+
+        Ok(Self {
+            native,
+            gl: gl.clone(),
+            format,
+            width,
+            height,
+        })
+    }
+
+    /// The format of the renderbuffer.
+    pub fn format(&self) -> RenderbufferFormat {
+        self.format
+    }
+
+    /// The width of the renderbuffer in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the renderbuffer in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl Drop for Renderbuffer {
+    fn drop(&mut self) {
+        self.gl.delete_renderbuffer(Some(&self.native));
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{Context as _, Result};
+use web_sys::{
+    ExtTextureFilterAnisotropic, WebGl2RenderingContext, WebGlRenderingContext, WebGlSampler,
+};
+
+use crate::{Context, TextureOptions};
+
+/// A set of sampling parameters that overrides those of the texture bound to the same unit.
+///
+/// This allows sampling the same texture with different filtering or wrapping in different units,
+/// without changing the parameters of the texture itself.
+/// Bind it to a texture unit with [`Context::bind_sampler`][Context::bind_sampler].
+///
+/// Requires a WebGL 2 context.
+pub struct Sampler {
+    gl: WebGl2RenderingContext,
+    sampler: WebGlSampler,
+}
+
+impl Sampler {
+    /// Creates a sampler with the sampling parameters of `options`.
+    ///
+    /// Only `min_filter`, `mag_filter`, `wrap_s`, `wrap_t`, `wrap_r`,
+    /// `max_anisotropy` and `compare` are used;
+    /// the other options describe the texture storage and upload, which a sampler does not affect.
+    pub fn new(context: &Context, options: &TextureOptions) -> Result<Self> {
+        let gl = context.require_webgl2("Sampler")?.clone();
+        let sampler = gl.create_sampler().context("Failed to create sampler")?;
+
+        for &(param, value) in &[
+            (
+                WebGlRenderingContext::TEXTURE_MIN_FILTER,
+                options.min_filter.to_const(),
+            ),
+            (
+                WebGlRenderingContext::TEXTURE_MAG_FILTER,
+                options.mag_filter.to_const(),
+            ),
+            (
+                WebGlRenderingContext::TEXTURE_WRAP_S,
+                options.wrap_s.to_const(),
+            ),
+            (
+                WebGlRenderingContext::TEXTURE_WRAP_T,
+                options.wrap_t.to_const(),
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_R,
+                options.wrap_r.to_const(),
+            ),
+        ] {
+            gl.sampler_parameteri(&sampler, param, value as i32);
+        }
+
+        if let Some(anisotropy) = options
+            .max_anisotropy
+            .and_then(|a| crate::texture::clamp_anisotropy(context, a))
+        {
+            gl.sampler_parameterf(
+                &sampler,
+                ExtTextureFilterAnisotropic::TEXTURE_MAX_ANISOTROPY_EXT,
+                anisotropy,
+            );
+        }
+
+        if let Some(compare) = options.compare {
+            gl.sampler_parameteri(
+                &sampler,
+                WebGl2RenderingContext::TEXTURE_COMPARE_MODE,
+                WebGl2RenderingContext::COMPARE_REF_TO_TEXTURE as i32,
+            );
+            gl.sampler_parameteri(
+                &sampler,
+                WebGl2RenderingContext::TEXTURE_COMPARE_FUNC,
+                compare.to_const() as i32,
+            );
+        }
+
+        Ok(Self { gl, sampler })
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        self.gl.delete_sampler(Some(&self.sampler));
+    }
+}
+
+impl Context {
+    /// Binds `sampler` to the texture unit `unit`,
+    /// or restores the parameters of the bound texture if `sampler` is `None`.
+    ///
+    /// Unlike textures, samplers are bound to a unit directly,
+    /// so this does not change the [active texture unit][Context::active_texture].
+    /// Requires a WebGL 2 context.
+    pub fn bind_sampler(&self, unit: u32, sampler: Option<&Sampler>) -> Result<()> {
+        let gl = self.require_webgl2("Sampler")?;
+        gl.bind_sampler(unit, sampler.map(|sampler| &sampler.sampler));
+        Ok(())
+    }
+}
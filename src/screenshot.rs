@@ -0,0 +1,50 @@
+use anyhow::{anyhow, Context as _, Result};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{Blob, HtmlCanvasElement};
+
+use crate::Context;
+
+impl Context {
+    /// Encodes the current contents of the canvas as a `data:` URL of the image type `mime`,
+    /// e.g. `"image/png"` or `"image/jpeg"`.
+    ///
+    /// The drawing buffer is cleared after it is composited,
+    /// so unless the context was created with
+    /// [`ContextAttributes::preserve_drawing_buffer`][crate::ContextAttributes::preserve_drawing_buffer],
+    /// this must be called in the same frame as the draws to capture,
+    /// otherwise the captured image is blank.
+    /// Preserving the drawing buffer may reduce performance on some platforms.
+    ///
+    /// Browsers fall back to `image/png` if `mime` is not supported.
+    /// Returns an error if the context was created on an `OffscreenCanvas`
+    /// or if the canvas is tainted by cross-origin images.
+    pub fn to_data_url(&self, mime: &str) -> Result<String> {
+        self.screenshot_canvas()?
+            .to_data_url_with_type(mime)
+            .map_err(|err| anyhow!("Failed to encode the canvas: {:?}", err))
+    }
+
+    /// Encodes the current contents of the canvas as a PNG `Blob`
+    /// and passes it to `callback` once encoding has finished.
+    ///
+    /// The callback receives `None` if the canvas could not be encoded, e.g. if it is empty.
+    /// The contents are captured when this method is called,
+    /// so the same [`preserve_drawing_buffer`][crate::ContextAttributes::preserve_drawing_buffer]
+    /// requirement as [`to_data_url`][Context::to_data_url] applies.
+    ///
+    /// Returns an error if the context was created on an `OffscreenCanvas`
+    /// or if the canvas is tainted by cross-origin images.
+    pub fn to_blob(&self, callback: impl FnOnce(Option<Blob>) + 'static) -> Result<()> {
+        let canvas = self.screenshot_canvas()?;
+        let callback = Closure::once_into_js(callback);
+        canvas
+            .to_blob(callback.unchecked_ref())
+            .map_err(|err| anyhow!("Failed to encode the canvas: {:?}", err))
+    }
+
+    fn screenshot_canvas(&self) -> Result<&HtmlCanvasElement> {
+        self.canvas()
+            .context("Capturing the canvas requires a <canvas> element")
+    }
+}
@@ -0,0 +1,113 @@
+use wasm_bindgen::JsCast;
+use web_sys::{WebGlFramebuffer, WebGlRenderingContext};
+
+use crate::{Capability, Context};
+
+/// Restores the GL state captured by [`Context::push_state`][Context::push_state] when dropped.
+///
+/// The captured state consists of:
+/// - the blend capability, blend functions, blend equations and blend color
+/// - the depth test capability, depth function and depth write mask
+/// - the viewport
+/// - the bound framebuffer
+///
+/// Other state changed while the guard is alive is not restored.
+#[must_use = "The state is restored immediately if the guard is not stored"]
+pub struct StateGuard<'t> {
+    context: &'t Context,
+    blend: bool,
+    blend_funcs: [u32; 4],
+    blend_equations: [u32; 2],
+    blend_color: Option<Vec<f32>>,
+    depth_test: bool,
+    depth_func: u32,
+    depth_mask: bool,
+    viewport: Option<[i32; 4]>,
+    framebuffer: Option<WebGlFramebuffer>,
+}
+
+impl Context {
+    /// Captures the blend, depth, viewport and framebuffer state,
+    /// which is restored when the returned guard is dropped.
+    ///
+    /// This makes rendering passes self-contained:
+    /// ```ignore
+    /// {
+    ///     let _state = context.push_state();
+    ///     context.set_blend(Some(Blend::oit_accumulation()))?;
+    ///     // draw the pass...
+    /// } // blending is restored here
+    /// ```
+    ///
+    /// The state is queried with `getParameter`, which may stall the GPU pipeline,
+    /// so prefer restoring the state explicitly in hot paths.
+    pub fn push_state(&self) -> StateGuard<'_> {
+        let gl = &self.native;
+        let get_u32 = |param| {
+            gl.get_parameter(param)
+                .ok()
+                .and_then(|value| value.as_f64())
+                .map_or(0, |value| value as u32)
+        };
+
+        StateGuard {
+            context: self,
+            blend: self.is_enabled(Capability::Blend),
+            blend_funcs: [
+                get_u32(WebGlRenderingContext::BLEND_SRC_RGB),
+                get_u32(WebGlRenderingContext::BLEND_DST_RGB),
+                get_u32(WebGlRenderingContext::BLEND_SRC_ALPHA),
+                get_u32(WebGlRenderingContext::BLEND_DST_ALPHA),
+            ],
+            blend_equations: [
+                get_u32(WebGlRenderingContext::BLEND_EQUATION_RGB),
+                get_u32(WebGlRenderingContext::BLEND_EQUATION_ALPHA),
+            ],
+            blend_color: gl
+                .get_parameter(WebGlRenderingContext::BLEND_COLOR)
+                .ok()
+                .and_then(|value| value.dyn_into::<js_sys::Float32Array>().ok())
+                .map(|array| array.to_vec()),
+            depth_test: self.is_enabled(Capability::DepthTest),
+            depth_func: get_u32(WebGlRenderingContext::DEPTH_FUNC),
+            depth_mask: gl
+                .get_parameter(WebGlRenderingContext::DEPTH_WRITEMASK)
+                .ok()
+                .and_then(|value| value.as_bool())
+                .unwrap_or(true),
+            viewport: self.query_viewport(),
+            framebuffer: gl
+                .get_parameter(WebGlRenderingContext::FRAMEBUFFER_BINDING)
+                .ok()
+                .and_then(|value| value.dyn_into::<WebGlFramebuffer>().ok()),
+        }
+    }
+}
+
+impl<'t> Drop for StateGuard<'t> {
+    fn drop(&mut self) {
+        let gl = &self.context.native;
+
+        self.context.set_capability(Capability::Blend, self.blend);
+        let [src_rgb, dst_rgb, src_alpha, dst_alpha] = self.blend_funcs;
+        gl.blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+        let [equation_rgb, equation_alpha] = self.blend_equations;
+        gl.blend_equation_separate(equation_rgb, equation_alpha);
+        if let Some(&[r, g, b, a]) = self.blend_color.as_deref() {
+            gl.blend_color(r, g, b, a);
+        }
+
+        self.context
+            .set_capability(Capability::DepthTest, self.depth_test);
+        gl.depth_func(self.depth_func);
+        gl.depth_mask(self.depth_mask);
+
+        if let Some([x, y, width, height]) = self.viewport {
+            gl.viewport(x, y, width, height);
+        }
+        gl.bind_framebuffer(
+            WebGlRenderingContext::FRAMEBUFFER,
+            self.framebuffer.as_ref(),
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use crate::Context;
+
+/// Statistics of the draw calls issued by a context,
+/// returned by [`Context::frame_stats`][Context::frame_stats].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrawStats {
+    /// The number of vertices processed, counting each instance separately.
+    ///
+    /// For indexed draws, this is the number of indices.
+    pub vertices: u64,
+    /// The number of instances drawn, counting 1 for each non-instanced draw call.
+    pub instances: u64,
+    /// The number of `drawArrays` and `drawElements` calls, including instanced ones.
+    pub draw_calls: u32,
+}
+
+impl Context {
+    /// The draw statistics accumulated since the last call to
+    /// [`reset_frame_stats`][Context::reset_frame_stats],
+    /// or since the context was created.
+    ///
+    /// Call `reset_frame_stats` at the start of each frame
+    /// to display the draw call count of each frame.
+    pub fn frame_stats(&self) -> DrawStats {
+        self.stats.get()
+    }
+
+    /// Resets the statistics returned by [`frame_stats`][Context::frame_stats].
+    pub fn reset_frame_stats(&self) {
+        self.stats.set(DrawStats::default());
+    }
+
+    /// Adds a draw call of `count` vertices and `instances` instances to the statistics.
+    pub(crate) fn record_draw(&self, count: i32, instances: i32) {
+        let mut stats = self.stats.get();
+        stats.vertices += count.max(0) as u64 * instances.max(0) as u64;
+        stats.instances += instances.max(0) as u64;
+        stats.draw_calls += 1;
+        self.stats.set(stats);
+    }
+}
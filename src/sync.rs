@@ -0,0 +1,41 @@
+use anyhow::{Context as _, Result};
+use web_sys::{WebGl2RenderingContext, WebGlSync};
+
+use crate::Context;
+
+/// A fence that is signaled when the GPU has completed all commands issued before it.
+///
+/// This is useful for avoiding queuing unbounded frames,
+/// e.g. by skipping the next frame until the fence of the previous frame is signaled.
+///
+/// Requires a WebGL 2 context.
+pub struct Fence {
+    gl: WebGl2RenderingContext,
+    sync: WebGlSync,
+}
+
+impl Fence {
+    /// Inserts a fence after all commands issued so far.
+    pub fn new(context: &Context) -> Result<Self> {
+        let gl = context.require_webgl2("Fence")?.clone();
+        let sync = gl
+            .fence_sync(WebGl2RenderingContext::SYNC_GPU_COMMANDS_COMPLETE, 0)
+            .context("Failed to create fence")?;
+        Ok(Self { gl, sync })
+    }
+
+    /// Returns whether the GPU has completed all commands issued before this fence.
+    ///
+    /// This method never blocks.
+    pub fn poll(&self) -> bool {
+        let status = self.gl.client_wait_sync_with_u32(&self.sync, 0, 0);
+        status == WebGl2RenderingContext::ALREADY_SIGNALED
+            || status == WebGl2RenderingContext::CONDITION_SATISFIED
+    }
+}
+
+impl Drop for Fence {
+    fn drop(&mut self) {
+        self.gl.delete_sync(Some(&self.sync));
+    }
+}
@@ -0,0 +1,1079 @@
+use anyhow::{bail, Context as _, Result};
+use js_sys::{Float32Array, Object, Uint16Array, Uint32Array, Uint8Array};
+use wasm_bindgen::JsValue;
+use web_sys::{
+    ExtSRgb, ExtTextureFilterAnisotropic, HtmlCanvasElement, WebGl2RenderingContext,
+    WebGlRenderingContext, WebGlTexture, WebGlUniformLocation, WebglCompressedTextureAstc,
+    WebglCompressedTextureEtc, WebglCompressedTextureS3tc,
+};
+
+use crate::{Context, UniformType};
+
+/// Wraps a WebGL 2D texture.
+pub struct Texture {
+    /// The raw WebGlTexture object.
+    pub native: WebGlTexture,
+    gl: WebGlRenderingContext,
+    width: u32,
+    height: u32,
+}
+
+impl Texture {
+    /// Allocates a texture with the pixels in `data`.
+    ///
+    /// `data` contains the rows of pixels from bottom to top without padding,
+    /// and must contain exactly `width * height` pixels of the given format and type.
+    ///
+    /// WebGL assumes each row to be padded to a multiple of 4 bytes by default,
+    /// which misreads tightly packed rows, e.g. single-byte `Luminance` or `Alpha`
+    /// textures with a width not divisible by 4.
+    /// This method temporarily sets `UNPACK_ALIGNMENT` to match the row size,
+    /// so `data` never needs padding.
+    ///
+    /// Returns an error if `width` or `height` exceeds
+    /// [`GlLimits::max_texture_size`][crate::GlLimits::max_texture_size].
+    pub fn from_pixels(
+        context: &Context,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        ty: PixelType,
+        data: &[u8],
+        options: &TextureOptions,
+    ) -> Result<Self> {
+        let bytes_per_pixel = format.bytes_per_pixel(ty)?;
+        let expected = width as usize * height as usize * bytes_per_pixel;
+        if data.len() != expected {
+            bail!(
+                "Expected {} bytes of pixel data for a {}x{} texture, got {}",
+                expected,
+                width,
+                height,
+                data.len()
+            );
+        }
+
+        Self::allocate(context, width, height, format, ty, Some(data), options)
+    }
+
+    /// Allocates a `width` by `height` texture without uploading any pixels,
+    /// e.g. for use as a color attachment of a [`Framebuffer`][crate::Framebuffer]
+    /// that is filled by rendering into it.
+    ///
+    /// WebGL initializes the contents to zero,
+    /// so sampling the texture before writing to it reads transparent black.
+    ///
+    /// Returns an error if `width` or `height` exceeds
+    /// [`GlLimits::max_texture_size`][crate::GlLimits::max_texture_size],
+    /// or if the format and type are not supported by the context.
+    pub fn empty(
+        context: &Context,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        ty: PixelType,
+        options: &TextureOptions,
+    ) -> Result<Self> {
+        Self::allocate(context, width, height, format, ty, None, options)
+    }
+
+    fn allocate(
+        context: &Context,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        ty: PixelType,
+        data: Option<&[u8]>,
+        options: &TextureOptions,
+    ) -> Result<Self> {
+        let bytes_per_pixel = format.bytes_per_pixel(ty)?;
+        context.limits().check_texture_size(width, height)?;
+        if format.requires_webgl2() || ty == PixelType::HalfFloat {
+            context.require_webgl2(&format!("Pixel format {:?} with type {:?}", format, ty))?;
+        }
+
+        let gl = &context.native;
+        let native = gl
+            .create_texture()
+            .context("Failed to allocate WebGL texture")?;
+        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&native));
+
+        let (internal_format, format) = format_consts(context, format, ty, options.srgb)?;
+        let row_bytes = width as usize * bytes_per_pixel;
+        let view = data.map(|data| ty.array_view(data));
+        with_unpack_flags(gl, options, || {
+            with_unpack_alignment(gl, row_bytes, || {
+                tex_image_2d(
+                    gl,
+                    internal_format,
+                    width,
+                    height,
+                    format,
+                    ty,
+                    view.as_ref(),
+                )
+            })
+        })
+        .ok()
+        .context("Failed to allocate texture data")?;
+
+        let texture = Self {
+            native,
+            gl: context.native.clone(),
+            width,
+            height,
+        };
+        texture.apply_options(context, options)?;
+        Ok(texture)
+    }
+
+    /// Allocates an RGBA texture with the current contents of a 2D canvas,
+    /// e.g. text or patterns drawn with the `CanvasRenderingContext2D` API.
+    ///
+    /// The top row of the canvas is stored as the first row of the texture,
+    /// i.e. at texture coordinate `t = 0`,
+    /// unless [`TextureOptions::flip_y`][TextureOptions::flip_y] is set.
+    /// The canvas stores colors with premultiplied alpha,
+    /// but they are converted to straight alpha when uploaded
+    /// unless [`TextureOptions::premultiply_alpha`][TextureOptions::premultiply_alpha] is set.
+    ///
+    /// Later changes to the canvas are not reflected in the texture.
+    /// Returns an error if the canvas size exceeds
+    /// [`GlLimits::max_texture_size`][crate::GlLimits::max_texture_size].
+    pub fn from_canvas(
+        context: &Context,
+        canvas: &HtmlCanvasElement,
+        options: &TextureOptions,
+    ) -> Result<Self> {
+        let (width, height) = (canvas.width(), canvas.height());
+        context.limits().check_texture_size(width, height)?;
+
+        let gl = &context.native;
+        let native = gl
+            .create_texture()
+            .context("Failed to allocate WebGL texture")?;
+        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&native));
+
+        let (internal_format, format) = format_consts(
+            context,
+            PixelFormat::Rgba,
+            PixelType::UnsignedByte,
+            options.srgb,
+        )?;
+        with_unpack_flags(gl, options, || {
+            gl.tex_image_2d_with_u32_and_u32_and_canvas(
+                WebGlRenderingContext::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                format,
+                WebGlRenderingContext::UNSIGNED_BYTE,
+                canvas,
+            )
+        })
+        .ok()
+        .context("Failed to upload canvas to texture")?;
+
+        let texture = Self {
+            native,
+            gl: context.native.clone(),
+            width,
+            height,
+        };
+        texture.apply_options(context, options)?;
+        Ok(texture)
+    }
+
+    /// Sets the sampling parameters of the texture, which must be bound to `TEXTURE_2D`.
+    fn apply_options(&self, context: &Context, options: &TextureOptions) -> Result<()> {
+        set_params(context, WebGlRenderingContext::TEXTURE_2D, options)
+    }
+
+    /// Changes the sampling parameters of the texture,
+    /// e.g. to toggle between linear and nearest filtering when zooming into pixel art.
+    ///
+    /// This applies the filters, wrap modes, `max_anisotropy` and `compare` of `options`;
+    /// `srgb`, `flip_y` and `premultiply_alpha` only affect allocation and uploads, so they are ignored.
+    /// The texture is bound to the [active texture unit][Context::active_texture] and stays bound.
+    ///
+    /// Returns an error if `compare` is set in a WebGL 1 context.
+    pub fn set_params(&self, context: &Context, options: &TextureOptions) -> Result<()> {
+        self.bind(context);
+        self.apply_options(context, options)
+    }
+
+    /// Binds the texture to the texture unit `unit`,
+    /// so that it can be sampled by a [`Sampler2D`][crate::Sampler2D]
+    /// or [`Sampler2DShadow`][crate::Sampler2DShadow] uniform with the same unit.
+    pub fn bind_unit(&self, context: &Context, unit: u32) {
+        let gl = &context.native;
+        gl.active_texture(WebGlRenderingContext::TEXTURE0 + unit);
+        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&self.native));
+    }
+
+    /// Binds the texture to the active texture unit,
+    /// which is selected with [`Context::active_texture`][Context::active_texture].
+    pub fn bind(&self, context: &Context) {
+        context
+            .native
+            .bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&self.native));
+    }
+
+    /// Allocates a texture with compressed pixel data.
+    ///
+    /// `data` contains the blocks of pixels from bottom to top without padding,
+    /// and must contain exactly the number of blocks covering `width * height` pixels.
+    ///
+    /// Returns an error if the extension required by `format` is not supported.
+    pub fn from_compressed(
+        context: &Context,
+        format: CompressedFormat,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> Result<Self> {
+        let extension = format.extension();
+        if context.get_extension(extension).is_none() {
+            bail!(
+                "Compressed format {:?} requires the unsupported extension {}",
+                format,
+                extension
+            );
+        }
+
+        context.limits().check_texture_size(width, height)?;
+
+        let expected = format.data_len(width, height);
+        if data.len() != expected {
+            bail!(
+                "Expected {} bytes of {:?} data for a {}x{} texture, got {}",
+                expected,
+                format,
+                width,
+                height,
+                data.len()
+            );
+        }
+
+        let gl = &context.native;
+        let native = gl
+            .create_texture()
+            .context("Failed to allocate WebGL texture")?;
+        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&native));
+        gl.compressed_tex_image_2d_with_u8_array(
+            WebGlRenderingContext::TEXTURE_2D,
+            0,
+            format.to_const(),
+            width as i32,
+            height as i32,
+            0,
+            data,
+        );
+
+        let texture = Self {
+            native,
+            gl: context.native.clone(),
+            width,
+            height,
+        };
+        texture.apply_options(context, &TextureOptions::default())?;
+        Ok(texture)
+    }
+
+    /// Allocates a texture with RGBA pixels, one byte per component.
+    ///
+    /// This is a shorthand for [`from_pixels`][Texture::from_pixels]
+    /// with [`PixelFormat::Rgba`][PixelFormat::Rgba], [`PixelType::UnsignedByte`][PixelType::UnsignedByte]
+    /// and the default [`TextureOptions`][TextureOptions].
+    pub fn from_rgba(context: &Context, width: u32, height: u32, data: &[u8]) -> Result<Self> {
+        Self::from_pixels(
+            context,
+            width,
+            height,
+            PixelFormat::Rgba,
+            PixelType::UnsignedByte,
+            data,
+            &TextureOptions::default(),
+        )
+    }
+
+    /// Binds the texture to the texture unit `*unit` and assigns the unit to the sampler uniform,
+    /// then increments `*unit`.
+    /// Units in `fixed_units`, i.e. those assigned with `#[willow(sampler_unit = N)]`, are skipped.
+    ///
+    /// Used by the [`Program`][crate::Program] macro for texture uniforms.
+    #[doc(hidden)]
+    pub fn apply_sampler(
+        &self,
+        context: &Context,
+        location: &WebGlUniformLocation,
+        unit: &mut u32,
+        fixed_units: &[u32],
+    ) -> Result<()> {
+        while fixed_units.contains(unit) {
+            *unit += 1;
+        }
+        let max_units = context.limits().max_texture_image_units;
+        if *unit >= max_units {
+            bail!(
+                "Cannot bind more than {} textures in one draw (MAX_TEXTURE_IMAGE_UNITS)",
+                max_units
+            );
+        }
+
+        self.bind_unit(context, *unit);
+        UniformType::apply_uniform(*unit as i32, context, location);
+        *unit += 1;
+        Ok(())
+    }
+
+    /// The width of the texture in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the texture in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        self.gl.delete_texture(Some(&self.native));
+    }
+}
+
+/// Returns the `(internalformat, format)` pair of `format`,
+/// using the sRGB color space if `srgb` is set.
+pub(crate) fn format_consts(
+    context: &Context,
+    format: PixelFormat,
+    ty: PixelType,
+    srgb: bool,
+) -> Result<(u32, u32)> {
+    if !srgb {
+        return Ok(format.to_const());
+    }
+
+    if ty != PixelType::UnsignedByte || !matches!(format, PixelFormat::Rgb | PixelFormat::Rgba) {
+        bail!(
+            "sRGB textures require the Rgb or Rgba format with the UnsignedByte type, got {:?} with {:?}",
+            format,
+            ty
+        );
+    }
+    let rgba = format == PixelFormat::Rgba;
+    if context.webgl2().is_some() {
+        Ok(if rgba {
+            (
+                WebGl2RenderingContext::SRGB8_ALPHA8,
+                WebGlRenderingContext::RGBA,
+            )
+        } else {
+            (WebGl2RenderingContext::SRGB8, WebGlRenderingContext::RGB)
+        })
+    } else if context.get_extension("EXT_sRGB").is_some() {
+        Ok(if rgba {
+            (ExtSRgb::SRGB_ALPHA_EXT, ExtSRgb::SRGB_ALPHA_EXT)
+        } else {
+            (ExtSRgb::SRGB_EXT, ExtSRgb::SRGB_EXT)
+        })
+    } else {
+        bail!("sRGB textures require WebGL 2 or the EXT_sRGB extension")
+    }
+}
+
+/// Clamps `anisotropy` to the range supported by the device,
+/// or returns `None` if `EXT_texture_filter_anisotropic` is not supported.
+pub(crate) fn clamp_anisotropy(context: &Context, anisotropy: f32) -> Option<f32> {
+    if context
+        .get_extension("EXT_texture_filter_anisotropic")
+        .is_none()
+    {
+        log::debug!("EXT_texture_filter_anisotropic is not supported, ignoring max_anisotropy");
+        return None;
+    }
+    let max = context
+        .native
+        .get_parameter(ExtTextureFilterAnisotropic::MAX_TEXTURE_MAX_ANISOTROPY_EXT)
+        .ok()
+        .and_then(|value| value.as_f64())
+        .unwrap_or(1.) as f32;
+    Some(anisotropy.max(1.).min(max))
+}
+
+/// Sets the sampling parameters of the texture bound to `target`.
+pub(crate) fn set_params(context: &Context, target: u32, options: &TextureOptions) -> Result<()> {
+    let gl = &context.native;
+
+    for &(param, value) in &[
+        (
+            WebGlRenderingContext::TEXTURE_MIN_FILTER,
+            options.min_filter.to_const(),
+        ),
+        (
+            WebGlRenderingContext::TEXTURE_MAG_FILTER,
+            options.mag_filter.to_const(),
+        ),
+        (
+            WebGlRenderingContext::TEXTURE_WRAP_S,
+            options.wrap_s.to_const(),
+        ),
+        (
+            WebGlRenderingContext::TEXTURE_WRAP_T,
+            options.wrap_t.to_const(),
+        ),
+    ] {
+        gl.tex_parameteri(target, param, value as i32);
+    }
+    if target == WebGl2RenderingContext::TEXTURE_3D {
+        gl.tex_parameteri(
+            target,
+            WebGl2RenderingContext::TEXTURE_WRAP_R,
+            options.wrap_r.to_const() as i32,
+        );
+    }
+
+    if let Some(anisotropy) = options
+        .max_anisotropy
+        .and_then(|a| clamp_anisotropy(context, a))
+    {
+        gl.tex_parameterf(
+            target,
+            ExtTextureFilterAnisotropic::TEXTURE_MAX_ANISOTROPY_EXT,
+            anisotropy,
+        );
+    }
+
+    let gl2 = match options.compare {
+        Some(_) => Some(context.require_webgl2("Depth comparison")?),
+        None => context.webgl2(),
+    };
+    if let Some(gl2) = gl2 {
+        match options.compare {
+            Some(compare) => {
+                gl2.tex_parameteri(
+                    target,
+                    WebGl2RenderingContext::TEXTURE_COMPARE_MODE,
+                    WebGl2RenderingContext::COMPARE_REF_TO_TEXTURE as i32,
+                );
+                gl2.tex_parameteri(
+                    target,
+                    WebGl2RenderingContext::TEXTURE_COMPARE_FUNC,
+                    compare.to_const() as i32,
+                );
+            }
+            None => gl2.tex_parameteri(
+                target,
+                WebGl2RenderingContext::TEXTURE_COMPARE_MODE,
+                WebGlRenderingContext::NONE as i32,
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+impl Context {
+    /// Selects the texture unit that subsequent texture binds apply to,
+    /// e.g. with [`Texture::bind`][Texture::bind].
+    ///
+    /// Returns an error if `unit` is not less than
+    /// [`GlLimits::max_combined_texture_image_units`][crate::GlLimits::max_combined_texture_image_units].
+    ///
+    /// Texture uniforms assigned through the builder and [`Texture::bind_unit`][Texture::bind_unit]
+    /// also change the active texture unit.
+    pub fn active_texture(&self, unit: u32) -> Result<()> {
+        let max_units = self.limits().max_combined_texture_image_units;
+        if unit >= max_units {
+            bail!(
+                "Texture unit {} is out of range (MAX_COMBINED_TEXTURE_IMAGE_UNITS is {})",
+                unit,
+                max_units
+            );
+        }
+        self.native
+            .active_texture(WebGlRenderingContext::TEXTURE0 + unit);
+        Ok(())
+    }
+
+    /// The currently active texture unit, selected by [`active_texture`][Context::active_texture].
+    pub fn active_texture_unit(&self) -> u32 {
+        self.native
+            .get_parameter(WebGlRenderingContext::ACTIVE_TEXTURE)
+            .ok()
+            .and_then(|value| value.as_f64())
+            .map_or(0, |value| {
+                (value as u32).saturating_sub(WebGlRenderingContext::TEXTURE0)
+            })
+    }
+}
+
+/// Uploads `view` (or allocates uninitialized data if `None`)
+/// to level 0 of the texture bound to `TEXTURE_2D`.
+fn tex_image_2d(
+    gl: &WebGlRenderingContext,
+    internal_format: u32,
+    width: u32,
+    height: u32,
+    format: u32,
+    ty: PixelType,
+    view: Option<&Object>,
+) -> Result<(), JsValue> {
+    gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+        WebGlRenderingContext::TEXTURE_2D,
+        0,
+        internal_format as i32,
+        width as i32,
+        height as i32,
+        0,
+        format,
+        ty.to_const(),
+        view,
+    )
+}
+
+/// Runs `f` with `UNPACK_FLIP_Y_WEBGL` and `UNPACK_PREMULTIPLY_ALPHA_WEBGL`
+/// set according to `options`, then resets them to their default `false` values.
+fn with_unpack_flags<T>(
+    gl: &WebGlRenderingContext,
+    options: &TextureOptions,
+    f: impl FnOnce() -> T,
+) -> T {
+    gl.pixel_storei(
+        WebGlRenderingContext::UNPACK_FLIP_Y_WEBGL,
+        options.flip_y as i32,
+    );
+    gl.pixel_storei(
+        WebGlRenderingContext::UNPACK_PREMULTIPLY_ALPHA_WEBGL,
+        options.premultiply_alpha as i32,
+    );
+    let ret = f();
+    gl.pixel_storei(WebGlRenderingContext::UNPACK_FLIP_Y_WEBGL, 0);
+    gl.pixel_storei(WebGlRenderingContext::UNPACK_PREMULTIPLY_ALPHA_WEBGL, 0);
+    ret
+}
+
+/// Runs `f` with `UNPACK_ALIGNMENT` set to the largest alignment that divides `row_bytes`,
+/// then restores the previous alignment.
+pub(crate) fn with_unpack_alignment<T>(
+    gl: &WebGlRenderingContext,
+    row_bytes: usize,
+    f: impl FnOnce() -> T,
+) -> T {
+    with_alignment(gl, WebGlRenderingContext::UNPACK_ALIGNMENT, row_bytes, f)
+}
+
+/// Runs `f` with `PACK_ALIGNMENT` set to the largest alignment that divides `row_bytes`,
+/// then restores the previous alignment.
+pub(crate) fn with_pack_alignment<T>(
+    gl: &WebGlRenderingContext,
+    row_bytes: usize,
+    f: impl FnOnce() -> T,
+) -> T {
+    with_alignment(gl, WebGlRenderingContext::PACK_ALIGNMENT, row_bytes, f)
+}
+
+fn with_alignment<T>(
+    gl: &WebGlRenderingContext,
+    pname: u32,
+    row_bytes: usize,
+    f: impl FnOnce() -> T,
+) -> T {
+    let alignment = [8, 4, 2]
+        .iter()
+        .copied()
+        .find(|&alignment| row_bytes % alignment == 0)
+        .unwrap_or(1);
+    let previous = gl
+        .get_parameter(pname)
+        .ok()
+        .and_then(|value| value.as_f64())
+        .map_or(4, |value| value as i32);
+
+    gl.pixel_storei(pname, alignment as i32);
+    let ret = f();
+    gl.pixel_storei(pname, previous);
+    ret
+}
+
+/// Sampling parameters of a texture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureOptions {
+    /// The filter used when the texture is minified.
+    pub min_filter: TextureFilter,
+    /// The filter used when the texture is magnified.
+    pub mag_filter: TextureFilter,
+    /// The wrap mode for the horizontal texture coordinate.
+    pub wrap_s: TextureWrap,
+    /// The wrap mode for the vertical texture coordinate.
+    pub wrap_t: TextureWrap,
+    /// The wrap mode for the depth texture coordinate.
+    /// Only used by [`Texture3D`][crate::Texture3D].
+    pub wrap_r: TextureWrap,
+    /// The maximum anisotropy used when sampling the texture.
+    ///
+    /// The value is clamped to the maximum supported by the device.
+    /// Requires the `EXT_texture_filter_anisotropic` extension,
+    /// and is ignored if the extension is not supported.
+    pub max_anisotropy: Option<f32>,
+    /// The comparison function for sampling a depth texture with a
+    /// [`Sampler2DShadow`][crate::Sampler2DShadow] uniform, which requires WebGL 2.
+    ///
+    /// Sampling the texture returns the result of comparing the reference value
+    /// (the third texture coordinate) against the stored depth,
+    /// using this function with the reference value on the left side.
+    /// With linear filtering, the results of the nearest texels are interpolated.
+    ///
+    /// ```glsl
+    /// uniform sampler2DShadow u_shadow;
+    /// // ...
+    /// float lit = textureProj(u_shadow, v_light_position);
+    /// ```
+    pub compare: Option<CompareFunction>,
+    /// Whether the pixels are stored in the sRGB color space, like most images and colors.
+    ///
+    /// Shaders sampling the texture receive linear values converted automatically,
+    /// and rendering into the texture through a [`Framebuffer`][crate::Framebuffer]
+    /// converts the linear output to sRGB.
+    /// Without this option, sRGB data is treated as linear, which looks washed out after lighting.
+    ///
+    /// This only applies when the texture is allocated,
+    /// and requires the `Rgb` or `Rgba` format with the `UnsignedByte` type.
+    /// Requires WebGL 2 or the `EXT_sRGB` extension.
+    pub srgb: bool,
+    /// Whether the rows of the uploaded pixels are reversed,
+    /// so that the top row of a canvas is sampled at texture coordinate `t = 1`.
+    ///
+    /// Applies to [`Texture::from_pixels`][Texture::from_pixels]
+    /// and [`Texture::from_canvas`][Texture::from_canvas].
+    pub flip_y: bool,
+    /// Whether the color components of the uploaded pixels are multiplied by their alpha.
+    ///
+    /// Applies to [`Texture::from_pixels`][Texture::from_pixels]
+    /// and [`Texture::from_canvas`][Texture::from_canvas].
+    /// Blend such textures with `Blend::new(BlendFactor::One, BlendFactor::OneMinusSrcAlpha)`.
+    pub premultiply_alpha: bool,
+}
+
+impl Default for TextureOptions {
+    /// Linear filtering without mipmaps, clamped to the edge.
+    ///
+    /// Unlike the WebGL defaults, this works with non-power-of-two textures in WebGL 1.
+    fn default() -> Self {
+        Self {
+            min_filter: TextureFilter::Linear,
+            mag_filter: TextureFilter::Linear,
+            wrap_s: TextureWrap::ClampToEdge,
+            wrap_t: TextureWrap::ClampToEdge,
+            wrap_r: TextureWrap::ClampToEdge,
+            max_anisotropy: None,
+            compare: None,
+            srgb: false,
+            flip_y: false,
+            premultiply_alpha: false,
+        }
+    }
+}
+
+/// The filter used when sampling a texture.
+///
+/// Corresponds to the [`TEXTURE_MIN_FILTER`/`TEXTURE_MAG_FILTER` values in `texParameter`][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/texParameter#parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFilter {
+    /// Uses the nearest texel.
+    Nearest,
+    /// Interpolates the nearest texels linearly.
+    Linear,
+}
+
+impl TextureFilter {
+    pub(crate) fn to_const(self) -> u32 {
+        match self {
+            Self::Nearest => WebGlRenderingContext::NEAREST,
+            Self::Linear => WebGlRenderingContext::LINEAR,
+        }
+    }
+}
+
+/// A function comparing two depth values.
+///
+/// Corresponds to the [`TEXTURE_COMPARE_FUNC` values in `texParameter`][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/texParameter#parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareFunction {
+    /// Never passes.
+    Never,
+    /// Passes if the value is less than the other value.
+    Less,
+    /// Passes if the values are equal.
+    Equal,
+    /// Passes if the value is less than or equal to the other value.
+    LessOrEqual,
+    /// Passes if the value is greater than the other value.
+    Greater,
+    /// Passes if the values are not equal.
+    NotEqual,
+    /// Passes if the value is greater than or equal to the other value.
+    GreaterOrEqual,
+    /// Always passes.
+    Always,
+}
+
+impl CompareFunction {
+    pub(crate) fn to_const(self) -> u32 {
+        match self {
+            Self::Never => WebGlRenderingContext::NEVER,
+            Self::Less => WebGlRenderingContext::LESS,
+            Self::Equal => WebGlRenderingContext::EQUAL,
+            Self::LessOrEqual => WebGlRenderingContext::LEQUAL,
+            Self::Greater => WebGlRenderingContext::GREATER,
+            Self::NotEqual => WebGlRenderingContext::NOTEQUAL,
+            Self::GreaterOrEqual => WebGlRenderingContext::GEQUAL,
+            Self::Always => WebGlRenderingContext::ALWAYS,
+        }
+    }
+}
+
+/// The behavior when sampling a texture outside the range `[0, 1]`.
+///
+/// Corresponds to the [`TEXTURE_WRAP_S`/`TEXTURE_WRAP_T` values in `texParameter`][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/texParameter#parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureWrap {
+    /// Repeats the texture.
+    /// Requires power-of-two dimensions in WebGL 1.
+    Repeat,
+    /// Uses the texels at the edge of the texture.
+    ClampToEdge,
+    /// Repeats the texture, mirroring it on every repetition.
+    /// Requires power-of-two dimensions in WebGL 1.
+    MirroredRepeat,
+}
+
+impl TextureWrap {
+    pub(crate) fn to_const(self) -> u32 {
+        match self {
+            Self::Repeat => WebGlRenderingContext::REPEAT,
+            Self::ClampToEdge => WebGlRenderingContext::CLAMP_TO_EDGE,
+            Self::MirroredRepeat => WebGlRenderingContext::MIRRORED_REPEAT,
+        }
+    }
+}
+
+/// The format of the pixels in a texture.
+///
+/// Corresponds to the [`internalformat` and `format` parameters in `texImage2D`][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/texImage2D#parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Only the alpha component is stored.
+    Alpha,
+    /// The luminance component is stored, and is read as the R, G and B components.
+    Luminance,
+    /// The luminance and alpha components are stored.
+    LuminanceAlpha,
+    /// The R, G and B components are stored.
+    Rgb,
+    /// The R, G, B and A components are stored.
+    Rgba,
+    /// One 8-bit red component. Requires WebGL 2.
+    R8,
+    /// Two 8-bit red and green components. Requires WebGL 2.
+    Rg8,
+    /// One 16-bit float red component. Requires WebGL 2.
+    R16F,
+    /// One 32-bit float red component. Requires WebGL 2.
+    R32F,
+    /// Four 16-bit float components. Requires WebGL 2.
+    Rgba16F,
+    /// Four 32-bit float components. Requires WebGL 2.
+    Rgba32F,
+    /// A 16-bit depth component. Requires WebGL 2.
+    DepthComponent16,
+    /// A 24-bit depth component. Requires WebGL 2.
+    DepthComponent24,
+    /// A 32-bit float depth component. Requires WebGL 2.
+    DepthComponent32F,
+}
+
+impl PixelFormat {
+    /// Returns the `(internalformat, format)` pair.
+    pub(crate) fn to_const(self) -> (u32, u32) {
+        match self {
+            Self::Alpha => (WebGlRenderingContext::ALPHA, WebGlRenderingContext::ALPHA),
+            Self::Luminance => (
+                WebGlRenderingContext::LUMINANCE,
+                WebGlRenderingContext::LUMINANCE,
+            ),
+            Self::LuminanceAlpha => (
+                WebGlRenderingContext::LUMINANCE_ALPHA,
+                WebGlRenderingContext::LUMINANCE_ALPHA,
+            ),
+            Self::Rgb => (WebGlRenderingContext::RGB, WebGlRenderingContext::RGB),
+            Self::Rgba => (WebGlRenderingContext::RGBA, WebGlRenderingContext::RGBA),
+            Self::R8 => (WebGl2RenderingContext::R8, WebGl2RenderingContext::RED),
+            Self::Rg8 => (WebGl2RenderingContext::RG8, WebGl2RenderingContext::RG),
+            Self::R16F => (WebGl2RenderingContext::R16F, WebGl2RenderingContext::RED),
+            Self::R32F => (WebGl2RenderingContext::R32F, WebGl2RenderingContext::RED),
+            Self::Rgba16F => (WebGl2RenderingContext::RGBA16F, WebGlRenderingContext::RGBA),
+            Self::Rgba32F => (WebGl2RenderingContext::RGBA32F, WebGlRenderingContext::RGBA),
+            Self::DepthComponent16 => (
+                WebGl2RenderingContext::DEPTH_COMPONENT16,
+                WebGlRenderingContext::DEPTH_COMPONENT,
+            ),
+            Self::DepthComponent24 => (
+                WebGl2RenderingContext::DEPTH_COMPONENT24,
+                WebGlRenderingContext::DEPTH_COMPONENT,
+            ),
+            Self::DepthComponent32F => (
+                WebGl2RenderingContext::DEPTH_COMPONENT32F,
+                WebGlRenderingContext::DEPTH_COMPONENT,
+            ),
+        }
+    }
+
+    /// Whether the format is a sized internal format only available in WebGL 2.
+    pub fn requires_webgl2(self) -> bool {
+        !matches!(
+            self,
+            Self::Alpha | Self::Luminance | Self::LuminanceAlpha | Self::Rgb | Self::Rgba
+        )
+    }
+
+    /// The number of components per pixel.
+    pub fn num_comps(self) -> usize {
+        match self {
+            Self::Alpha
+            | Self::Luminance
+            | Self::R8
+            | Self::R16F
+            | Self::R32F
+            | Self::DepthComponent16
+            | Self::DepthComponent24
+            | Self::DepthComponent32F => 1,
+            Self::LuminanceAlpha | Self::Rg8 => 2,
+            Self::Rgb => 3,
+            Self::Rgba | Self::Rgba16F | Self::Rgba32F => 4,
+        }
+    }
+
+    /// The number of bytes per pixel when stored with the pixel type `ty`.
+    ///
+    /// Returns an error if the format cannot be used with `ty`.
+    pub fn bytes_per_pixel(self, ty: PixelType) -> Result<usize> {
+        let valid = match ty {
+            PixelType::UnsignedByte => !matches!(
+                self,
+                Self::R16F
+                    | Self::R32F
+                    | Self::Rgba16F
+                    | Self::Rgba32F
+                    | Self::DepthComponent16
+                    | Self::DepthComponent24
+                    | Self::DepthComponent32F
+            ),
+            PixelType::UnsignedShort => self == Self::DepthComponent16,
+            PixelType::UnsignedInt => {
+                matches!(self, Self::DepthComponent16 | Self::DepthComponent24)
+            }
+            PixelType::UnsignedShort565 => self == Self::Rgb,
+            PixelType::UnsignedShort4444 | PixelType::UnsignedShort5551 => self == Self::Rgba,
+            PixelType::HalfFloat => matches!(self, Self::R16F | Self::Rgba16F),
+            PixelType::Float => !matches!(
+                self,
+                Self::R8 | Self::Rg8 | Self::DepthComponent16 | Self::DepthComponent24
+            ),
+        };
+        if !valid {
+            bail!("Pixel format {:?} cannot be used with type {:?}", self, ty);
+        }
+
+        Ok(match ty {
+            PixelType::UnsignedShort565
+            | PixelType::UnsignedShort4444
+            | PixelType::UnsignedShort5551 => 2,
+            PixelType::UnsignedByte => self.num_comps(),
+            PixelType::UnsignedShort | PixelType::HalfFloat => self.num_comps() * 2,
+            PixelType::UnsignedInt => self.num_comps() * 4,
+            PixelType::Float => self.num_comps() * 4,
+        })
+    }
+}
+
+/// The data type of the pixels in a texture.
+///
+/// Corresponds to the [`type` parameter in `texImage2D`][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/texImage2D#parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelType {
+    /// One byte per component.
+    UnsignedByte,
+    /// Two bytes per component. Only used for depth formats.
+    UnsignedShort,
+    /// Four bytes per component. Only used for depth formats.
+    UnsignedInt,
+    /// 5 red bits, 6 green bits and 5 blue bits packed in two bytes.
+    UnsignedShort565,
+    /// 4 bits per RGBA component packed in two bytes.
+    UnsignedShort4444,
+    /// 5 bits per RGB component and 1 alpha bit packed in two bytes.
+    UnsignedShort5551,
+    /// A 16-bit float per component. Requires WebGL 2.
+    HalfFloat,
+    /// A 32-bit float per component.
+    /// Requires WebGL 2 or the `OES_texture_float` extension.
+    Float,
+}
+
+impl PixelType {
+    pub(crate) fn to_const(self) -> u32 {
+        match self {
+            Self::UnsignedByte => WebGlRenderingContext::UNSIGNED_BYTE,
+            Self::UnsignedShort => WebGlRenderingContext::UNSIGNED_SHORT,
+            Self::UnsignedInt => WebGlRenderingContext::UNSIGNED_INT,
+            Self::UnsignedShort565 => WebGlRenderingContext::UNSIGNED_SHORT_5_6_5,
+            Self::UnsignedShort4444 => WebGlRenderingContext::UNSIGNED_SHORT_4_4_4_4,
+            Self::UnsignedShort5551 => WebGlRenderingContext::UNSIGNED_SHORT_5_5_5_1,
+            Self::HalfFloat => WebGl2RenderingContext::HALF_FLOAT,
+            Self::Float => WebGlRenderingContext::FLOAT,
+        }
+    }
+
+    /// Copies `data` into a typed array matching this type,
+    /// since WebGL rejects array views of other types.
+    pub(crate) fn array_view(self, data: &[u8]) -> Object {
+        let bytes = Uint8Array::from(data);
+        match self {
+            Self::UnsignedByte => bytes.into(),
+            Self::UnsignedShort
+            | Self::UnsignedShort565
+            | Self::UnsignedShort4444
+            | Self::UnsignedShort5551
+            | Self::HalfFloat => Uint16Array::new_with_byte_offset_and_length(
+                &bytes.buffer(),
+                0,
+                data.len() as u32 / 2,
+            )
+            .into(),
+            Self::UnsignedInt => Uint32Array::new_with_byte_offset_and_length(
+                &bytes.buffer(),
+                0,
+                data.len() as u32 / 4,
+            )
+            .into(),
+            Self::Float => Float32Array::new_with_byte_offset_and_length(
+                &bytes.buffer(),
+                0,
+                data.len() as u32 / 4,
+            )
+            .into(),
+        }
+    }
+}
+
+/// The format of compressed pixel data.
+///
+/// Each format requires a [compressed texture extension][mdn].
+///
+/// [mdn]: https://developer.mozilla.org/en-US/docs/Web/API/WebGL_API/Compressed_texture_formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressedFormat {
+    /// S3TC DXT1 with RGB components.
+    /// Requires `WEBGL_compressed_texture_s3tc`.
+    RgbS3tcDxt1,
+    /// S3TC DXT1 with RGB components and a 1-bit alpha.
+    /// Requires `WEBGL_compressed_texture_s3tc`.
+    RgbaS3tcDxt1,
+    /// S3TC DXT3 with RGBA components.
+    /// Requires `WEBGL_compressed_texture_s3tc`.
+    RgbaS3tcDxt3,
+    /// S3TC DXT5 with RGBA components.
+    /// Requires `WEBGL_compressed_texture_s3tc`.
+    RgbaS3tcDxt5,
+    /// EAC with one red component.
+    /// Requires `WEBGL_compressed_texture_etc`.
+    R11Eac,
+    /// EAC with red and green components.
+    /// Requires `WEBGL_compressed_texture_etc`.
+    Rg11Eac,
+    /// ETC2 with RGB components.
+    /// Requires `WEBGL_compressed_texture_etc`.
+    Rgb8Etc2,
+    /// ETC2 with RGBA components.
+    /// Requires `WEBGL_compressed_texture_etc`.
+    Rgba8Etc2Eac,
+    /// ASTC with RGBA components in 4x4 blocks.
+    /// Requires `WEBGL_compressed_texture_astc`.
+    RgbaAstc4x4,
+    /// ASTC with RGBA components in 6x6 blocks.
+    /// Requires `WEBGL_compressed_texture_astc`.
+    RgbaAstc6x6,
+    /// ASTC with RGBA components in 8x8 blocks.
+    /// Requires `WEBGL_compressed_texture_astc`.
+    RgbaAstc8x8,
+}
+
+impl CompressedFormat {
+    fn to_const(self) -> u32 {
+        match self {
+            Self::RgbS3tcDxt1 => WebglCompressedTextureS3tc::COMPRESSED_RGB_S3TC_DXT1_EXT,
+            Self::RgbaS3tcDxt1 => WebglCompressedTextureS3tc::COMPRESSED_RGBA_S3TC_DXT1_EXT,
+            Self::RgbaS3tcDxt3 => WebglCompressedTextureS3tc::COMPRESSED_RGBA_S3TC_DXT3_EXT,
+            Self::RgbaS3tcDxt5 => WebglCompressedTextureS3tc::COMPRESSED_RGBA_S3TC_DXT5_EXT,
+            Self::R11Eac => WebglCompressedTextureEtc::COMPRESSED_R11_EAC,
+            Self::Rg11Eac => WebglCompressedTextureEtc::COMPRESSED_RG11_EAC,
+            Self::Rgb8Etc2 => WebglCompressedTextureEtc::COMPRESSED_RGB8_ETC2,
+            Self::Rgba8Etc2Eac => WebglCompressedTextureEtc::COMPRESSED_RGBA8_ETC2_EAC,
+            Self::RgbaAstc4x4 => WebglCompressedTextureAstc::COMPRESSED_RGBA_ASTC_4X4_KHR,
+            Self::RgbaAstc6x6 => WebglCompressedTextureAstc::COMPRESSED_RGBA_ASTC_6X6_KHR,
+            Self::RgbaAstc8x8 => WebglCompressedTextureAstc::COMPRESSED_RGBA_ASTC_8X8_KHR,
+        }
+    }
+
+    /// The name of the extension required by this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::RgbS3tcDxt1 | Self::RgbaS3tcDxt1 | Self::RgbaS3tcDxt3 | Self::RgbaS3tcDxt5 => {
+                "WEBGL_compressed_texture_s3tc"
+            }
+            Self::R11Eac | Self::Rg11Eac | Self::Rgb8Etc2 | Self::Rgba8Etc2Eac => {
+                "WEBGL_compressed_texture_etc"
+            }
+            Self::RgbaAstc4x4 | Self::RgbaAstc6x6 | Self::RgbaAstc8x8 => {
+                "WEBGL_compressed_texture_astc"
+            }
+        }
+    }
+
+    /// The `(width, height)` of each block in pixels.
+    pub fn block_size(self) -> (u32, u32) {
+        match self {
+            Self::RgbaAstc6x6 => (6, 6),
+            Self::RgbaAstc8x8 => (8, 8),
+            _ => (4, 4),
+        }
+    }
+
+    /// The number of bytes in each block.
+    pub fn block_bytes(self) -> usize {
+        match self {
+            Self::RgbS3tcDxt1 | Self::RgbaS3tcDxt1 | Self::R11Eac | Self::Rgb8Etc2 => 8,
+            _ => 16,
+        }
+    }
+
+    /// The number of bytes of data for a `width * height` texture.
+    pub fn data_len(self, width: u32, height: u32) -> usize {
+        let (block_width, block_height) = self.block_size();
+        let blocks_x = (width + block_width - 1) / block_width;
+        let blocks_y = (height + block_height - 1) / block_height;
+        blocks_x as usize * blocks_y as usize * self.block_bytes()
+    }
+}
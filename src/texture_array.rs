@@ -0,0 +1,259 @@
+use anyhow::{bail, Context as _, Result};
+use web_sys::{WebGl2RenderingContext, WebGlRenderingContext, WebGlTexture};
+
+use crate::texture::{format_consts, set_params, with_unpack_alignment};
+use crate::{Context, PixelFormat, PixelType, TextureOptions};
+
+/// Wraps a WebGL 2D array texture, sampled by a [`Sampler2DArray`][crate::Sampler2DArray] uniform.
+///
+/// Each layer is a separate 2D image of the same size and format,
+/// e.g. the tiles of an atlas, which avoids bleeding between neighbouring tiles.
+/// This requires WebGL 2.
+pub struct TextureArray {
+    /// The raw WebGlTexture object.
+    pub native: WebGlTexture,
+    gl: WebGlRenderingContext,
+    width: u32,
+    height: u32,
+    layers: u32,
+}
+
+impl TextureArray {
+    /// Allocates an array texture with one layer for each element of `layers`.
+    ///
+    /// Each layer contains the rows of pixels from bottom to top without padding,
+    /// and must contain exactly `width * height` pixels of the given format and type.
+    ///
+    /// Returns an error if the context is not WebGL 2,
+    /// or if there are more layers than `MAX_ARRAY_TEXTURE_LAYERS` (at least 256).
+    pub fn from_layers(
+        context: &Context,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        ty: PixelType,
+        layers: &[&[u8]],
+        options: &TextureOptions,
+    ) -> Result<Self> {
+        context.require_webgl2("Array textures")?;
+        context.limits().check_texture_size(width, height)?;
+
+        let max_layers = context.limits().max_array_texture_layers.unwrap_or(0);
+        if layers.len() > max_layers as usize {
+            bail!(
+                "Cannot allocate {} layers in an array texture (MAX_ARRAY_TEXTURE_LAYERS = {})",
+                layers.len(),
+                max_layers
+            );
+        }
+
+        let expected = width as usize * height as usize * format.bytes_per_pixel(ty)?;
+        for (index, layer) in layers.iter().enumerate() {
+            if layer.len() != expected {
+                bail!(
+                    "Expected {} bytes of pixel data for layer {} of a {}x{} texture, got {}",
+                    expected,
+                    index,
+                    width,
+                    height,
+                    layer.len()
+                );
+            }
+        }
+
+        let native = allocate(
+            context,
+            WebGl2RenderingContext::TEXTURE_2D_ARRAY,
+            (width, height, layers.len() as u32),
+            format,
+            ty,
+            &layers.concat(),
+            options,
+        )?;
+        Ok(Self {
+            native,
+            gl: context.native.clone(),
+            width,
+            height,
+            layers: layers.len() as u32,
+        })
+    }
+
+    /// Binds the texture to the texture unit `unit`,
+    /// so that it can be sampled by a [`Sampler2DArray`][crate::Sampler2DArray] uniform with the same unit.
+    pub fn bind_unit(&self, context: &Context, unit: u32) {
+        let gl = &context.native;
+        gl.active_texture(WebGlRenderingContext::TEXTURE0 + unit);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D_ARRAY, Some(&self.native));
+    }
+
+    /// The width of each layer in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of each layer in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of layers.
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+}
+
+impl Drop for TextureArray {
+    fn drop(&mut self) {
+        self.gl.delete_texture(Some(&self.native));
+    }
+}
+
+/// Wraps a WebGL 3D texture, sampled by a [`Sampler3D`][crate::Sampler3D] uniform.
+///
+/// This requires WebGL 2.
+pub struct Texture3D {
+    /// The raw WebGlTexture object.
+    pub native: WebGlTexture,
+    gl: WebGlRenderingContext,
+    width: u32,
+    height: u32,
+    depth: u32,
+}
+
+impl Texture3D {
+    /// Allocates a 3D texture with the pixels in `data`.
+    ///
+    /// `data` contains the slices from front to back,
+    /// each containing the rows of pixels from bottom to top without padding,
+    /// and must contain exactly `width * height * depth` pixels of the given format and type.
+    ///
+    /// Returns an error if the context is not WebGL 2,
+    /// or if a dimension exceeds `MAX_3D_TEXTURE_SIZE` (at least 256).
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_pixels(
+        context: &Context,
+        width: u32,
+        height: u32,
+        depth: u32,
+        format: PixelFormat,
+        ty: PixelType,
+        data: &[u8],
+        options: &TextureOptions,
+    ) -> Result<Self> {
+        context.require_webgl2("3D textures")?;
+
+        let max_size = context.limits().max_3d_texture_size.unwrap_or(0);
+        if width.max(height).max(depth) > max_size {
+            bail!(
+                "Cannot allocate a {}x{}x{} texture (MAX_3D_TEXTURE_SIZE = {})",
+                width,
+                height,
+                depth,
+                max_size
+            );
+        }
+
+        let expected =
+            width as usize * height as usize * depth as usize * format.bytes_per_pixel(ty)?;
+        if data.len() != expected {
+            bail!(
+                "Expected {} bytes of pixel data for a {}x{}x{} texture, got {}",
+                expected,
+                width,
+                height,
+                depth,
+                data.len()
+            );
+        }
+
+        let native = allocate(
+            context,
+            WebGl2RenderingContext::TEXTURE_3D,
+            (width, height, depth),
+            format,
+            ty,
+            data,
+            options,
+        )?;
+        Ok(Self {
+            native,
+            gl: context.native.clone(),
+            width,
+            height,
+            depth,
+        })
+    }
+
+    /// Binds the texture to the texture unit `unit`,
+    /// so that it can be sampled by a [`Sampler3D`][crate::Sampler3D] uniform with the same unit.
+    pub fn bind_unit(&self, context: &Context, unit: u32) {
+        let gl = &context.native;
+        gl.active_texture(WebGlRenderingContext::TEXTURE0 + unit);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_3D, Some(&self.native));
+    }
+
+    /// The width of the texture in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the texture in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The depth of the texture in pixels.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+}
+
+impl Drop for Texture3D {
+    fn drop(&mut self) {
+        self.gl.delete_texture(Some(&self.native));
+    }
+}
+
+/// Allocates a texture bound to `target` (`TEXTURE_2D_ARRAY` or `TEXTURE_3D`)
+/// with the validated pixels in `data`.
+fn allocate(
+    context: &Context,
+    target: u32,
+    (width, height, depth): (u32, u32, u32),
+    format: PixelFormat,
+    ty: PixelType,
+    data: &[u8],
+    options: &TextureOptions,
+) -> Result<WebGlTexture> {
+    let gl2 = context.require_webgl2("Array and 3D textures")?;
+    let bytes_per_pixel = format.bytes_per_pixel(ty)?;
+
+    let native = gl2
+        .create_texture()
+        .context("Failed to allocate WebGL texture")?;
+    gl2.bind_texture(target, Some(&native));
+
+    let (internal_format, format) = format_consts(context, format, ty, options.srgb)?;
+    let row_bytes = width as usize * bytes_per_pixel;
+    let view = ty.array_view(data);
+    with_unpack_alignment(&context.native, row_bytes, || {
+        gl2.tex_image_3d_with_opt_array_buffer_view(
+            target,
+            0,
+            internal_format as i32,
+            width as i32,
+            height as i32,
+            depth as i32,
+            0,
+            format,
+            ty.to_const(),
+            Some(&view),
+        )
+    })
+    .ok()
+    .context("Failed to allocate texture data")?;
+
+    set_params(context, target, options)?;
+    Ok(native)
+}
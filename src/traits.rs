@@ -1,41 +1,65 @@
-use crate::{AbstractIndices, Buffer, BufferDataUsage, Context, RenderPrimitiveType};
+use crate::{
+    AbstractIndices, Backend, Buffer, BufferDataUsage, Context, DrawError, Framebuffer,
+    RenderPrimitiveType, Result,
+};
 
 /// Represents WebGL programs.
 ///
-/// This type should only be implemented by the [`Program`][super::Program] macro.
+/// This type should only be implemented by the [`Program`][super::Program] macro,
+/// except for programs on a mock [`Backend`][Backend] used in tests.
 pub trait Program: Sized {
     /// The struct generated for storing attributes.
     /// Always `#[repr(C)]`.
     type AttrStruct: AttrStruct;
 
+    /// The backend that the program runs on.
+    /// This is [`Context`][crate::Context] for programs derived by the macro.
+    type Backend: Backend;
+
     /// Compiles and links the program in the given [`Context`](struct.Context.html).
-    fn create(context: &Context) -> Self {
-        let p = Self::create_internally(context);
-        p.compile_shaders(context);
-        p.link_shaders(context);
-        p
+    ///
+    /// Returns [`DrawError::ContextLost`][DrawError::ContextLost] if the context is lost,
+    /// or an error if the shader sources cannot be prepared,
+    /// e.g. if a source declares `#version` in addition to the `glsl_version` attribute.
+    fn create(context: &Self::Backend) -> Result<Self> {
+        let p = Self::create_internally(context)?;
+        p.compile_shaders(context)?;
+        p.link_shaders(context)?;
+        Ok(p)
     }
 
     /// Creates an instance of the type. Allocate necessary resources like `gl.createShader()`.
-    fn create_internally(gl: &Context) -> Self;
+    ///
+    /// Returns [`DrawError::ContextLost`][DrawError::ContextLost] if the context is lost.
+    fn create_internally(gl: &Self::Backend) -> Result<Self>;
 
     /// Compiles the vertex and fragment shaders.
-    fn compile_shaders(&self, gl: &Context);
+    fn compile_shaders(&self, gl: &Self::Backend) -> Result<()>;
 
     /// Attaches and links the vertex and fragment shaders.
-    fn link_shaders(&self, gl: &Context);
+    ///
+    /// Returns an error if a fixed sampler unit exceeds the limits of the context.
+    fn link_shaders(&self, gl: &Self::Backend) -> Result<()>;
 
     /// Prepares a buffer with the attributes in the vec.
+    ///
+    /// The per-vertex attributes are interleaved in a single buffer,
+    /// so `usage` applies to all of them.
+    /// Attributes marked `#[willow(instanced)]` are stored in a separate instance buffer
+    /// with its own usage, prepared with the derived `prepare_instance_buffer` method,
+    /// so per-instance data updated every frame does not affect the usage of static vertices.
+    ///
+    /// Returns an error in the same cases as [`Buffer::from_slice`][Buffer::from_slice].
     fn prepare_buffer(
-        context: &Context,
+        context: &Self::Backend,
         attrs: &[Self::AttrStruct],
         usage: BufferDataUsage,
-    ) -> Buffer<Self::AttrStruct> {
+    ) -> Result<Buffer<Self::AttrStruct, Self::Backend>, DrawError> {
         Buffer::from_slice(context, attrs, usage)
     }
 
     /// Calls the WebGL context to use the current program for draw calls.
-    fn use_program(&self, gl: &Context);
+    fn use_program(&self, gl: &Self::Backend);
 
     /// Runs the program with the given attributes indexed by `indices`.
     ///
@@ -47,17 +71,75 @@ pub trait Program: Sized {
     /// to draw with uniforms specified.
     fn draw(
         &self,
-        context: &Context,
+        context: &Self::Backend,
         mode: RenderPrimitiveType,
-        buffer: &Buffer<Self::AttrStruct>,
-        indices: impl AbstractIndices,
-    ) {
+        buffer: &Buffer<Self::AttrStruct, Self::Backend>,
+        indices: impl AbstractIndices<Self::Backend>,
+    ) -> Result<(), DrawError> {
         self.use_program(context);
-        indices.draw(mode, context, self, buffer);
+        indices.draw(mode, context, self, buffer)
     }
 
     /// Applies the buffer ot the attributes in this program.
-    fn apply_attrs(&self, context: &Context, buffer: &Buffer<Self::AttrStruct>);
+    fn apply_attrs(
+        &self,
+        context: &Self::Backend,
+        buffer: &Buffer<Self::AttrStruct, Self::Backend>,
+    );
+}
+
+/// The trait implemented by uniform builders with all uniforms set.
+///
+/// This trait is implemented by the builder types derived by the [`Program`][super::Program] macro,
+/// which are returned by the `with_uniforms` method after setting every uniform.
+pub trait DrawBuilder {
+    /// The program that this builder draws with.
+    type Program: Program;
+
+    /// Uses the program and assigns all uniforms without drawing.
+    fn apply_uniforms(&self, context: &<Self::Program as Program>::Backend) -> Result<()>;
+
+    /// Calls the program after setting all uniforms.
+    fn draw(
+        self,
+        context: &<Self::Program as Program>::Backend,
+        mode: RenderPrimitiveType,
+        buffer: &Buffer<
+            <Self::Program as Program>::AttrStruct,
+            <Self::Program as Program>::Backend,
+        >,
+        indices: &impl AbstractIndices<<Self::Program as Program>::Backend>,
+    ) -> Result<()>;
+
+    /// Skips uniforms without a location instead of returning an error when drawing,
+    /// e.g. uniforms optimized out by the GLSL compiler because they are unused.
+    ///
+    /// By default, drawing fails if a uniform has no location,
+    /// which usually indicates a typo in the GLSL name.
+    /// With this option, the skipped uniforms are logged at the debug level instead.
+    fn ignore_missing_uniforms(self) -> Self
+    where
+        Self: Sized;
+
+    /// Calls the program after setting all uniforms,
+    /// drawing into `framebuffer` instead of the canvas.
+    ///
+    /// The viewport is set to the size of the framebuffer during the draw.
+    /// The default framebuffer and the previous viewport are restored afterwards.
+    fn draw_to(
+        self,
+        framebuffer: &Framebuffer,
+        context: &Context,
+        mode: RenderPrimitiveType,
+        buffer: &Buffer<<Self::Program as Program>::AttrStruct>,
+        indices: &impl AbstractIndices,
+    ) -> Result<()>
+    where
+        Self: Sized,
+        Self::Program: Program<Backend = Context>,
+    {
+        framebuffer.with_bound(context, || self.draw(context, mode, buffer, indices))
+    }
 }
 
 /// The trait implemented by attribute structs.
@@ -79,6 +161,17 @@ pub trait AttrStruct {
     /// The number of components for the type in field `i`
     fn field_num_comps(i: usize) -> usize;
 
-    /// Whether the field `i` should be normalized
+    /// Whether the field `i` should be normalized,
+    /// i.e. whether the `normalized` parameter of `vertexAttribPointer` is set
+    /// when the field is bound by [`Buffer::bind_to_attr`][crate::Buffer::bind_to_attr].
+    ///
+    /// Normalized unsigned integers are mapped to `[0, 1]`,
+    /// and normalized signed integers are mapped to `[-1, 1]`.
     fn field_normalized(i: usize) -> bool;
+
+    /// The number of consecutive attribute locations occupied by field `i`,
+    /// which is greater than 1 for matrix attributes.
+    fn field_num_columns(_i: usize) -> usize {
+        1
+    }
 }
@@ -1,24 +1,224 @@
+use std::borrow::Cow;
+
 use cfg_if::cfg_if;
-use web_sys::{WebGlRenderingContext, WebGlUniformLocation};
+use web_sys::WebGlRenderingContext;
+
+use crate::Backend;
 
 /// Types that can be used as a uniform argument type.
+///
+/// All of `i8`, `i16`, `i32`, `u8`, `u16` and `u32` correspond to GLSL `int` uniforms.
 pub trait UniformType: Sized + Copy + 'static {
+    /// The components of `values` as uploaded to a GLSL uniform,
+    /// which is an array uniform if there are multiple values.
+    fn uniform_value(values: &[Self]) -> UniformValue<'_>;
+
+    /// Whether assigning this type requires a WebGL 2 context.
+    ///
+    /// The [`Program`][crate::Program] macro checks this when the program is created,
+    /// so that programs with unsupported uniforms fail to create instead of failing to draw.
+    fn requires_webgl2() -> bool {
+        false
+    }
+
     /// Applies the uniform value to the specified location.
-    fn apply_uniform(self, context: &WebGlRenderingContext, location: &WebGlUniformLocation);
+    fn apply_uniform<B: Backend>(self, context: &B, location: &B::UniformLocation) {
+        Self::apply_uniforms(std::slice::from_ref(&self), context, location);
+    }
+
+    /// Applies the values to consecutive elements of the array uniform,
+    /// starting from the element at `location`, in a single call.
+    fn apply_uniforms<B: Backend>(values: &[Self], context: &B, location: &B::UniformLocation) {
+        context.uniform(location, Self::uniform_value(values));
+    }
 }
 
+/// The components of a uniform value passed to [`Backend::uniform`][Backend::uniform].
+///
+/// The data may contain multiple values of the GLSL type to assign an array uniform.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UniformValue<'t> {
+    /// `int` or `ivec{size}` values, including sampler texture units.
+    Int {
+        /// The number of components of each value.
+        size: VectorSize,
+        /// The components of all values.
+        data: Cow<'t, [i32]>,
+    },
+    /// `float` or `vec{size}` values.
+    Float {
+        /// The number of components of each value.
+        size: VectorSize,
+        /// The components of all values.
+        data: Cow<'t, [f32]>,
+    },
+    /// `mat{columns}x{rows}` values in column-major order.
+    ///
+    /// Matrices that are not square require WebGL 2.
+    Matrix {
+        /// The GLSL matrix type of each value.
+        shape: MatrixShape,
+        /// The components of all matrices.
+        data: Cow<'t, [f32]>,
+    },
+}
+
+impl UniformValue<'_> {
+    /// Copies the borrowed components, e.g. to store the value.
+    pub fn into_owned(self) -> UniformValue<'static> {
+        match self {
+            Self::Int { size, data } => UniformValue::Int {
+                size,
+                data: Cow::Owned(data.into_owned()),
+            },
+            Self::Float { size, data } => UniformValue::Float {
+                size,
+                data: Cow::Owned(data.into_owned()),
+            },
+            Self::Matrix { shape, data } => UniformValue::Matrix {
+                shape,
+                data: Cow::Owned(data.into_owned()),
+            },
+        }
+    }
+}
+
+/// The number of components of an `int`, `ivec`, `float` or `vec` uniform value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorSize {
+    /// `int` or `float`
+    One = 1,
+    /// `ivec2` or `vec2`
+    Two = 2,
+    /// `ivec3` or `vec3`
+    Three = 3,
+    /// `ivec4` or `vec4`
+    Four = 4,
+}
+
+/// The GLSL type of a matrix uniform value, named `Mat{columns}x{rows}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixShape {
+    /// `mat2`
+    Mat2,
+    /// `mat3`
+    Mat3,
+    /// `mat4`
+    Mat4,
+    /// `mat2x3`, which requires WebGL 2.
+    Mat2x3,
+    /// `mat2x4`, which requires WebGL 2.
+    Mat2x4,
+    /// `mat3x2`, which requires WebGL 2.
+    Mat3x2,
+    /// `mat3x4`, which requires WebGL 2.
+    Mat3x4,
+    /// `mat4x2`, which requires WebGL 2.
+    Mat4x2,
+    /// `mat4x3`, which requires WebGL 2.
+    Mat4x3,
+}
+
+impl MatrixShape {
+    /// The number of columns, from 2 to 4.
+    pub fn columns(self) -> usize {
+        match self {
+            Self::Mat2 | Self::Mat2x3 | Self::Mat2x4 => 2,
+            Self::Mat3 | Self::Mat3x2 | Self::Mat3x4 => 3,
+            Self::Mat4 | Self::Mat4x2 | Self::Mat4x3 => 4,
+        }
+    }
+
+    /// The number of rows, from 2 to 4.
+    pub fn rows(self) -> usize {
+        match self {
+            Self::Mat2 | Self::Mat3x2 | Self::Mat4x2 => 2,
+            Self::Mat3 | Self::Mat2x3 | Self::Mat4x3 => 3,
+            Self::Mat4 | Self::Mat2x4 | Self::Mat3x4 => 4,
+        }
+    }
+
+    /// Whether the matrix is not square, which requires WebGL 2.
+    pub fn requires_webgl2(self) -> bool {
+        self.columns() != self.rows()
+    }
+}
+
+/// Implements [`UniformType`][UniformType] for fieldless enums,
+/// assigning the discriminant as an `int` uniform with `uniform1i`.
+///
+/// This allows shader "mode" uniforms to be typed as Rust enums:
+/// ```
+/// #[derive(Clone, Copy)]
+/// #[repr(i32)]
+/// enum Mode {
+///     Solid = 0,
+///     Wireframe = 1,
+///     Normals = 2,
+/// }
+///
+/// willow::uniform_enum!(Mode);
+/// ```
+/// A field of type `Uniform<Mode>` then corresponds to an `int` uniform in GLSL.
+/// The enum must implement `Copy`, and its discriminants must fit in an `i32`.
+#[macro_export]
+macro_rules! uniform_enum {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl $crate::UniformType for $ty {
+                fn uniform_value(values: &[Self]) -> $crate::UniformValue<'_> {
+                    $crate::UniformValue::Int {
+                        size: $crate::VectorSize::One,
+                        data: values.iter().map(|&value| value as i32).collect(),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+/// Implements `UniformType` for a type with `size` components of the `$kind` variant,
+/// copying the components listed by `$value`.
 macro_rules! impl_uniform {
-    ($ty:ty; $method:ident, |$x:ident| ($($value:expr),*)) => {
+    ($ty:ty; $kind:ident($size:ident), |$x:ident| [$($value:expr),*]) => {
         impl UniformType for $ty {
-            fn apply_uniform(self, context: &WebGlRenderingContext, location: &WebGlUniformLocation) {
-                let $x = self;
-                context.$method(Some(location), $($value),*);
+            fn uniform_value(values: &[Self]) -> UniformValue<'_> {
+                UniformValue::$kind {
+                    size: VectorSize::$size,
+                    data: values.iter().flat_map(|&$x| [$($value),*]).collect(),
+                }
             }
         }
     }
 }
 
+/// Implements `UniformType` for a GLSL matrix type of the shape `$shape`,
+/// copying the column-major components iterated by `$value`.
+///
+/// Programs with uniforms of matrices that are not square fail to create in a WebGL 1 context.
+#[cfg_attr(not(any(feature = "nalgebra", feature = "mint")), allow(unused_macros))]
+macro_rules! impl_uniform_matrix {
+    ($ty:ty; $shape:ident, |$x:ident| $value:expr) => {
+        impl UniformType for $ty {
+            fn requires_webgl2() -> bool {
+                MatrixShape::$shape.requires_webgl2()
+            }
+
+            fn uniform_value(values: &[Self]) -> UniformValue<'_> {
+                UniformValue::Matrix {
+                    shape: MatrixShape::$shape,
+                    data: values.iter().flat_map(|$x| $value).collect(),
+                }
+            }
+        }
+    };
+}
+
 /// Types that can be used as an attribute argument type.
+///
+/// Arrays such as `[f32; 3]` are supported with 1 to 4 elements;
+/// larger arrays fail to compile when used as an attribute,
+/// since WebGL attributes have at most 4 components.
 pub trait AttributeType: Sized + Copy + 'static {
     /// Number of components in the type.
     fn num_comps() -> usize;
@@ -26,17 +226,44 @@ pub trait AttributeType: Sized + Copy + 'static {
     /// Corresponding GLenum specifying the data type of each component in the array,
     /// e.g. `WebGlRenderingContext::UNSIGNED_SHORT`.
     fn gl_type() -> u32;
+
+    /// Whether the components are always normalized,
+    /// regardless of `#[willow(normalized)]` on the field.
+    fn normalized() -> bool {
+        false
+    }
+
+    /// The number of consecutive attribute locations occupied by the type,
+    /// e.g. 4 for a `mat4` attribute.
+    ///
+    /// Each location reads one column of [`num_comps`][AttributeType::num_comps] components.
+    fn num_columns() -> usize {
+        1
+    }
+}
+
+/// Fails to compile when `N` is not a valid number of attribute components.
+struct AssertComps<const N: usize>;
+
+impl<const N: usize> AssertComps<N> {
+    const VALID: () = assert!(
+        N >= 1 && N <= 4,
+        "Vertex attributes must have 1 to 4 components"
+    );
 }
 
 macro_rules! impl_attribute {
     ($ty:ty; $glty:ident) => {
+        impl_attribute!($ty; WebGlRenderingContext::$glty);
+    };
+    ($ty:ty; $glty:expr) => {
         impl AttributeType for $ty {
             fn num_comps() -> usize {
                 1
             }
 
             fn gl_type() -> u32 {
-                WebGlRenderingContext::$glty
+                $glty
             }
         }
 
@@ -46,7 +273,7 @@ macro_rules! impl_attribute {
             }
 
             fn gl_type() -> u32 {
-                WebGlRenderingContext::$glty
+                $glty
             }
         }
 
@@ -56,7 +283,7 @@ macro_rules! impl_attribute {
             }
 
             fn gl_type() -> u32 {
-                WebGlRenderingContext::$glty
+                $glty
             }
         }
 
@@ -66,7 +293,24 @@ macro_rules! impl_attribute {
             }
 
             fn gl_type() -> u32 {
-                WebGlRenderingContext::$glty
+                $glty
+            }
+        }
+
+        // `Default` is only implemented for arrays up to 32 elements,
+        // which is more than enough since attributes have at most 4 components.
+        impl<const N: usize> AttributeType for [$ty; N]
+        where
+            [$ty; N]: Default,
+        {
+            fn num_comps() -> usize {
+                #[allow(clippy::let_unit_value)]
+                let () = AssertComps::<N>::VALID;
+                N
+            }
+
+            fn gl_type() -> u32 {
+                $glty
             }
         }
 
@@ -77,7 +321,7 @@ macro_rules! impl_attribute {
             }
 
             fn gl_type() -> u32 {
-                WebGlRenderingContext::$glty
+                $glty
             }
         }
 
@@ -88,7 +332,7 @@ macro_rules! impl_attribute {
             }
 
             fn gl_type() -> u32 {
-                WebGlRenderingContext::$glty
+                $glty
             }
         }
 
@@ -99,7 +343,40 @@ macro_rules! impl_attribute {
             }
 
             fn gl_type() -> u32 {
-                WebGlRenderingContext::$glty
+                $glty
+            }
+        }
+
+        #[cfg(feature = "mint")]
+        impl AttributeType for mint::Vector2<$ty> {
+            fn num_comps() -> usize {
+                2
+            }
+
+            fn gl_type() -> u32 {
+                $glty
+            }
+        }
+
+        #[cfg(feature = "mint")]
+        impl AttributeType for mint::Vector3<$ty> {
+            fn num_comps() -> usize {
+                3
+            }
+
+            fn gl_type() -> u32 {
+                $glty
+            }
+        }
+
+        #[cfg(feature = "mint")]
+        impl AttributeType for mint::Vector4<$ty> {
+            fn num_comps() -> usize {
+                4
+            }
+
+            fn gl_type() -> u32 {
+                $glty
             }
         }
     };
@@ -111,31 +388,275 @@ impl_attribute!(u8; UNSIGNED_BYTE);
 impl_attribute!(u16; UNSIGNED_SHORT);
 impl_attribute!(f32; FLOAT);
 
-impl_uniform!(i32; uniform1i, |x| (x));
-impl_uniform!(f32; uniform1f, |x| (x));
+// Half-float vertex attributes are only supported in WebGL 2;
+// `OES_texture_half_float` in WebGL 1 only applies to textures.
+#[cfg(feature = "half")]
+impl_attribute!(half::f16; web_sys::WebGl2RenderingContext::HALF_FLOAT);
+
+/// An RGBA color packed into a `u32` with one byte per component,
+/// read by the shader as a `vec4` with components in `[0, 1]`.
+///
+/// The components are stored in memory in the order red, green, blue, alpha,
+/// i.e. the value is `0xAABBGGRR` on little-endian targets such as WebAssembly.
+/// Use [`from_rgba`][PackedColor::from_rgba] to construct a value independent of endianness.
+///
+/// The components are always normalized, so `#[willow(normalized)]` is not needed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct PackedColor(pub u32);
+
+impl PackedColor {
+    /// Packs the RGBA components into a color.
+    pub fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self(u32::from_ne_bytes([r, g, b, a]))
+    }
+}
+
+impl AttributeType for PackedColor {
+    fn num_comps() -> usize {
+        4
+    }
 
-impl_uniform!((i32, i32); uniform2i, |x| (x.0, x.1));
-impl_uniform!((f32, f32); uniform2f, |x| (x.0, x.1));
+    fn gl_type() -> u32 {
+        WebGlRenderingContext::UNSIGNED_BYTE
+    }
 
-impl_uniform!((i32, i32, i32); uniform3i, |x| (x.0, x.1, x.2));
-impl_uniform!((f32, f32, f32); uniform3f, |x| (x.0, x.1, x.2));
+    fn normalized() -> bool {
+        true
+    }
+}
 
-impl_uniform!((i32, i32, i32, i32); uniform4i, |x| (x.0, x.1, x.2, x.3));
-impl_uniform!((f32, f32, f32, f32); uniform4f, |x| (x.0, x.1, x.2, x.3));
+/// A `sampler2D` uniform, with the value being the texture unit to sample from.
+///
+/// Bind a texture to the unit with [`Texture::bind_unit`][crate::Texture::bind_unit].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sampler2D(pub u32);
+
+/// A `sampler2DShadow` uniform, with the value being the texture unit to sample from.
+///
+/// The texture bound to the unit must be a depth texture with
+/// [`TextureOptions::compare`][crate::TextureOptions::compare] set.
+/// This requires WebGL 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sampler2DShadow(pub u32);
+
+/// A `sampler2DArray` uniform, with the value being the texture unit to sample from.
+///
+/// Bind a texture to the unit with [`TextureArray::bind_unit`][crate::TextureArray::bind_unit].
+/// This requires WebGL 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sampler2DArray(pub u32);
+
+/// A `sampler3D` uniform, with the value being the texture unit to sample from.
+///
+/// Bind a texture to the unit with [`Texture3D::bind_unit`][crate::Texture3D::bind_unit].
+/// This requires WebGL 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sampler3D(pub u32);
+
+/// A `mat4` array uniform with a length only known at runtime,
+/// e.g. the bone matrices of a skeleton.
+///
+/// Wraps a slice of column-major 4x4 matrices, 16 floats each.
+/// A `Uniform<MatrixArray>` field is assigned with a `&MatrixArray` in the builder,
+/// which uploads all matrices in one `uniformMatrix4fv` call
+/// starting from the first element of the GLSL array.
+/// Matrices beyond the length of the GLSL array are ignored.
+#[derive(Debug, PartialEq)]
+#[repr(transparent)]
+pub struct MatrixArray([f32]);
+
+impl MatrixArray {
+    /// Wraps a slice of 4x4 matrices.
+    ///
+    /// Returns an error if the length of `data` is not a multiple of 16.
+    pub fn new(data: &[f32]) -> anyhow::Result<&Self> {
+        if data.len() % 16 != 0 {
+            anyhow::bail!("Matrix array length {} is not a multiple of 16", data.len());
+        }
+        // Safety: `MatrixArray` is a `repr(transparent)` wrapper of `[f32]`
+        Ok(unsafe { &*(data as *const [f32] as *const Self) })
+    }
+
+    /// The number of matrices.
+    pub fn len(&self) -> usize {
+        self.0.len() / 16
+    }
+
+    /// Whether there are no matrices.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The matrices as a flat slice of floats.
+    pub fn as_slice(&self) -> &[f32] {
+        &self.0
+    }
+
+    /// Assigns the matrices to the array uniform at `location`.
+    ///
+    /// Used by the [`Program`][crate::Program] macro for `MatrixArray` uniforms.
+    #[doc(hidden)]
+    pub fn apply_uniform<B: Backend>(&self, context: &B, location: &B::UniformLocation) {
+        context.uniform(
+            location,
+            UniformValue::Matrix {
+                shape: MatrixShape::Mat4,
+                data: Cow::Borrowed(&self.0),
+            },
+        );
+    }
+}
+
+impl_uniform!(Sampler2D; Int(One), |x| [x.0 as i32]);
+impl_uniform!(Sampler2DShadow; Int(One), |x| [x.0 as i32]);
+impl_uniform!(Sampler2DArray; Int(One), |x| [x.0 as i32]);
+impl_uniform!(Sampler3D; Int(One), |x| [x.0 as i32]);
+
+impl UniformType for i32 {
+    fn uniform_value(values: &[Self]) -> UniformValue<'_> {
+        UniformValue::Int {
+            size: VectorSize::One,
+            data: Cow::Borrowed(values),
+        }
+    }
+}
+
+// GLSL has no 8-bit or 16-bit integer types,
+// so smaller integers are widened and assigned to `int` uniforms.
+impl_uniform!(i8; Int(One), |x| [i32::from(x)]);
+impl_uniform!(i16; Int(One), |x| [i32::from(x)]);
+impl_uniform!(u8; Int(One), |x| [i32::from(x)]);
+impl_uniform!(u16; Int(One), |x| [i32::from(x)]);
+
+/// Assigned to an `int` uniform, since `uint` uniforms require WebGL 2.
+///
+/// Values above `i32::MAX` wrap around, which fails an assertion in debug builds.
+impl UniformType for u32 {
+    fn uniform_value(values: &[Self]) -> UniformValue<'_> {
+        UniformValue::Int {
+            size: VectorSize::One,
+            data: values
+                .iter()
+                .map(|&value| {
+                    debug_assert!(
+                        value <= i32::MAX as u32,
+                        "u32 uniform value {} does not fit in a GLSL int",
+                        value
+                    );
+                    value as i32
+                })
+                .collect(),
+        }
+    }
+}
+
+impl UniformType for f32 {
+    fn uniform_value(values: &[Self]) -> UniformValue<'_> {
+        UniformValue::Float {
+            size: VectorSize::One,
+            data: Cow::Borrowed(values),
+        }
+    }
+}
+
+impl_uniform!((i32, i32); Int(Two), |x| [x.0, x.1]);
+impl_uniform!((f32, f32); Float(Two), |x| [x.0, x.1]);
+
+impl_uniform!((i32, i32, i32); Int(Three), |x| [x.0, x.1, x.2]);
+impl_uniform!((f32, f32, f32); Float(Three), |x| [x.0, x.1, x.2]);
+
+impl_uniform!((i32, i32, i32, i32); Int(Four), |x| [x.0, x.1, x.2, x.3]);
+impl_uniform!((f32, f32, f32, f32); Float(Four), |x| [x.0, x.1, x.2, x.3]);
 
 cfg_if! {
     if #[cfg(feature = "nalgebra")] {
-        impl_uniform!(nalgebra::Vector2<i32>; uniform2i, |x| (x[0], x[1]));
-        impl_uniform!(nalgebra::Vector2<f32>; uniform2f, |x| (x[0], x[1]));
+        impl_uniform!(nalgebra::Vector2<i32>; Int(Two), |x| [x[0], x[1]]);
+        impl_uniform!(nalgebra::Vector2<f32>; Float(Two), |x| [x[0], x[1]]);
 
-        impl_uniform!(nalgebra::Vector3<i32>; uniform3i, |x| (x[0], x[1], x[2]));
-        impl_uniform!(nalgebra::Vector3<f32>; uniform3f, |x| (x[0], x[1], x[2]));
+        impl_uniform!(nalgebra::Vector3<i32>; Int(Three), |x| [x[0], x[1], x[2]]);
+        impl_uniform!(nalgebra::Vector3<f32>; Float(Three), |x| [x[0], x[1], x[2]]);
 
-        impl_uniform!(nalgebra::Vector4<i32>; uniform4i, |x| (x[0], x[1], x[2], x[3]));
-        impl_uniform!(nalgebra::Vector4<f32>; uniform4f, |x| (x[0], x[1], x[2], x[3]));
+        impl_uniform!(nalgebra::Vector4<i32>; Int(Four), |x| [x[0], x[1], x[2], x[3]]);
+        impl_uniform!(nalgebra::Vector4<f32>; Float(Four), |x| [x[0], x[1], x[2], x[3]]);
 
-        impl_uniform!(nalgebra::Matrix2<f32>; uniform_matrix2fv_with_f32_array, |x| (false, x.as_slice()));
-        impl_uniform!(nalgebra::Matrix3<f32>; uniform_matrix3fv_with_f32_array, |x| (false, x.as_slice()));
-        impl_uniform!(nalgebra::Matrix4<f32>; uniform_matrix4fv_with_f32_array, |x| (false, x.as_slice()));
+        impl_uniform_matrix!(nalgebra::Matrix2<f32>; Mat2, |x| x.iter().copied());
+        impl_uniform_matrix!(nalgebra::Matrix3<f32>; Mat3, |x| x.iter().copied());
+        impl_uniform_matrix!(nalgebra::Matrix4<f32>; Mat4, |x| x.iter().copied());
+
+        // GLSL `matCxR` has C columns and R rows, while nalgebra `MatrixRxC` has R rows and C columns,
+        // so e.g. `Matrix2x3` (2 rows, 3 columns) corresponds to `mat3x2`.
+        impl_uniform_matrix!(nalgebra::Matrix2x3<f32>; Mat3x2, |x| x.iter().copied());
+        impl_uniform_matrix!(nalgebra::Matrix3x2<f32>; Mat2x3, |x| x.iter().copied());
+        impl_uniform_matrix!(nalgebra::Matrix2x4<f32>; Mat4x2, |x| x.iter().copied());
+        impl_uniform_matrix!(nalgebra::Matrix4x2<f32>; Mat2x4, |x| x.iter().copied());
+        impl_uniform_matrix!(nalgebra::Matrix3x4<f32>; Mat4x3, |x| x.iter().copied());
+        impl_uniform_matrix!(nalgebra::Matrix4x3<f32>; Mat3x4, |x| x.iter().copied());
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "mint")] {
+        impl_uniform!(mint::Vector2<i32>; Int(Two), |x| [x.x, x.y]);
+        impl_uniform!(mint::Vector2<f32>; Float(Two), |x| [x.x, x.y]);
+
+        impl_uniform!(mint::Vector3<i32>; Int(Three), |x| [x.x, x.y, x.z]);
+        impl_uniform!(mint::Vector3<f32>; Float(Three), |x| [x.x, x.y, x.z]);
+
+        impl_uniform!(mint::Vector4<i32>; Int(Four), |x| [x.x, x.y, x.z, x.w]);
+        impl_uniform!(mint::Vector4<f32>; Float(Four), |x| [x.x, x.y, x.z, x.w]);
+
+        impl_uniform_matrix!(mint::ColumnMatrix2<f32>; Mat2, |x| <[f32; 4]>::from(*x));
+        impl_uniform_matrix!(mint::ColumnMatrix3<f32>; Mat3, |x| <[f32; 9]>::from(*x));
+        impl_uniform_matrix!(mint::ColumnMatrix4<f32>; Mat4, |x| <[f32; 16]>::from(*x));
+
+        // WebGL does not support transposing matrix uniforms, so row matrices are transposed on the CPU.
+        impl_uniform_matrix!(mint::RowMatrix2<f32>; Mat2, |x| <[f32; 4]>::from(mint::ColumnMatrix2::from(*x)));
+        impl_uniform_matrix!(mint::RowMatrix3<f32>; Mat3, |x| <[f32; 9]>::from(mint::ColumnMatrix3::from(*x)));
+        impl_uniform_matrix!(mint::RowMatrix4<f32>; Mat4, |x| <[f32; 16]>::from(mint::ColumnMatrix4::from(*x)));
+
+        // A `matN` attribute occupies N consecutive locations, one for each column.
+        // Row matrices have no attribute impls since their columns are not contiguous.
+        impl AttributeType for mint::ColumnMatrix2<f32> {
+            fn num_comps() -> usize {
+                2
+            }
+
+            fn gl_type() -> u32 {
+                WebGlRenderingContext::FLOAT
+            }
+
+            fn num_columns() -> usize {
+                2
+            }
+        }
+
+        impl AttributeType for mint::ColumnMatrix3<f32> {
+            fn num_comps() -> usize {
+                3
+            }
+
+            fn gl_type() -> u32 {
+                WebGlRenderingContext::FLOAT
+            }
+
+            fn num_columns() -> usize {
+                3
+            }
+        }
+
+        impl AttributeType for mint::ColumnMatrix4<f32> {
+            fn num_comps() -> usize {
+                4
+            }
+
+            fn gl_type() -> u32 {
+                WebGlRenderingContext::FLOAT
+            }
+
+            fn num_columns() -> usize {
+                4
+            }
+        }
     }
 }